@@ -18,7 +18,13 @@ use std::{env, fs::File, io::Read, process::Command, str::FromStr, time::Duratio
 use tempfile::TempDir;
 use tokio::time::timeout;
 use utoipa::ToSchema;
-use zkcir::ir::Cir;
+use zkcir::{
+    analysis::{
+        lint::{lint, Finding},
+        rules::{lint_with_rules, RuleSet, RULES_FILE_NAME},
+    },
+    ir::Cir,
+};
 
 use crate::{
     app_error::AppError,
@@ -136,6 +142,30 @@ pub async fn compile_to_ir(
                         ))
                         .build()?,
                 )
+                .objects(
+                    ObjectIdentifier::builder()
+                        .key(format!(
+                            "{}/{repo_name}/{commit_id}/fingerprint.txt",
+                            user_data.claims.sub
+                        ))
+                        .build()?,
+                )
+                .objects(
+                    ObjectIdentifier::builder()
+                        .key(format!(
+                            "{}/{repo_name}/{commit_id}/findings.json",
+                            user_data.claims.sub
+                        ))
+                        .build()?,
+                )
+                .objects(
+                    ObjectIdentifier::builder()
+                        .key(format!(
+                            "{}/{repo_name}/{commit_id}/rules_error.txt",
+                            user_data.claims.sub
+                        ))
+                        .build()?,
+                )
                 .objects(
                     ObjectIdentifier::builder()
                         .key(format!(
@@ -257,6 +287,8 @@ async fn compile_and_upload(
 
     let mut parsed_cargo = get_parsed_cargo(&unzipped_dir_path.join("Cargo.toml"))?;
 
+    let rules = read_rules(&unzipped_dir_path.join(RULES_FILE_NAME));
+
     let dependencies = parsed_cargo
         .get("dependencies")
         .ok_or("No dependencies found in `Cargo.toml`")?;
@@ -406,6 +438,14 @@ async fn compile_and_upload(
         Cir::from_json(&json_ir_string).map_err(|e| format!("Failed to parse json CIR: {e}"))?;
 
     let source_ir_string = cir.to_code_ir();
+    let fingerprint = cir.fingerprint()?;
+    // An invalid rule file shouldn't discard a successful compile, so fall back to the built-in lints and report it
+    let (findings, rules_error) = match rules.and_then(|rules| {
+        lint_with_rules(&cir, &rules).map_err(|e| format!("Invalid `{RULES_FILE_NAME}`: {e}"))
+    }) {
+        Ok(findings) => (findings, None),
+        Err(e) => (lint(&cir), Some(e)),
+    };
 
     app_state
         .s3_client
@@ -429,6 +469,46 @@ async fn compile_and_upload(
         .await
         .map_err(|e| format!("Failed to upload to S3: {e}"))?;
 
+    // Lets clients detect commits that produce the same circuit without downloading the IR
+    app_state
+        .s3_client
+        .put_object()
+        .bucket(circuits_bucket_name)
+        .key(format!("{owner}/{repo_name}/{commit_id}/fingerprint.txt"))
+        .body(ByteStream::from(fingerprint.into_bytes()))
+        .content_type("text/plain")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload to S3: {e}"))?;
+
+    app_state
+        .s3_client
+        .put_object()
+        .bucket(circuits_bucket_name)
+        .key(format!("{owner}/{repo_name}/{commit_id}/findings.json"))
+        .body(ByteStream::from(
+            serde_json::to_string(&findings)
+                .map_err(|e| format!("Failed to serialize findings: {e}"))?
+                .into_bytes(),
+        ))
+        .content_type("application/json")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload to S3: {e}"))?;
+
+    if let Some(rules_error) = rules_error {
+        app_state
+            .s3_client
+            .put_object()
+            .bucket(circuits_bucket_name)
+            .key(format!("{owner}/{repo_name}/{commit_id}/rules_error.txt"))
+            .body(ByteStream::from(rules_error.into_bytes()))
+            .content_type("text/plain")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload to S3: {e}"))?;
+    }
+
     app_state
         .s3_client
         .delete_object()
@@ -453,6 +533,18 @@ async fn compile_and_upload(
     Ok(())
 }
 
+/// Rules of the repository's rule file, or none if it doesn't have one
+fn read_rules(path: &std::path::Path) -> Result<RuleSet, String> {
+    if !path.is_file() {
+        return Ok(RuleSet::default());
+    }
+
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read `{RULES_FILE_NAME}`: {e}"))?;
+
+    toml::from_str(&contents).map_err(|e| format!("Failed to parse `{RULES_FILE_NAME}`: {e}"))
+}
+
 #[derive(Serialize, ToSchema, Clone)]
 pub struct GetIrResponse {
     /// IR as JSON if compiled
@@ -461,6 +553,16 @@ pub struct GetIrResponse {
     /// IR as CIR if compiled
     cir: Option<String>,
 
+    /// Digest of the canonicalized IR if compiled, equal for commits producing the same circuit
+    fingerprint: Option<String>,
+
+    /// Built-in lint findings and those of the repository's `zkcir.toml` if compiled
+    #[schema(value_type = Option<Vec<Object>>)]
+    findings: Option<Vec<Finding>>,
+
+    /// Why the repository's `zkcir.toml` couldn't be used, in which case `findings` only has the built-in lints
+    rules_error: Option<String>,
+
     /// StatusCode
     status: CompilationProgress,
 }
@@ -551,6 +653,61 @@ pub async fn get_ir(
             )
         })?;
 
+        // Computed from the json for IRs stored before fingerprints were
+        let fingerprint = match app_state
+            .s3_client
+            .get_object()
+            .bucket(circuits_bucket_name)
+            .key(format!("{owner}/{repo_name}/{commit_id}/fingerprint.txt"))
+            .send()
+            .await
+        {
+            Ok(fingerprint_res) => fingerprint_res
+                .body
+                .collect()
+                .await
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok()),
+            Err(_) => Cir::from_json(&ir_json_string)
+                .ok()
+                .and_then(|cir| cir.fingerprint().ok()),
+        };
+
+        // Missing for IRs stored before findings were, and can't be recomputed since the rule file isn't kept
+        let findings = match app_state
+            .s3_client
+            .get_object()
+            .bucket(circuits_bucket_name)
+            .key(format!("{owner}/{repo_name}/{commit_id}/findings.json"))
+            .send()
+            .await
+        {
+            Ok(findings_res) => findings_res
+                .body
+                .collect()
+                .await
+                .ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes.into_bytes()).ok()),
+            Err(_) => None,
+        };
+
+        let rules_error = match app_state
+            .s3_client
+            .get_object()
+            .bucket(circuits_bucket_name)
+            .key(format!("{owner}/{repo_name}/{commit_id}/rules_error.txt"))
+            .send()
+            .await
+        {
+            Ok(rules_error_res) => rules_error_res
+                .body
+                .collect()
+                .await
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok()),
+            Err(_) => None,
+        };
+
         return Response::builder()
             .status(StatusCode::OK)
             .header("Content-Type", "application/json")
@@ -558,6 +715,9 @@ pub async fn get_ir(
                 serde_json::to_string(&GetIrResponse {
                     json: Some(ir_json_string),
                     cir: Some(ir_cir_string),
+                    fingerprint,
+                    findings,
+                    rules_error,
                     status: CompilationProgress::Completed,
                 })
                 .map_err(AppError::from)?,
@@ -595,6 +755,9 @@ pub async fn get_ir(
                 serde_json::to_string(&GetIrResponse {
                     json: None,
                     cir: None,
+                    fingerprint: None,
+                    findings: None,
+                    rules_error: None,
                     status: status_string.parse().map_err(|()| {
                         AppError::new(
                             StatusCode::INTERNAL_SERVER_ERROR,
@@ -614,6 +777,9 @@ pub async fn get_ir(
             serde_json::to_string(&GetIrResponse {
                 json: None,
                 cir: None,
+                fingerprint: None,
+                findings: None,
+                rules_error: None,
                 status: CompilationProgress::NotStarted,
             })
             .map_err(AppError::from)?,
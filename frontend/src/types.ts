@@ -13,9 +13,20 @@ export type GetRepoMetadataResponse = {
   latest_commit_id?: string;
 };
 
+export type Finding = {
+  rule: string;
+  severity: "Info" | "Warning" | "Error";
+  stmt_index: number;
+  message: string;
+  explanation: string;
+};
+
 export type GetIrResponse = {
   json?: string;
   cir?: string;
+  fingerprint?: string;
+  findings?: Finding[];
+  rules_error?: string;
   status: GetIrStatusResponse;
 };
 
@@ -1,8 +1,13 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// emits ir as json; this and/or `source` must be enabled
     #[arg(long)]
     pub json: bool,
@@ -11,6 +16,10 @@ pub struct Args {
     #[arg(long)]
     pub source: bool,
 
+    /// annotates `source` output with value ranges inferred for wires and locals
+    #[arg(long, requires = "source")]
+    pub ranges: bool,
+
     /// replaces output file(s) when they already exist
     #[arg(long)]
     pub allow_dirty: bool,
@@ -23,3 +32,244 @@ pub struct Args {
     #[arg(last = true)]
     pub cargo_args: Vec<String>,
 }
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// reports polynomial degree and constraint cost of an emitted json ir
+    Cost(CostArgs),
+
+    /// prints the statements that constrain (or with `--forward`, depend on) a wire, local or statement as `.cir`
+    Slice(SliceArgs),
+
+    /// checks whether two emitted json irs accept the same witnesses
+    Equiv(EquivArgs),
+
+    /// lowers an emitted json ir to r1cs, writing the iden3 binary `.r1cs` format and a json variant
+    R1cs(R1csArgs),
+
+    /// imports a circuit compiled by circom into `zkcir_out` as json and source ir
+    Circom(CircomArgs),
+
+    /// generates rust source building an ir with plonky2's `CircuitBuilder`
+    Plonky2(Plonky2Args),
+
+    /// generates a standalone rust function computing every local of an ir from its inputs
+    Witness(WitnessArgs),
+
+    /// prints a digest of each ir that is equal for circuits differing only in local names, random values, operand
+    /// order of commutative operations and annotations
+    Fingerprint(FingerprintArgs),
+
+    /// prints statements matching a pattern like `verify!($x * ($x - 1) == 0)`, with their indices and positions
+    Query(QueryArgs),
+
+    /// reports built-in lints and the rules of the nearest `zkcir.toml`, failing if any finding is an error
+    Lint(LintArgs),
+
+    /// browses the statements of an ir interactively, with search, wire filters and lint findings
+    Tui(TuiArgs),
+
+    /// shrinks an ir to the fewest statements and simplest expressions for which a command still exits with the same
+    /// code, writing the result as json and source ir
+    Reduce(ReduceArgs),
+
+    /// renders an ir with its summary statistics, lint findings and wire cross references as a standalone html file
+    Report(ReportArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CostArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`
+    pub path: PathBuf,
+
+    /// prints the report as json instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(group(clap::ArgGroup::new("target").required(true)))]
+pub struct SliceArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`
+    pub path: PathBuf,
+
+    /// wire to slice from, as `row,column`
+    #[arg(long, group = "target", value_parser = parse_wire)]
+    pub wire: Option<(usize, usize)>,
+
+    /// virtual wire to slice from, as its index
+    #[arg(long, group = "target")]
+    pub virtual_wire: Option<usize>,
+
+    /// local to slice from, as its name
+    #[arg(long, group = "target")]
+    pub local: Option<String>,
+
+    /// statement to slice from, as its index
+    #[arg(long, group = "target")]
+    pub stmt: Option<usize>,
+
+    /// prints statements influenced by the target instead of statements influencing it
+    #[arg(long)]
+    pub forward: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct EquivArgs {
+    /// path to the original json or source ir
+    pub a: PathBuf,
+
+    /// path to the json or source ir to compare against
+    pub b: PathBuf,
+
+    /// number of random witnesses to evaluate
+    #[arg(long, default_value_t = 64)]
+    pub trials: usize,
+
+    /// seed for generating random witnesses
+    #[arg(long, default_value_t = 0x5EED)]
+    pub seed: u64,
+
+    /// prints an smt-lib query over finite fields instead, satisfiable exactly when the circuits differ
+    #[arg(long)]
+    pub smt: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct R1csArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`
+    pub path: PathBuf,
+
+    /// path of the binary output. the json variant is written next to it with `.json` appended. defaults to the
+    /// input path with extension `r1cs`
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CircomArgs {
+    /// path to the `.r1cs` file emitted by `circom --r1cs`
+    pub path: PathBuf,
+
+    /// path to the `.sym` file naming the signals. defaults to the `.r1cs` path with extension `sym` if it exists
+    #[arg(long)]
+    pub sym: Option<PathBuf>,
+
+    /// name of the output files without extension. defaults to the name of the `.r1cs` file
+    #[arg(long, short)]
+    pub name: Option<String>,
+
+    /// replaces output files when they already exist
+    #[arg(long)]
+    pub allow_dirty: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Plonky2Args {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`
+    pub path: PathBuf,
+
+    /// path of the generated rust file. prints to stdout if omitted
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct WitnessArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`
+    pub path: PathBuf,
+
+    /// path of the generated rust file. prints to stdout if omitted
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct FingerprintArgs {
+    /// paths to json or source irs, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct QueryArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`. positions in a json
+    /// ir refer to its source form
+    pub path: PathBuf,
+
+    /// statement or expression written like source ir, where `$name` matches any expression and `$name:kind` only an
+    /// `ident`, `wire`, `local` or `value`
+    pub pattern: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct LintArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`. positions in a json
+    /// ir refer to its source form
+    pub path: PathBuf,
+
+    /// rule file to use instead of the `zkcir.toml` found in the current directory or its ancestors
+    #[arg(long)]
+    pub rules: Option<PathBuf>,
+
+    /// prints findings as json instead of one per line
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TuiArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`
+    pub path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ReduceArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`
+    pub path: PathBuf,
+
+    /// path of the reduced source ir. the json ir is written next to it with extension `json`. defaults to the
+    /// input path with extension `reduced.cir`
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// exit code of the command that marks a candidate as still reproducing the problem
+    #[arg(long, default_value_t = 0)]
+    pub exit_code: i32,
+
+    /// command testing each candidate, written in the input's form. `{}` in its args is replaced by the candidate's
+    /// path, which is appended if there is no `{}`
+    #[arg(last = true, required = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ReportArgs {
+    /// path to the json or source ir, like `zkcir_out/circuit.json` or `zkcir_out/circuit.cir`. positions in a json
+    /// ir refer to its source form
+    pub path: PathBuf,
+
+    /// path of the html file. defaults to the input path with extension `html`
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// rule file to use instead of the `zkcir.toml` found in the current directory or its ancestors
+    #[arg(long)]
+    pub rules: Option<PathBuf>,
+}
+
+fn parse_wire(s: &str) -> Result<(usize, usize), String> {
+    let (row, column) = s
+        .split_once(',')
+        .ok_or("Expected wire as `row,column`".to_string())?;
+
+    Ok((
+        row.trim()
+            .parse()
+            .map_err(|e| format!("Invalid row `{row}`: {e}"))?,
+        column
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid column `{column}`: {e}"))?,
+    ))
+}
@@ -0,0 +1,60 @@
+use std::{env, fs, path::Path};
+
+use zkcir::circom::import;
+
+use crate::args::CircomArgs;
+
+pub fn run(args: &CircomArgs) -> Result<(), String> {
+    let r1cs = fs::read(&args.path)
+        .map_err(|e| format!("Failed to read {}: {}", args.path.display(), e))?;
+
+    let sym_path = args.sym.clone().or_else(|| {
+        let default = args.path.with_extension("sym");
+        default.exists().then_some(default)
+    });
+    let sym = sym_path
+        .map(|path| {
+            fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+        })
+        .transpose()?;
+
+    let cir = import(&r1cs, sym.as_deref())
+        .map_err(|e| format!("Failed to import {}: {}", args.path.display(), e))?;
+
+    let name = match &args.name {
+        Some(name) => name.clone(),
+        None => args.path.file_stem().map_or("circuit".to_string(), |stem| {
+            stem.to_string_lossy().to_string()
+        }),
+    };
+
+    let output_dir_path = env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?
+        .join("zkcir_out");
+    fs::create_dir_all(&output_dir_path)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let output_cir_path_json = output_dir_path.join(&name).with_extension("json");
+    let output_cir_path_source = output_dir_path.join(&name).with_extension("cir");
+
+    write_output(&output_cir_path_json, &cir.to_string()?, args.allow_dirty)?;
+    write_output(&output_cir_path_source, &cir.to_code_ir(), args.allow_dirty)?;
+
+    println!(
+        "Imported {} constraints to {} and {}",
+        cir.stmts.len(),
+        output_cir_path_json.display(),
+        output_cir_path_source.display()
+    );
+
+    Ok(())
+}
+
+fn write_output(path: &Path, contents: &str, allow_dirty: bool) -> Result<(), String> {
+    if path.exists() && !allow_dirty {
+        return Err(format!("Output file ({}) already exists. Either remove it or rerun command with `--allow-dirty`", path.display()));
+    }
+
+    fs::write(path, contents).map_err(|e| format!("Failed to write cir to output file: {}", e))
+}
@@ -0,0 +1,69 @@
+use zkcir::analysis::cost::{Cost, CostReport};
+
+use crate::{args::CostArgs, commands::read_cir, terminal::format_table};
+
+pub fn run(args: &CostArgs) -> Result<(), String> {
+    let cir = read_cir(&args.path)?;
+    let report = CostReport::new(&cir);
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report)
+                .map_err(|e| format!("Failed to serialize cost report: {}", e))?
+        );
+
+        return Ok(());
+    }
+
+    let mut rows = vec![cost_row("circuit", &report.circuit)];
+    rows.extend(report.gadgets.iter().map(|gadget| {
+        cost_row(
+            &format!("{} [{}..{}]", gadget.name, gadget.start, gadget.end),
+            &gadget.cost,
+        )
+    }));
+
+    println!(
+        "{}",
+        format_table(
+            &[
+                "scope", "verifies", "locals", "degree", "mul", "add", "div", "exp", "public",
+                "private", "const",
+            ],
+            &rows,
+        )
+    );
+
+    println!();
+    println!(
+        "{}",
+        format_table(
+            &["degree", "verifies"],
+            &report
+                .circuit
+                .degree_histogram
+                .iter()
+                .map(|(degree, count)| vec![degree.to_string(), count.to_string()])
+                .collect::<Vec<_>>(),
+        )
+    );
+
+    Ok(())
+}
+
+fn cost_row(scope: &str, cost: &Cost) -> Vec<String> {
+    vec![
+        scope.to_string(),
+        cost.num_verifies.to_string(),
+        cost.num_locals.to_string(),
+        cost.max_degree.to_string(),
+        cost.multiplications.to_string(),
+        cost.additions.to_string(),
+        cost.divisions.to_string(),
+        cost.exponentiations.to_string(),
+        cost.wires.public.to_string(),
+        cost.wires.private.to_string(),
+        cost.wires.constant.to_string(),
+    ]
+}
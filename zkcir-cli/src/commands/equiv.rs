@@ -0,0 +1,52 @@
+use zkcir::analysis::equivalence::{check_equivalence, smt_query, Equivalence, EquivalenceOptions};
+
+use crate::{args::EquivArgs, commands::read_cir};
+
+pub fn run(args: &EquivArgs) -> Result<(), String> {
+    let (a, b) = (read_cir(&args.a)?, read_cir(&args.b)?);
+
+    if args.smt {
+        println!("{}", smt_query(&a, &b).map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+
+    let options = EquivalenceOptions {
+        trials: args.trials,
+        seed: args.seed,
+    };
+
+    match check_equivalence(&a, &b, &options).map_err(|e| e.to_string())? {
+        Equivalence::ProbablyEquivalent { trials } => {
+            println!("No distinguishing witness found in {trials} random witnesses");
+            Ok(())
+        }
+        Equivalence::Different(counterexample) => {
+            let accepts = |accepts| if accepts { "accepts" } else { "rejects" };
+
+            println!("Distinguishing witness found:");
+            println!(
+                "  {}: {}",
+                args.a.display(),
+                accepts(counterexample.a_accepts)
+            );
+            for (var, value) in &counterexample.inputs_a {
+                println!("    {} = {value}", a.var_name(var));
+            }
+
+            println!(
+                "  {}: {}",
+                args.b.display(),
+                accepts(counterexample.b_accepts)
+            );
+            for (var, value) in &counterexample.inputs_b {
+                println!("    {} = {value}", b.var_name(var));
+            }
+
+            for wire_ref in &counterexample.differing_public_inputs {
+                println!("  public input {} differs", a.var_name(&(*wire_ref).into()));
+            }
+
+            Err("Circuits are not equivalent".to_string())
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use crate::{args::FingerprintArgs, commands::read_cir};
+
+pub fn run(args: &FingerprintArgs) -> Result<(), String> {
+    for path in &args.paths {
+        let cir = read_cir(path)?;
+        println!("{}  {}", cir.fingerprint()?, path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use zkcir::{
+    analysis::{
+        lint::Severity,
+        rules::{lint_with_rules, RuleSet, RULES_FILE_NAME},
+    },
+    parse::parse,
+};
+
+use crate::{
+    args::LintArgs,
+    commands::{read_cir, read_source},
+};
+
+pub fn run(args: &LintArgs) -> Result<(), String> {
+    let rules_path = match &args.rules {
+        Some(path) => Some(path.clone()),
+        None => find_rules(
+            &env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?,
+        ),
+    };
+
+    let rules = match &rules_path {
+        Some(path) => read_rules(path)?,
+        None => RuleSet::default(),
+    };
+
+    let cir = read_cir(&args.path)?;
+    let findings = lint_with_rules(&cir, &rules).map_err(|e| {
+        format!(
+            "Invalid {}: {}",
+            rules_path
+                .as_deref()
+                .unwrap_or(Path::new(RULES_FILE_NAME))
+                .display(),
+            e
+        )
+    })?;
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&findings)
+                .map_err(|e| format!("Failed to serialize findings: {}", e))?
+        );
+    } else {
+        let source = read_source(&args.path, &cir)?;
+        let stmt_spans = parse(&source)
+            .map_err(|e| format!("Failed to parse source ir at {}", e))?
+            .stmt_spans;
+
+        if let Some(path) = &rules_path {
+            println!("using rules from {}", path.display());
+        }
+
+        for finding in &findings {
+            // Findings of statements the source doesn't have, like from a stale `.cir`, are reported without a location
+            let location = match stmt_spans.get(finding.stmt_index) {
+                Some(span) => {
+                    let (line, column) = span.line_column(&source);
+                    format!("{}:{line}:{column}", args.path.display())
+                }
+                None => args.path.display().to_string(),
+            };
+
+            println!(
+                "{location}: {}[{}]: {}",
+                finding.severity, finding.rule, finding.message
+            );
+        }
+
+        println!(
+            "{} finding{}",
+            findings.len(),
+            if findings.len() == 1 { "" } else { "s" }
+        );
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+
+    if errors > 0 {
+        return Err(format!(
+            "{errors} finding{} with severity error",
+            if errors == 1 { "" } else { "s" }
+        ));
+    }
+
+    Ok(())
+}
+
+/// Nearest rule file in `dir` or its ancestors
+pub fn find_rules(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|ancestor| ancestor.join(RULES_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+pub fn read_rules(path: &Path) -> Result<RuleSet, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    toml::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
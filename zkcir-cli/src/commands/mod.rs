@@ -0,0 +1,39 @@
+use std::{fs, path::Path};
+
+use zkcir::ir::Cir;
+
+pub mod circom;
+pub mod cost;
+pub mod equiv;
+pub mod fingerprint;
+pub mod lint;
+pub mod plonky2;
+pub mod query;
+pub mod r1cs;
+pub mod reduce;
+pub mod report;
+pub mod slice;
+pub mod tui;
+pub mod witness;
+
+/// Reads a json ir, or a source ir if the extension is `cir`
+pub fn read_cir(path: &Path) -> Result<Cir, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    if path.extension().is_some_and(|extension| extension == "cir") {
+        Cir::from_code_ir(&contents)
+            .map_err(|e| format!("Failed to parse {}:{}", path.display(), e))
+    } else {
+        Cir::from_json(&contents).map_err(|e| format!("Failed to parse json CIR: {}", e))
+    }
+}
+
+/// Source ir of the ir read from `path`. Positions in a json ir refer to the source ir it renders as.
+pub fn read_source(path: &Path, cir: &Cir) -> Result<String, String> {
+    if path.extension().is_some_and(|extension| extension == "cir") {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    } else {
+        Ok(cir.to_code_ir())
+    }
+}
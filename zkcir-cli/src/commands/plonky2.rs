@@ -0,0 +1,22 @@
+use std::fs;
+
+use zkcir::codegen::plonky2;
+
+use crate::{args::Plonky2Args, commands::read_cir};
+
+pub fn run(args: &Plonky2Args) -> Result<(), String> {
+    let cir = read_cir(&args.path)?;
+    let source = plonky2::generate(&cir)
+        .map_err(|e| format!("Failed to generate plonky2 circuit: {}", e))?;
+
+    match &args.output {
+        Some(output_path) => {
+            fs::write(output_path, source)
+                .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))?;
+            println!("Wrote {}", output_path.display());
+        }
+        None => print!("{source}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use zkcir::{
+    node::Node,
+    parse::parse,
+    query::{query, Pattern},
+};
+
+use crate::{
+    args::QueryArgs,
+    commands::{read_cir, read_source},
+};
+
+pub fn run(args: &QueryArgs) -> Result<(), String> {
+    let pattern =
+        Pattern::parse(&args.pattern).map_err(|e| format!("Failed to parse pattern at {}", e))?;
+
+    let cir = read_cir(&args.path)?;
+
+    let source = read_source(&args.path, &cir)?;
+    let stmt_spans = parse(&source)
+        .map_err(|e| format!("Failed to parse source ir at {}", e))?
+        .stmt_spans;
+
+    let matches = query(&cir, &pattern);
+
+    for found in &matches {
+        let span = stmt_spans[found.stmt_index];
+        let (line, column) = span.line_column(&source);
+
+        println!(
+            "{}:{line}:{column}: stmt {}: {}",
+            args.path.display(),
+            found.stmt_index,
+            &source[span.start..span.end]
+        );
+
+        for (name, expr) in &found.bindings {
+            println!("    ${name} = {}", expr.to_code_ir());
+        }
+    }
+
+    println!(
+        "{} matching statement{}",
+        matches.len(),
+        if matches.len() == 1 { "" } else { "s" }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use std::fs;
+
+use zkcir::r1cs::R1cs;
+
+use crate::{args::R1csArgs, commands::read_cir};
+
+pub fn run(args: &R1csArgs) -> Result<(), String> {
+    let cir = read_cir(&args.path)?;
+    let r1cs = R1cs::new(&cir).map_err(|e| format!("Failed to lower to r1cs: {}", e))?;
+
+    let output_path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| args.path.with_extension("r1cs"));
+    let mut json_path = output_path.clone().into_os_string();
+    json_path.push(".json");
+
+    fs::write(&output_path, r1cs.to_iden3_bytes()?)
+        .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))?;
+    fs::write(&json_path, r1cs.to_json()?)
+        .map_err(|e| format!("Failed to write {}: {}", json_path.to_string_lossy(), e))?;
+
+    println!(
+        "Wrote {} constraints over {} signals to {} and {}",
+        r1cs.constraints.len(),
+        r1cs.signals.len(),
+        output_path.display(),
+        json_path.to_string_lossy()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use tempfile::tempdir;
+use zkcir::{ir::Cir, reduce::reduce};
+
+use crate::{args::ReduceArgs, commands::read_cir};
+
+/// Placeholder in the command's args replaced by the path of each candidate
+const PATH_PLACEHOLDER: &str = "{}";
+
+pub fn run(args: &ReduceArgs) -> Result<(), String> {
+    let cir = read_cir(&args.path)?;
+
+    let (program, program_args) = args
+        .command
+        .split_first()
+        .ok_or("Expected a command to test candidates with")?;
+
+    let temp_dir = tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    let candidate_path = temp_dir.path().join(
+        args.path
+            .file_name()
+            .ok_or_else(|| format!("Expected a file at {}", args.path.display()))?,
+    );
+    let is_source = args
+        .path
+        .extension()
+        .is_some_and(|extension| extension == "cir");
+
+    let mut error = None;
+    // A command that can't run rejects every candidate, so the first error stops the reduction
+    let reduction = reduce(&cir, |candidate| {
+        if error.is_some() {
+            return false;
+        }
+
+        test(
+            &candidate_path,
+            candidate,
+            is_source,
+            program,
+            program_args,
+            args.exit_code,
+        )
+        .unwrap_or_else(|e| {
+            error = Some(e);
+            false
+        })
+    });
+    if let Some(e) = error {
+        return Err(e);
+    }
+    let reduction = reduction.ok_or(format!(
+        "`{}` doesn't exit with code {} on {}, so there is nothing to reduce",
+        args.command.join(" "),
+        args.exit_code,
+        args.path.display()
+    ))?;
+
+    let output_source_path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| args.path.with_extension("reduced.cir"));
+    let output_json_path = output_source_path.with_extension("json");
+
+    fs::write(&output_source_path, reduction.cir.to_code_ir())
+        .map_err(|e| format!("Failed to write {}: {}", output_source_path.display(), e))?;
+    fs::write(&output_json_path, reduction.cir.to_string()?)
+        .map_err(|e| format!("Failed to write {}: {}", output_json_path.display(), e))?;
+
+    println!(
+        "reduced {} statements to {} in {} tests",
+        cir.stmts.len(),
+        reduction.cir.stmts.len(),
+        reduction.tests
+    );
+    println!("wrote {}", output_source_path.display());
+    println!("wrote {}", output_json_path.display());
+
+    Ok(())
+}
+
+/// Whether `program` exits with `exit_code` on `candidate`, written to `path` in the same form as the input
+fn test(
+    path: &Path,
+    candidate: &Cir,
+    is_source: bool,
+    program: &str,
+    program_args: &[String],
+    exit_code: i32,
+) -> Result<bool, String> {
+    let contents = if is_source {
+        candidate.to_code_ir()
+    } else {
+        candidate.to_string()?
+    };
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let mut command = Command::new(program);
+    command.args(candidate_args(program_args, &path.to_string_lossy()));
+
+    let status = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to execute `{program}`: {}", e))?;
+
+    Ok(status.code() == Some(exit_code))
+}
+
+/// `program_args` with `{}` replaced by `path`, or followed by `path` if no arg contains `{}`
+fn candidate_args(program_args: &[String], path: &str) -> Vec<String> {
+    if program_args
+        .iter()
+        .any(|arg| arg.contains(PATH_PLACEHOLDER))
+    {
+        program_args
+            .iter()
+            .map(|arg| arg.replace(PATH_PLACEHOLDER, path))
+            .collect()
+    } else {
+        program_args
+            .iter()
+            .cloned()
+            .chain([path.to_string()])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_candidate_args() {
+        assert_eq!(
+            candidate_args(&args(&["lint", "--deny"]), "/tmp/a.cir"),
+            args(&["lint", "--deny", "/tmp/a.cir"])
+        );
+        assert_eq!(
+            candidate_args(&args(&["-c", "zkcir lint {} | grep {}"]), "/tmp/a.cir"),
+            args(&["-c", "zkcir lint /tmp/a.cir | grep /tmp/a.cir"])
+        );
+        assert_eq!(candidate_args(&[], "a.json"), args(&["a.json"]));
+    }
+}
@@ -0,0 +1,456 @@
+use std::{collections::BTreeMap, env, fmt::Write, fs};
+
+use zkcir::{
+    analysis::{
+        cost::CostReport,
+        eval::definitions,
+        lint::{Finding, Severity},
+        rules::{lint_with_rules, RuleSet},
+        Var,
+    },
+    ast::{Ident, WireRef, Wiretype},
+    ir::Cir,
+    parse::{parse, tokens, ParsedCir, TokenKind},
+};
+
+use crate::{
+    args::ReportArgs,
+    commands::{
+        lint::{find_rules, read_rules},
+        read_cir, read_source,
+    },
+};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 70em; color: #1f2328; }
+h1 { font-size: 1.6em; }
+h2 { border-bottom: 1px solid #d0d7de; margin-top: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d0d7de; padding: 0.25em 0.6em; text-align: left; vertical-align: top; }
+td.number { text-align: right; }
+a { color: #0969da; text-decoration: none; }
+a:hover { text-decoration: underline; }
+pre.source { background: #f6f8fa; padding: 1em; overflow-x: auto; line-height: 1.5; }
+.stmt { display: inline; }
+.stmt:target, tr:target, li:target { background: #fff8c5; }
+.comment { color: #6e7781; font-style: italic; }
+.keyword { color: #cf222e; }
+.label { color: #8250df; }
+.name { color: #953800; }
+.number { color: #0550ae; }
+.ident.public { text-decoration: underline dotted #1a7f37; }
+.ident.private { text-decoration: underline dotted #cf222e; }
+.marker { font-family: sans-serif; font-size: 0.75em; border-radius: 0.8em; padding: 0 0.5em; margin-left: 0.5em; color: #fff; }
+.marker.error, .severity.error { background: #cf222e; }
+.marker.warning, .severity.warning { background: #9a6700; }
+.marker.info, .severity.info { background: #0969da; }
+.severity { color: #fff; border-radius: 0.8em; padding: 0 0.5em; font-size: 0.85em; }
+.explanation { color: #57606a; }
+";
+
+pub fn run(args: &ReportArgs) -> Result<(), String> {
+    let rules_path = match &args.rules {
+        Some(path) => Some(path.clone()),
+        None => find_rules(
+            &env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?,
+        ),
+    };
+
+    let rules = match &rules_path {
+        Some(path) => read_rules(path)?,
+        None => RuleSet::default(),
+    };
+
+    let cir = read_cir(&args.path)?;
+    let findings = lint_with_rules(&cir, &rules).map_err(|e| format!("Invalid rules: {}", e))?;
+
+    let source = read_source(&args.path, &cir)?;
+    let parsed = parse(&source).map_err(|e| format!("Failed to parse source ir at {}", e))?;
+
+    let title = args.path.file_name().map_or_else(
+        || args.path.display().to_string(),
+        |name| name.to_string_lossy().to_string(),
+    );
+
+    let html = render(&title, &cir, &source, &parsed, &findings)?;
+
+    let output_path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| args.path.with_extension("html"));
+
+    fs::write(&output_path, html)
+        .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))?;
+
+    println!(
+        "wrote {} with {} finding{}",
+        output_path.display(),
+        findings.len(),
+        if findings.len() == 1 { "" } else { "s" }
+    );
+
+    Ok(())
+}
+
+/// Standalone html page, with styles inlined and no scripts
+fn render(
+    title: &str,
+    cir: &Cir,
+    source: &str,
+    parsed: &ParsedCir,
+    findings: &[Finding],
+) -> Result<String, String> {
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0} - zkcir report</title>\n\
+         <style>{STYLE}</style>\n</head>\n<body>\n<h1>{0}</h1>\n<p>Fingerprint <code>{1}</code></p>\n",
+        escape(title),
+        cir.fingerprint()?
+    );
+
+    render_summary(&mut html, cir, findings);
+    render_findings(&mut html, source, parsed, findings);
+    render_source(&mut html, source, parsed, findings)?;
+    render_wires(&mut html, cir, parsed);
+
+    html.push_str("</body>\n</html>\n");
+    Ok(html)
+}
+
+fn render_summary(html: &mut String, cir: &Cir, findings: &[Finding]) {
+    let report = CostReport::new(cir);
+    let cost = &report.circuit;
+
+    let count = |severity| {
+        findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    };
+
+    let rows = [
+        ("statements", cir.stmts.len()),
+        ("verifies", cost.num_verifies),
+        ("locals", cost.num_locals),
+        (
+            "max degree",
+            usize::try_from(cost.max_degree).unwrap_or(usize::MAX),
+        ),
+        ("multiplications", cost.multiplications),
+        ("additions", cost.additions),
+        ("divisions", cost.divisions),
+        ("exponentiations", cost.exponentiations),
+        ("public wires", cost.wires.public),
+        ("private wires", cost.wires.private),
+        ("constant wires", cost.wires.constant),
+        ("gadgets", cir.gadgets.len()),
+        ("errors", count(Severity::Error)),
+        ("warnings", count(Severity::Warning)),
+        ("infos", count(Severity::Info)),
+    ];
+
+    html.push_str("<h2 id=\"summary\">Summary</h2>\n<table>\n");
+    for (name, value) in rows {
+        let _ = writeln!(
+            html,
+            "<tr><th>{name}</th><td class=\"number\">{value}</td></tr>"
+        );
+    }
+    html.push_str("</table>\n");
+
+    if report.gadgets.is_empty() {
+        return;
+    }
+
+    html.push_str(
+        "<h3>Gadgets</h3>\n<table>\n<tr><th>gadget</th><th>statements</th><th>verifies</th><th>locals</th>\
+         <th>max degree</th></tr>\n",
+    );
+    for gadget in &report.gadgets {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}..{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td>\
+             <td class=\"number\">{}</td></tr>",
+            escape(&gadget.name),
+            stmt_link(gadget.start),
+            gadget.end,
+            gadget.cost.num_verifies,
+            gadget.cost.num_locals,
+            gadget.cost.max_degree
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_findings(html: &mut String, source: &str, parsed: &ParsedCir, findings: &[Finding]) {
+    html.push_str("<h2 id=\"findings\">Findings</h2>\n");
+
+    if findings.is_empty() {
+        html.push_str("<p>No findings.</p>\n");
+        return;
+    }
+
+    html.push_str("<ol>\n");
+    for (index, finding) in findings.iter().enumerate() {
+        // Findings no statement is responsible for, like a challenge that is never read, are at the end
+        let location = match parsed.stmt_spans.get(finding.stmt_index) {
+            Some(span) => {
+                let (line, column) = span.line_column(source);
+                format!(
+                    "{} (line {line}, column {column})",
+                    stmt_link(finding.stmt_index)
+                )
+            }
+            None => "the end of the circuit".to_string(),
+        };
+
+        let _ = write!(
+            html,
+            "<li id=\"finding-{index}\"><span class=\"severity {0}\">{0}</span> <code>{1}</code> in \
+             {location}: {2}",
+            finding.severity,
+            escape(&finding.rule),
+            escape(&finding.message)
+        );
+        if !finding.explanation.is_empty() {
+            let _ = write!(
+                html,
+                "<p class=\"explanation\">{}</p>",
+                escape(&finding.explanation)
+            );
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ol>\n");
+}
+
+/// Highlighted source with an anchor per statement, wires linking to their cross references, and locals linking to
+/// the statement defining them
+fn render_source(
+    html: &mut String,
+    source: &str,
+    parsed: &ParsedCir,
+    findings: &[Finding],
+) -> Result<(), String> {
+    let definitions = definitions(&parsed.cir);
+
+    // Markup to insert at each byte offset. At the same offset closing tags go first, innermost first, and opening
+    // tags go last, outermost first.
+    let mut inserts = Vec::new();
+
+    for (stmt_index, span) in parsed.stmt_spans.iter().enumerate() {
+        inserts.push((
+            span.start,
+            4,
+            format!("<span class=\"stmt\" id=\"stmt-{stmt_index}\">"),
+        ));
+        inserts.push((span.end, 2, "</span>".to_string()));
+
+        for (finding_index, finding) in findings.iter().enumerate() {
+            if finding.stmt_index == stmt_index {
+                inserts.push((
+                    span.end,
+                    3,
+                    format!(
+                        "<a class=\"marker {}\" href=\"#finding-{finding_index}\" title=\"{}\">{}</a>",
+                        finding.severity,
+                        escape(&finding.message),
+                        escape(&finding.rule)
+                    ),
+                ));
+            }
+        }
+    }
+
+    for occurrence in &parsed.idents {
+        let (class, href) = match &occurrence.ident {
+            Ident::Wire(wire) => (wiretype_class(wire.wiretype), wire_id(wire.wire_ref())),
+            Ident::VirtualWire(virtual_wire) => (
+                wiretype_class(virtual_wire.wiretype),
+                wire_id(virtual_wire.wire_ref()),
+            ),
+            Ident::String(_) => match definitions.get(&Var::from(&occurrence.ident)) {
+                Some(&stmt_index) if !occurrence.is_definition => {
+                    ("local", format!("stmt-{stmt_index}"))
+                }
+                _ => continue,
+            },
+        };
+
+        inserts.push((
+            occurrence.span.start,
+            5,
+            format!("<a class=\"ident {class}\" href=\"#{href}\">"),
+        ));
+        inserts.push((occurrence.span.end, 1, "</a>".to_string()));
+    }
+
+    for (span, kind) in tokens(source).map_err(|e| format!("Failed to parse source ir at {}", e))? {
+        let class = match kind {
+            TokenKind::Comment => "comment",
+            TokenKind::Keyword => "keyword",
+            TokenKind::Label => "label",
+            TokenKind::Name => "name",
+            TokenKind::Number => "number",
+            TokenKind::Punctuation => continue,
+        };
+
+        inserts.push((span.start, 6, format!("<span class=\"{class}\">")));
+        inserts.push((span.end, 0, "</span>".to_string()));
+    }
+
+    // Stable, so markers keep the order of the findings
+    inserts.sort_by_key(|(offset, order, _)| (*offset, *order));
+
+    html.push_str("<h2 id=\"source\">Source</h2>\n<pre class=\"source\">");
+
+    let mut offset = 0;
+    for (insert_offset, _, markup) in inserts {
+        html.push_str(&escape(&source[offset..insert_offset]));
+        html.push_str(&markup);
+        offset = insert_offset;
+    }
+    html.push_str(&escape(&source[offset..]));
+
+    html.push_str("</pre>\n");
+    Ok(())
+}
+
+/// Where each wire is defined and used
+#[derive(Default)]
+struct WireUsage {
+    wiretype: Option<Wiretype>,
+    stmts: Vec<usize>,
+}
+
+fn render_wires(html: &mut String, cir: &Cir, parsed: &ParsedCir) {
+    let definitions = definitions(cir);
+
+    let mut wires = BTreeMap::<WireRef, WireUsage>::new();
+    for occurrence in &parsed.idents {
+        let (wire_ref, wiretype) = match &occurrence.ident {
+            Ident::Wire(wire) => (wire.wire_ref(), wire.wiretype),
+            Ident::VirtualWire(virtual_wire) => (virtual_wire.wire_ref(), virtual_wire.wiretype),
+            Ident::String(_) => continue,
+        };
+
+        let usage = wires.entry(wire_ref).or_default();
+        usage.wiretype.get_or_insert(wiretype);
+        if !usage.stmts.contains(&occurrence.stmt_index) {
+            usage.stmts.push(occurrence.stmt_index);
+        }
+    }
+
+    html.push_str("<h2 id=\"wires\">Wires</h2>\n");
+
+    if wires.is_empty() {
+        html.push_str("<p>No wires.</p>\n");
+        return;
+    }
+
+    html.push_str(
+        "<table>\n<tr><th>wire</th><th>type</th><th>name</th><th>defined by</th><th>used by</th></tr>\n",
+    );
+    for (wire_ref, usage) in &wires {
+        let annotation = cir.annotation(*wire_ref);
+        let defined_by = definitions.get(&Var::Wire(*wire_ref)).copied();
+
+        let name = annotation.map_or_else(String::new, |annotation| {
+            let mut name = escape(annotation.name.as_deref().unwrap_or_default());
+            if let Some(doc) = &annotation.doc {
+                let _ = write!(
+                    name,
+                    "<br><span class=\"explanation\">{}</span>",
+                    escape(doc)
+                );
+            }
+            if !annotation.tags.is_empty() {
+                let _ = write!(name, "<br>tags: {}", escape(&annotation.tags.join(", ")));
+            }
+            name
+        });
+
+        let used_by = usage
+            .stmts
+            .iter()
+            .filter(|&&stmt_index| Some(stmt_index) != defined_by)
+            .map(|&stmt_index| stmt_link(stmt_index))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let _ = writeln!(
+            html,
+            "<tr id=\"{}\"><td><code>{}</code></td><td>{}</td><td>{name}</td><td>{}</td><td>{used_by}</td></tr>",
+            wire_id(*wire_ref),
+            escape(&Var::Wire(*wire_ref).to_code_ir()),
+            usage.wiretype.map_or_else(String::new, |wiretype| wiretype.to_string()),
+            defined_by.map_or_else(|| "input".to_string(), stmt_link)
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn stmt_link(stmt_index: usize) -> String {
+    format!("<a href=\"#stmt-{stmt_index}\">stmt {stmt_index}</a>")
+}
+
+fn wire_id(wire_ref: WireRef) -> String {
+    match wire_ref {
+        WireRef::Wire { row, column } => format!("wire-{row}-{column}"),
+        WireRef::VirtualWire { index } => format!("virtual-wire-{index}"),
+    }
+}
+
+fn wiretype_class(wiretype: Wiretype) -> &'static str {
+    match wiretype {
+        Wiretype::Public => "public",
+        Wiretype::Private => "private",
+        Wiretype::Constant => "constant",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use zkcir::analysis::lint::lint;
+
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x">&amp;</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let source = "// x < y & \"z\"\nlet x = 1u64 / wire::private(row: 0, column: 0);\n\nverify!(x < 8u64);\n";
+        let parsed = parse(source).unwrap();
+        let findings = lint(&parsed.cir);
+
+        let html = render("<circuit>.cir", &parsed.cir, source, &parsed, &findings).unwrap();
+
+        assert!(html.contains("<title>&lt;circuit&gt;.cir - zkcir report</title>"));
+        assert!(html.contains("<span class=\"comment\">// x &lt; y &amp; &quot;z&quot;</span>"));
+        assert!(html.contains("<span class=\"stmt\" id=\"stmt-1\">"));
+        assert!(html.contains(
+            "<a class=\"ident local\" href=\"#stmt-0\"><span class=\"name\">x</span></a> &lt; <span \
+             class=\"number\">8u64</span>"
+        ));
+        assert!(html.contains("<a class=\"ident private\" href=\"#wire-0-0\">"));
+        assert!(html.contains(
+            "title=\"`x &lt; 8u64` compares `x` without a preceding range check\">unchecked-comparison</a>"
+        ));
+        assert!(!html.contains("<circuit>"));
+    }
+}
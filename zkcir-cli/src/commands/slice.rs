@@ -0,0 +1,51 @@
+use zkcir::{
+    analysis::{
+        slice::{
+            backward_slice_indices, backward_slice_indices_from_stmt, forward_slice_indices,
+            forward_slice_indices_from_stmt, sub_cir,
+        },
+        Var,
+    },
+    ast::WireRef,
+};
+
+use crate::{args::SliceArgs, commands::read_cir};
+
+pub fn run(args: &SliceArgs) -> Result<(), String> {
+    let cir = read_cir(&args.path)?;
+
+    let indices = if let Some(stmt_index) = args.stmt {
+        if stmt_index >= cir.stmts.len() {
+            return Err(format!(
+                "Statement {stmt_index} out of range; circuit has {} statements",
+                cir.stmts.len()
+            ));
+        }
+
+        if args.forward {
+            forward_slice_indices_from_stmt(&cir, stmt_index)
+        } else {
+            backward_slice_indices_from_stmt(&cir, stmt_index)
+        }
+    } else {
+        let target = if let Some((row, column)) = args.wire {
+            Var::Wire(WireRef::Wire { row, column })
+        } else if let Some(index) = args.virtual_wire {
+            Var::Wire(WireRef::VirtualWire { index })
+        } else if let Some(name) = &args.local {
+            Var::Local(name.clone())
+        } else {
+            return Err("Expected a wire, virtual wire, local or statement to slice from".into());
+        };
+
+        if args.forward {
+            forward_slice_indices(&cir, target)
+        } else {
+            backward_slice_indices(&cir, target)
+        }
+    };
+
+    println!("{}", sub_cir(&cir, &indices).to_code_ir());
+
+    Ok(())
+}
@@ -0,0 +1,630 @@
+use std::{
+    env,
+    io::{self, Stdout},
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+use zkcir::{
+    analysis::{
+        lint::{Finding, Severity},
+        range::RangeAnalysis,
+        rules::{lint_with_rules, RuleSet},
+        stmt_def, Var,
+    },
+    ast::{Ident, Stmt, Value, WireRef, Wiretype},
+    ir::Cir,
+    node::Node,
+    parse::parse,
+};
+
+use crate::{
+    args::TuiArgs,
+    commands::{
+        lint::{find_rules, read_rules},
+        read_cir, read_source,
+    },
+};
+
+const HELP: &str = "↑↓ move  / search  t wiretype  tab wire  enter uses of wire  f findings only  v json  esc clear  q quit";
+
+pub fn run(args: &TuiArgs) -> Result<(), String> {
+    let cir = read_cir(&args.path)?;
+    let source = read_source(&args.path, &cir)?;
+
+    let rules = match find_rules(
+        &env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?,
+    ) {
+        Some(path) => read_rules(&path)?,
+        None => RuleSet::default(),
+    };
+    let findings = lint_with_rules(&cir, &rules).map_err(|e| format!("Invalid rules: {}", e))?;
+
+    let mut app = App::new(cir, &source, findings)?;
+
+    let mut terminal = start_terminal().map_err(|e| format!("Failed to start terminal: {}", e))?;
+    let result = app.run(&mut terminal);
+    stop_terminal(&mut terminal).map_err(|e| format!("Failed to restore terminal: {}", e))?;
+
+    result.map_err(|e| format!("Terminal error: {}", e))
+}
+
+fn start_terminal() -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(io::stdout()))
+}
+
+fn stop_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum View {
+    CodeIr,
+    Json,
+}
+
+/// Wire read or written by a statement
+struct StmtWire {
+    wire_ref: WireRef,
+    wiretype: Wiretype,
+    value: Option<Value>,
+    code_ir: String,
+}
+
+struct App {
+    cir: Cir,
+    ranges: RangeAnalysis,
+    findings: Vec<Finding>,
+
+    /// Source of each statement on a single line
+    lines: Vec<String>,
+
+    /// Wires of each statement in order of first appearance
+    wires: Vec<Vec<StmtWire>>,
+
+    /// Indices into `cir.stmts` shown in the list
+    visible: Vec<usize>,
+    list: ListState,
+
+    search: String,
+    searching: bool,
+    wiretype: Option<Wiretype>,
+    findings_only: bool,
+
+    /// Only statements using this wire are shown
+    uses_of: Option<WireRef>,
+
+    /// Index into the selected statement's `wires`
+    wire_cursor: usize,
+    view: View,
+    quit: bool,
+}
+
+impl App {
+    fn new(cir: Cir, source: &str, findings: Vec<Finding>) -> Result<Self, String> {
+        let stmt_spans = parse(source)
+            .map_err(|e| format!("Failed to parse source ir at {}", e))?
+            .stmt_spans;
+
+        let lines = stmt_spans
+            .iter()
+            .map(|span| {
+                source[span.start..span.end]
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+
+        let wires = cir.stmts.iter().map(stmt_wires).collect();
+
+        let mut app = Self {
+            ranges: RangeAnalysis::new(&cir),
+            cir,
+            findings,
+            lines,
+            wires,
+            visible: Vec::new(),
+            list: ListState::default(),
+            search: String::new(),
+            searching: false,
+            wiretype: None,
+            findings_only: false,
+            uses_of: None,
+            wire_cursor: 0,
+            view: View::CodeIr,
+            quit: false,
+        };
+        app.filter();
+
+        Ok(app)
+    }
+
+    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.list
+            .selected()
+            .and_then(|selected| self.visible.get(selected).copied())
+    }
+
+    fn selected_wire(&self) -> Option<&StmtWire> {
+        self.wires[self.selected()?].get(self.wire_cursor)
+    }
+
+    fn stmt_findings(&self, stmt_index: usize) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(move |finding| finding.stmt_index == stmt_index)
+    }
+
+    /// Recomputes `visible` from the filters, keeping the selected statement if it's still shown
+    fn filter(&mut self) {
+        let selected = self.selected();
+        let search = self.search.to_lowercase();
+
+        self.visible = (0..self.cir.stmts.len())
+            .filter(|&stmt_index| {
+                let wires = &self.wires[stmt_index];
+
+                self.lines[stmt_index].to_lowercase().contains(&search)
+                    && self
+                        .wiretype
+                        .is_none_or(|wiretype| wires.iter().any(|wire| wire.wiretype == wiretype))
+                    && self
+                        .uses_of
+                        .is_none_or(|wire_ref| wires.iter().any(|wire| wire.wire_ref == wire_ref))
+                    && (!self.findings_only || self.stmt_findings(stmt_index).next().is_some())
+            })
+            .collect();
+
+        let position = selected
+            .and_then(|selected| self.visible.iter().position(|&index| index == selected))
+            .unwrap_or(0);
+
+        self.list
+            .select((!self.visible.is_empty()).then_some(position));
+        self.wire_cursor = 0;
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+
+        let selected = self.list.selected().unwrap_or(0);
+        let last = self.visible.len() - 1;
+
+        self.list
+            .select(Some(selected.saturating_add_signed(offset).min(last)));
+        self.wire_cursor = 0;
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if self.searching {
+            match key.code {
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.search.clear();
+                }
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Char(c) => self.search.push(c),
+                _ => return,
+            }
+
+            self.filter();
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-20),
+            KeyCode::PageDown => self.move_selection(20),
+            KeyCode::Home | KeyCode::Char('g') => self.move_selection(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.move_selection(isize::MAX),
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Char('t') => {
+                self.wiretype = match self.wiretype {
+                    None => Some(Wiretype::Public),
+                    Some(Wiretype::Public) => Some(Wiretype::Private),
+                    Some(Wiretype::Private) => Some(Wiretype::Constant),
+                    Some(Wiretype::Constant) => None,
+                };
+                self.filter();
+            }
+            KeyCode::Char('f') => {
+                self.findings_only = !self.findings_only;
+                self.filter();
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                let count = self
+                    .selected()
+                    .map_or(0, |selected| self.wires[selected].len());
+
+                if count > 0 {
+                    self.wire_cursor = if key.code == KeyCode::Tab {
+                        (self.wire_cursor + 1) % count
+                    } else {
+                        (self.wire_cursor + count - 1) % count
+                    };
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(wire) = self.selected_wire() {
+                    self.uses_of = Some(wire.wire_ref);
+                    self.filter();
+                }
+            }
+            KeyCode::Char('v') => {
+                self.view = match self.view {
+                    View::CodeIr => View::Json,
+                    View::Json => View::CodeIr,
+                };
+            }
+            KeyCode::Esc => {
+                self.search.clear();
+                self.wiretype = None;
+                self.findings_only = false;
+                self.uses_of = None;
+                self.filter();
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [status, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.size());
+        let [list, details] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(body);
+
+        frame.render_widget(Paragraph::new(self.status()), status);
+
+        let items = self
+            .visible
+            .iter()
+            .map(|&stmt_index| {
+                let severity = self
+                    .stmt_findings(stmt_index)
+                    .map(|finding| finding.severity)
+                    .max();
+
+                let (marker, color) = match severity {
+                    Some(Severity::Error) => ("E", Color::Red),
+                    Some(Severity::Warning) => ("W", Color::Yellow),
+                    Some(Severity::Info) => ("I", Color::Blue),
+                    None => (" ", Color::Reset),
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{stmt_index:>5} "),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(format!("{marker} "), Style::default().fg(color)),
+                    Span::raw(self.lines[stmt_index].clone()),
+                ]))
+            })
+            .collect::<Vec<_>>();
+
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(format!(
+                    "statements ({}/{})",
+                    self.visible.len(),
+                    self.cir.stmts.len()
+                )))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            list,
+            &mut self.list,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.details())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(match self.view {
+                            View::CodeIr => "statement",
+                            View::Json => "statement (json)",
+                        }),
+                )
+                .wrap(Wrap { trim: false }),
+            details,
+        );
+
+        let footer_text = if self.searching {
+            format!("/{}", self.search)
+        } else {
+            HELP.to_string()
+        };
+        frame.render_widget(
+            Paragraph::new(footer_text).style(Style::default().fg(Color::DarkGray)),
+            footer,
+        );
+    }
+
+    fn status(&self) -> String {
+        let mut filters = Vec::new();
+
+        if !self.search.is_empty() {
+            filters.push(format!("search `{}`", self.search));
+        }
+        if let Some(wiretype) = self.wiretype {
+            filters.push(format!("{wiretype} wires"));
+        }
+        if let Some(wire_ref) = self.uses_of {
+            filters.push(format!("uses of {}", Var::Wire(wire_ref).to_code_ir()));
+        }
+        if self.findings_only {
+            filters.push("with findings".to_string());
+        }
+
+        if filters.is_empty() {
+            "all statements".to_string()
+        } else {
+            filters.join(", ")
+        }
+    }
+
+    fn details(&self) -> Vec<Line<'static>> {
+        let Some(stmt_index) = self.selected() else {
+            return vec![Line::from("no matching statements")];
+        };
+        let stmt = &self.cir.stmts[stmt_index];
+
+        let text = match self.view {
+            View::CodeIr => self.lines[stmt_index].clone(),
+            View::Json => serde_json::to_string_pretty(stmt)
+                .unwrap_or_else(|e| format!("Failed to serialize statement: {}", e)),
+        };
+
+        let mut lines = vec![
+            Line::styled(
+                format!("stmt {stmt_index}"),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Line::from(""),
+        ];
+        lines.extend(text.lines().map(|line| Line::from(line.to_string())));
+
+        if let Some(Var::Local(name)) = stmt_def(stmt) {
+            if let Some(range) = self.ranges.local(&name) {
+                lines.push(Line::from(""));
+                lines.push(Line::from(format!("{name} in {range}")));
+            }
+        }
+
+        let wires = &self.wires[stmt_index];
+        if !wires.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                "wires (tab to select, enter for uses)",
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        for (index, wire) in wires.iter().enumerate() {
+            let mut text = format!("{} {}", wire.wiretype, wire.code_ir);
+
+            if let Some(value) = wire.value {
+                text.push_str(&format!(" = {value}"));
+            }
+            if let Some(range) = self.ranges.range(wire.wire_ref) {
+                text.push_str(&format!(" in {range}"));
+            }
+            if let Some(name) = self
+                .cir
+                .annotation(wire.wire_ref)
+                .and_then(|annotation| annotation.name.as_ref())
+            {
+                text.push_str(&format!(" ({name})"));
+            }
+
+            lines.push(if index == self.wire_cursor {
+                Line::styled(
+                    format!("> {text}"),
+                    Style::default().add_modifier(Modifier::REVERSED),
+                )
+            } else {
+                Line::from(format!("  {text}"))
+            });
+        }
+
+        let findings = self.stmt_findings(stmt_index).collect::<Vec<_>>();
+        if !findings.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                "findings",
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        for finding in findings {
+            let color = match finding.severity {
+                Severity::Error => Color::Red,
+                Severity::Warning => Color::Yellow,
+                Severity::Info => Color::Blue,
+            };
+
+            lines.push(Line::styled(
+                format!(
+                    "{}[{}]: {}",
+                    finding.severity, finding.rule, finding.message
+                ),
+                Style::default().fg(color),
+            ));
+        }
+
+        lines
+    }
+}
+
+/// Wires of `stmt` in order of first appearance, with the value written on any occurrence
+fn stmt_wires(stmt: &Stmt) -> Vec<StmtWire> {
+    let mut wires: Vec<StmtWire> = Vec::new();
+
+    stmt.clone().visit_idents_mut(&mut |ident| {
+        let (wire_ref, wiretype, value) = match ident {
+            Ident::Wire(wire) => (wire.wire_ref(), wire.wiretype, wire.value),
+            Ident::VirtualWire(virtual_wire) => (
+                virtual_wire.wire_ref(),
+                virtual_wire.wiretype,
+                virtual_wire.value,
+            ),
+            Ident::String(_) => return ident.clone(),
+        };
+
+        match wires.iter_mut().find(|wire| wire.wire_ref == wire_ref) {
+            Some(wire) => wire.value = wire.value.or(value),
+            None => wires.push(StmtWire {
+                wire_ref,
+                wiretype,
+                value,
+                code_ir: Var::Wire(wire_ref).to_code_ir(),
+            }),
+        }
+
+        ident.clone()
+    });
+
+    wires
+}
+
+#[cfg(test)]
+mod tests {
+    use zkcir::analysis::lint::lint;
+
+    use super::*;
+
+    const SOURCE: &str =
+        "let x = wire::private(row: 0, column: 0) + wire::public(row: 1, column: 0, value: 5u64);
+
+verify!(x == wire::private(row: 0, column: 0));
+
+verify!(wire::private(row: 2, column: 0) == wire::private(row: 2, column: 0, value: 3u64));
+
+let y = x / wire::private(row: 2, column: 0);
+";
+
+    fn app() -> App {
+        let cir = parse(SOURCE).unwrap().cir;
+        let findings = lint(&cir);
+        App::new(cir, SOURCE, findings).unwrap()
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::from(code));
+    }
+
+    #[test]
+    fn test_stmt_wires() {
+        let app = app();
+
+        let wires = app.wires[0]
+            .iter()
+            .map(|wire| (wire.code_ir.as_str(), wire.wiretype, wire.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            wires,
+            [
+                ("wire(row: 0, column: 0)", Wiretype::Private, None),
+                (
+                    "wire(row: 1, column: 0)",
+                    Wiretype::Public,
+                    Some(Value::U64(5))
+                ),
+            ]
+        );
+
+        // A value written on any occurrence is kept
+        assert_eq!(app.wires[2].len(), 1);
+        assert_eq!(app.wires[2][0].value, Some(Value::U64(3)));
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut app = app();
+        assert_eq!(app.visible, [0, 1, 2, 3]);
+        assert_eq!(app.status(), "all statements");
+
+        press(&mut app, KeyCode::Char('/'));
+        for c in "VERIFY".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.visible, [1, 2]);
+        assert_eq!(app.status(), "search `VERIFY`");
+
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('t'));
+        assert_eq!(app.visible, [0]);
+
+        press(&mut app, KeyCode::Char('t'));
+        assert_eq!(app.visible, [0, 1, 2, 3]);
+
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('f'));
+        assert_eq!(app.visible, [3]);
+        assert_eq!(app.status(), "with findings");
+    }
+
+    #[test]
+    fn test_uses_of_wire() {
+        let mut app = app();
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected(), Some(3));
+
+        // The selected statement stays selected when the list is filtered
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.visible, [2, 3]);
+        assert_eq!(app.selected(), Some(3));
+        assert_eq!(app.status(), "uses of wire(row: 2, column: 0)");
+
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Home);
+        assert_eq!(app.selected(), Some(2));
+    }
+}
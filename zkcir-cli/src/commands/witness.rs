@@ -0,0 +1,22 @@
+use std::fs;
+
+use zkcir::codegen::witness;
+
+use crate::{args::WitnessArgs, commands::read_cir};
+
+pub fn run(args: &WitnessArgs) -> Result<(), String> {
+    let cir = read_cir(&args.path)?;
+    let source = witness::generate(&cir)
+        .map_err(|e| format!("Failed to generate witness function: {}", e))?;
+
+    match &args.output {
+        Some(output_path) => {
+            fs::write(output_path, source)
+                .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))?;
+            println!("Wrote {}", output_path.display());
+        }
+        None => print!("{source}"),
+    }
+
+    Ok(())
+}
@@ -12,11 +12,12 @@ use tempfile::tempdir;
 use terminal::{create_new_pb, get_formatted_left_output, OutputColor};
 use toml::Value;
 use walkdir::{DirEntry, WalkDir};
-use zkcir::{ir::Cir, END_DISCRIMINATOR, START_DISCRIMINATOR};
+use zkcir::{analysis::range::RangeAnalysis, ir::Cir, END_DISCRIMINATOR, START_DISCRIMINATOR};
 
-use args::Args;
+use args::{Args, Command as CliCommand};
 
 mod args;
+mod commands;
 mod terminal;
 
 fn start(current_dir: &Path, args: &Args, pb: &ProgressBar) -> Result<(), String> {
@@ -241,6 +242,11 @@ fn start(current_dir: &Path, args: &Args, pb: &ProgressBar) -> Result<(), String
         "{} cir output",
         get_formatted_left_output("Parsed", OutputColor::Green)
     ));
+    pb.println(format!(
+        "{} {}",
+        get_formatted_left_output("Fingerprint", OutputColor::Green),
+        cir.fingerprint()?
+    ));
     pb.inc(1);
 
     pb.set_message(": emit".to_string());
@@ -281,7 +287,13 @@ fn start(current_dir: &Path, args: &Args, pb: &ProgressBar) -> Result<(), String
             .open(&output_cir_path_source)
             .map_err(|e| format!("Failed to create output file: {}", e))?;
 
-        file.write_all(cir.to_code_ir().as_bytes())
+        let source = if args.ranges {
+            RangeAnalysis::new(&cir).to_code_ir(&cir)
+        } else {
+            cir.to_code_ir()
+        };
+
+        file.write_all(source.as_bytes())
             .map_err(|e| format!("Failed to write cir to output file: {}", e))?;
 
         pb.println(format!(
@@ -305,11 +317,45 @@ fn start(current_dir: &Path, args: &Args, pb: &ProgressBar) -> Result<(), String
 }
 
 fn main() {
+    let args = Args::parse();
+
+    if let Some(command) = &args.command {
+        let result = match command {
+            CliCommand::Cost(cost_args) => commands::cost::run(cost_args),
+            CliCommand::Slice(slice_args) => commands::slice::run(slice_args),
+            CliCommand::Equiv(equiv_args) => commands::equiv::run(equiv_args),
+            CliCommand::R1cs(r1cs_args) => commands::r1cs::run(r1cs_args),
+            CliCommand::Circom(circom_args) => commands::circom::run(circom_args),
+            CliCommand::Plonky2(plonky2_args) => commands::plonky2::run(plonky2_args),
+            CliCommand::Witness(witness_args) => commands::witness::run(witness_args),
+            CliCommand::Fingerprint(fingerprint_args) => {
+                commands::fingerprint::run(fingerprint_args)
+            }
+            CliCommand::Query(query_args) => commands::query::run(query_args),
+            CliCommand::Lint(lint_args) => commands::lint::run(lint_args),
+            CliCommand::Tui(tui_args) => commands::tui::run(tui_args),
+            CliCommand::Reduce(reduce_args) => commands::reduce::run(reduce_args),
+            CliCommand::Report(report_args) => commands::report::run(report_args),
+        };
+
+        let _ = result.map_err(|e| {
+            eprintln!(
+                "{} {}",
+                get_formatted_left_output("Error", OutputColor::Red),
+                e
+            );
+
+            process::exit(1);
+        });
+
+        return;
+    }
+
     let current_dir = env::current_dir().expect("Failed to get current directory");
 
     let pb = &create_new_pb(7, "Running");
 
-    let _ = start(&current_dir, &Args::parse(), pb).map_err(|e| {
+    let _ = start(&current_dir, &args, pb).map_err(|e| {
         pb.abandon();
 
         eprintln!(
@@ -33,3 +33,66 @@ pub fn create_new_pb(length: u64, progress_message_left: &str) -> ProgressBar {
     );
     pb
 }
+
+/// Left-aligns the first column and right-aligns the rest, padding each column to its widest cell
+pub fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, width))| {
+                if i == 0 {
+                    format!("{cell:<width$}")
+                } else {
+                    format!("{cell:>width$}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    let mut lines = vec![format_row(headers.to_vec())];
+    lines.push(
+        widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<_>>()
+            .join("  "),
+    );
+    lines.extend(
+        rows.iter()
+            .map(|row| format_row(row.iter().map(String::as_str).collect())),
+    );
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table() {
+        let rows = [
+            vec!["main".to_string(), "12".to_string(), "3".to_string()],
+            vec!["poseidon".to_string(), "1024".to_string(), "".to_string()],
+        ];
+
+        assert_eq!(
+            format_table(&["gadget", "stmts", "degree"], &rows),
+            "gadget    stmts  degree\n\
+             --------  -----  ------\n\
+             main         12       3\n\
+             poseidon   1024        "
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, FoldingRange, FoldingRangeKind, NumberOrString, Position, Range,
+};
+use zkcir::{
+    analysis::{
+        eval::definitions,
+        lint::{lint, Severity},
+        range::RangeAnalysis,
+        Var,
+    },
+    ast::{Ident, Value},
+    parse::{parse, IdentOccurrence, ParseError, ParsedCir, Span},
+};
+
+/// Open `.cir` file along with what was parsed from it
+pub struct Document {
+    source: String,
+    parsed: Result<Parsed, ParseError>,
+}
+
+/// Analyses that only exist when the source parses
+struct Parsed {
+    cir: ParsedCir,
+
+    /// Statement giving each variable its value, see `zkcir::analysis::eval::definitions`
+    definitions: BTreeMap<Var, usize>,
+
+    ranges: RangeAnalysis,
+}
+
+impl Parsed {
+    fn occurrences(&self, var: &Var) -> Vec<&IdentOccurrence> {
+        self.cir
+            .idents
+            .iter()
+            .filter(|occurrence| Var::from(&occurrence.ident) == *var)
+            .collect()
+    }
+
+    /// Value written on any occurrence of `var`, since the source usually only writes it once
+    fn value(&self, var: &Var) -> Option<Value> {
+        self.occurrences(var)
+            .into_iter()
+            .find_map(|occurrence| match &occurrence.ident {
+                Ident::Wire(wire) => wire.value,
+                Ident::VirtualWire(virtual_wire) => virtual_wire.value,
+                Ident::String(_) => None,
+            })
+    }
+}
+
+impl Document {
+    pub fn new(source: String) -> Self {
+        let parsed = parse(&source).map(|cir| Parsed {
+            definitions: definitions(&cir.cir),
+            ranges: RangeAnalysis::new(&cir.cir),
+            cir,
+        });
+
+        Self { source, parsed }
+    }
+
+    /// Parse error if there is one, since nothing else can be reported without a parse, otherwise a finding of the
+    /// built-in lint rules on each statement they flag
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let parsed = match &self.parsed {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                return vec![Diagnostic {
+                    range: self.range(error.span),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("zkcir".to_string()),
+                    message: error.message.clone(),
+                    ..Diagnostic::default()
+                }]
+            }
+        };
+
+        lint(&parsed.cir.cir)
+            .into_iter()
+            .map(|finding| {
+                // Findings that aren't about a statement go at the end of the file
+                let span = parsed
+                    .cir
+                    .stmt_spans
+                    .get(finding.stmt_index)
+                    .copied()
+                    .unwrap_or(Span::new(self.source.len(), self.source.len()));
+
+                Diagnostic {
+                    range: self.range(span),
+                    severity: Some(match finding.severity {
+                        Severity::Info => DiagnosticSeverity::INFORMATION,
+                        Severity::Warning => DiagnosticSeverity::WARNING,
+                        Severity::Error => DiagnosticSeverity::ERROR,
+                    }),
+                    code: Some(NumberOrString::String(finding.rule)),
+                    source: Some("zkcir".to_string()),
+                    message: finding.message,
+                    ..Diagnostic::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Markdown describing the identifier at `position`: its type, value, range, annotation and defining statement
+    pub fn hover(&self, position: Position) -> Option<(Range, String)> {
+        let parsed = self.parsed.as_ref().ok()?;
+        let occurrence = parsed.cir.ident_at(self.offset(position))?;
+        let var = Var::from(&occurrence.ident);
+
+        let mut lines = vec![format!(
+            "```cir\n{}\n```",
+            &self.source[occurrence.span.start..occurrence.span.end]
+        )];
+
+        match &occurrence.ident {
+            Ident::String(_) => lines.push("local".to_string()),
+            Ident::Wire(wire) => lines.push(format!("{} wire", wire.wiretype)),
+            Ident::VirtualWire(virtual_wire) => {
+                lines.push(format!("{} virtual wire", virtual_wire.wiretype));
+            }
+        }
+
+        if let Some(value) = parsed.value(&var) {
+            lines.push(format!("value: `{value}`"));
+        }
+
+        if let Some(range) = parsed.ranges.range(var.clone()) {
+            lines.push(format!("range: `{range}`"));
+        }
+
+        if let Var::Wire(wire_ref) = &var {
+            if let Some(annotation) = parsed.cir.cir.annotation(*wire_ref) {
+                if let Some(name) = &annotation.name {
+                    lines.push(format!("name: `{name}`"));
+                }
+                if let Some(doc) = &annotation.doc {
+                    lines.push(doc.clone());
+                }
+                if !annotation.tags.is_empty() {
+                    lines.push(format!("tags: {}", annotation.tags.join(", ")));
+                }
+            }
+        }
+
+        match parsed.definitions.get(&var) {
+            Some(&stmt_index) => lines.push(format!(
+                "defined by stmt {stmt_index}:\n```cir\n{}\n```",
+                self.stmt_source(parsed, stmt_index)
+            )),
+            None => lines.push("input, not computed by the circuit".to_string()),
+        }
+
+        Some((self.range(occurrence.span), lines.join("\n\n")))
+    }
+
+    /// Where the identifier at `position` gets its value: the target of a local's `let`, or the wire in the
+    /// `verify!(x == e)` solving it
+    pub fn definition(&self, position: Position) -> Option<Range> {
+        let parsed = self.parsed.as_ref().ok()?;
+        let var = Var::from(&parsed.cir.ident_at(self.offset(position))?.ident);
+        let stmt_index = *parsed.definitions.get(&var)?;
+
+        let occurrences = parsed.occurrences(&var);
+        let definition = occurrences
+            .iter()
+            .find(|occurrence| occurrence.stmt_index == stmt_index && occurrence.is_definition)
+            .or_else(|| {
+                occurrences
+                    .iter()
+                    .find(|occurrence| occurrence.stmt_index == stmt_index)
+            })?;
+
+        Some(self.range(definition.span))
+    }
+
+    /// Every occurrence of the identifier at `position`, matching wires by coordinates
+    pub fn references(&self, position: Position, include_declaration: bool) -> Vec<Range> {
+        let Some(parsed) = self.parsed.as_ref().ok() else {
+            return Vec::new();
+        };
+        let Some(occurrence) = parsed.cir.ident_at(self.offset(position)) else {
+            return Vec::new();
+        };
+
+        parsed
+            .occurrences(&Var::from(&occurrence.ident))
+            .into_iter()
+            .filter(|occurrence| include_declaration || !occurrence.is_definition)
+            .map(|occurrence| self.range(occurrence.span))
+            .collect()
+    }
+
+    /// Header comments, statements spanning several lines, and parenthesized expressions within them spanning
+    /// several lines
+    pub fn folding_ranges(&self) -> Vec<FoldingRange> {
+        let mut ranges = Vec::new();
+
+        let mut comment_start = None;
+        for (line, text) in self.source.lines().chain([""]).enumerate() {
+            let line = u32::try_from(line).unwrap_or(u32::MAX);
+
+            match (text.trim_start().starts_with("//"), comment_start) {
+                (true, None) => comment_start = Some(line),
+                (false, Some(start)) => {
+                    if line - 1 > start {
+                        ranges.push(folding_range(start, line - 1, FoldingRangeKind::Comment));
+                    }
+                    comment_start = None;
+                }
+                _ => {}
+            }
+        }
+
+        let Ok(parsed) = &self.parsed else {
+            return ranges;
+        };
+
+        for span in &parsed.cir.stmt_spans {
+            self.push_folding_range(&mut ranges, *span);
+
+            let mut open = Vec::new();
+            let mut in_comment = false;
+
+            for (index, c) in self.source[span.start..span.end].char_indices() {
+                let offset = span.start + index;
+
+                match c {
+                    '\n' => in_comment = false,
+                    _ if in_comment => {}
+                    '/' if self.source[offset..].starts_with("//") => in_comment = true,
+                    '(' => open.push(offset),
+                    ')' => {
+                        if let Some(start) = open.pop() {
+                            self.push_folding_range(&mut ranges, Span::new(start, offset + 1));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        ranges
+    }
+
+    fn push_folding_range(&self, ranges: &mut Vec<FoldingRange>, span: Span) {
+        let range = self.range(span);
+
+        // The last line stays visible so the closing `)` or `;` isn't hidden. Editors fold by line, so only the
+        // outermost range starting on a line is kept.
+        let starts_line = ranges
+            .iter()
+            .any(|folding_range| folding_range.start_line == range.start.line);
+
+        if range.end.line > range.start.line + 1 && !starts_line {
+            ranges.push(folding_range(
+                range.start.line,
+                range.end.line - 1,
+                FoldingRangeKind::Region,
+            ));
+        }
+    }
+
+    fn stmt_source(&self, parsed: &Parsed, stmt_index: usize) -> &str {
+        let span = parsed.cir.stmt_spans[stmt_index];
+        &self.source[span.start..span.end]
+    }
+
+    /// Byte offset of an LSP position, which counts columns in UTF-16 code units
+    fn offset(&self, position: Position) -> usize {
+        let mut line_start = 0;
+        for _ in 0..position.line {
+            match self.source[line_start..].find('\n') {
+                Some(newline) => line_start += newline + 1,
+                None => return self.source.len(),
+            }
+        }
+
+        let mut column = 0;
+        for (index, c) in self.source[line_start..].char_indices() {
+            if column >= position.character as usize || c == '\n' {
+                return line_start + index;
+            }
+            column += c.len_utf16();
+        }
+
+        self.source.len()
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let before = &self.source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+        Position::new(
+            u32::try_from(before.matches('\n').count()).unwrap_or(u32::MAX),
+            u32::try_from(before[line_start..].encode_utf16().count()).unwrap_or(u32::MAX),
+        )
+    }
+
+    fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+}
+
+fn folding_range(start_line: u32, end_line: u32, kind: FoldingRangeKind) -> FoldingRange {
+    FoldingRange {
+        start_line,
+        end_line,
+        kind: Some(kind),
+        ..FoldingRange::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_diagnostic() {
+        let document = Document::new("let x = 1u64;\nverify!(x ==);\n".to_string());
+
+        let diagnostics = document.diagnostics();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostics[0].range.start.line, 1);
+    }
+
+    #[test]
+    fn test_lint_diagnostics() {
+        let document = Document::new(
+            "let x = 1u64;\nlet y = x / wire::private(row: 0, column: 0);\n".to_string(),
+        );
+
+        let diagnostics = document.diagnostics();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("division-by-zero".to_string()))
+        );
+        assert_eq!(
+            diagnostics[0].range,
+            Range::new(Position::new(1, 0), Position::new(1, 45))
+        );
+    }
+
+    #[test]
+    fn test_offset_and_position() {
+        // `é` is 2 bytes and 1 UTF-16 code unit, `𝔽` is 4 bytes and 2 code units
+        let document = Document::new("// é𝔽\nlet x = 1u64;".to_string());
+
+        assert_eq!(document.offset(Position::new(0, 4)), 5);
+        assert_eq!(document.offset(Position::new(0, 6)), 9);
+        assert_eq!(document.position(9), Position::new(0, 6));
+        assert_eq!(document.offset(Position::new(1, 4)), 14);
+        assert_eq!(document.position(14), Position::new(1, 4));
+
+        // Positions past the end of a line or the file clamp to it
+        assert_eq!(document.offset(Position::new(0, 40)), 9);
+        assert_eq!(document.offset(Position::new(5, 0)), document.source.len());
+    }
+}
@@ -0,0 +1,183 @@
+#![warn(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::too_many_lines)]
+
+use std::{collections::HashMap, error::Error};
+
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as NotificationTrait, PublishDiagnostics,
+    },
+    request::{
+        FoldingRangeRequest, GotoDefinition, HoverRequest, References, Request as RequestTrait,
+    },
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    FoldingRangeParams, FoldingRangeProviderCapability, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location,
+    MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams, ReferenceParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+use document::Document;
+
+mod document;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Language server for `.cir` files over stdio
+fn main() -> Result<(), BoxError> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut documents = HashMap::new();
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+
+                let response = handle_request(&documents, request)?;
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(uri) = handle_notification(&mut documents, notification)? {
+                    let diagnostics = documents
+                        .get(&uri)
+                        .map(Document::diagnostics)
+                        .unwrap_or_default();
+
+                    connection
+                        .sender
+                        .send(Message::Notification(Notification::new(
+                            PublishDiagnostics::METHOD.to_string(),
+                            PublishDiagnosticsParams::new(uri, diagnostics, None),
+                        )))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    // The writer thread only stops once every sender is dropped
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn handle_request(
+    documents: &HashMap<Url, Document>,
+    request: Request,
+) -> Result<Response, BoxError> {
+    let id = request.id.clone();
+
+    let result = match request.method.as_str() {
+        HoverRequest::METHOD => {
+            let params: HoverParams = serde_json::from_value(request.params)?;
+            let position = params.text_document_position_params;
+
+            let hover = documents
+                .get(&position.text_document.uri)
+                .and_then(|document| document.hover(position.position))
+                .map(|(range, markdown)| Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: markdown,
+                    }),
+                    range: Some(range),
+                });
+
+            serde_json::to_value(hover)?
+        }
+        GotoDefinition::METHOD => {
+            let params: GotoDefinitionParams = serde_json::from_value(request.params)?;
+            let position = params.text_document_position_params;
+            let uri = position.text_document.uri;
+
+            let definition = documents
+                .get(&uri)
+                .and_then(|document| document.definition(position.position))
+                .map(|range| GotoDefinitionResponse::Scalar(Location::new(uri.clone(), range)));
+
+            serde_json::to_value(definition)?
+        }
+        References::METHOD => {
+            let params: ReferenceParams = serde_json::from_value(request.params)?;
+            let position = params.text_document_position;
+            let uri = position.text_document.uri;
+
+            let references = documents.get(&uri).map(|document| {
+                document
+                    .references(position.position, params.context.include_declaration)
+                    .into_iter()
+                    .map(|range| Location::new(uri.clone(), range))
+                    .collect::<Vec<_>>()
+            });
+
+            serde_json::to_value(references)?
+        }
+        FoldingRangeRequest::METHOD => {
+            let params: FoldingRangeParams = serde_json::from_value(request.params)?;
+
+            let ranges = documents
+                .get(&params.text_document.uri)
+                .map(Document::folding_ranges);
+
+            serde_json::to_value(ranges)?
+        }
+        method => {
+            return Ok(Response::new_err(
+                id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{method}`"),
+            ))
+        }
+    };
+
+    Ok(Response::new_ok(id, result))
+}
+
+/// Updates `documents`, returning the uri of the document whose diagnostics changed
+fn handle_notification(
+    documents: &mut HashMap<Url, Document>,
+    notification: Notification,
+) -> Result<Option<Url>, BoxError> {
+    match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params: DidOpenTextDocumentParams = serde_json::from_value(notification.params)?;
+            let uri = params.text_document.uri;
+
+            documents.insert(uri.clone(), Document::new(params.text_document.text));
+            Ok(Some(uri))
+        }
+        DidChangeTextDocument::METHOD => {
+            let params: DidChangeTextDocumentParams = serde_json::from_value(notification.params)?;
+            let uri = params.text_document.uri;
+
+            // Full sync, so the last change is the whole text
+            if let Some(change) = params.content_changes.into_iter().last() {
+                documents.insert(uri.clone(), Document::new(change.text));
+            }
+            Ok(Some(uri))
+        }
+        DidCloseTextDocument::METHOD => {
+            let params: DidCloseTextDocumentParams = serde_json::from_value(notification.params)?;
+
+            documents.remove(&params.text_document.uri);
+            Ok(Some(params.text_document.uri))
+        }
+        _ => Ok(None),
+    }
+}
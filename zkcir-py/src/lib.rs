@@ -0,0 +1,770 @@
+//! Python bindings for `zkcir`, built with `maturin develop` in this directory
+//!
+//! Statements, expressions and wires are only wrapped when accessed, so scripts can walk a large circuit without
+//! converting all of it to Python objects first.
+
+#![warn(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::needless_pass_by_value)]
+// Raised on the code `#[pymethods]` generates for methods returning `PyResult`
+#![allow(clippy::useless_conversion)]
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use pyo3::{
+    exceptions::{PyIOError, PyIndexError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+use zkcir::{
+    analysis::Var,
+    ast::{Expression, Ident, Stmt, Value, VirtualWire, Wire, WireRef},
+    ir::{Cir, TranscriptOp},
+    node::Node,
+};
+
+/// Nodes and edges of a graph as `networkx` takes them in `add_nodes_from` and `add_edges_from`
+type Graph = (Vec<(String, Py<PyDict>)>, Vec<(String, String, Py<PyDict>)>);
+
+#[pyclass(module = "zkcir", name = "Cir", frozen)]
+struct PyCir {
+    cir: Arc<Cir>,
+}
+
+#[pymethods]
+impl PyCir {
+    /// Parses the json emitted by `zkcir --json`
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        Cir::from_json(json)
+            .map(Self::new)
+            .map_err(|e| PyValueError::new_err(format!("Failed to parse json CIR: {e}")))
+    }
+
+    /// Parses the source ir emitted by `zkcir --source`
+    #[staticmethod]
+    fn from_code_ir(source: &str) -> PyResult<Self> {
+        Cir::from_code_ir(source)
+            .map(Self::new)
+            .map_err(|e| PyValueError::new_err(format!("Failed to parse source ir at {e}")))
+    }
+
+    /// Reads a json ir, or a source ir if the extension is `cir`
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| PyIOError::new_err(format!("Failed to read {}: {e}", path.display())))?;
+
+        if is_code_ir(&path) {
+            Self::from_code_ir(&contents)
+        } else {
+            Self::from_json(&contents)
+        }
+    }
+
+    /// Writes a json ir, or a source ir if the extension is `cir`
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        let contents = if is_code_ir(&path) {
+            self.cir.to_code_ir()
+        } else {
+            self.to_json()?
+        };
+
+        fs::write(&path, contents)
+            .map_err(|e| PyIOError::new_err(format!("Failed to write {}: {e}", path.display())))
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        self.cir.to_string().map_err(PyValueError::new_err)
+    }
+
+    fn to_code_ir(&self) -> String {
+        self.cir.to_code_ir()
+    }
+
+    /// Digest that is equal for circuits differing only in local names, random values, operand order of commutative
+    /// operations and annotations
+    fn fingerprint(&self) -> PyResult<String> {
+        self.cir.fingerprint().map_err(PyValueError::new_err)
+    }
+
+    #[getter]
+    fn stmts(&self) -> PyStmts {
+        PyStmts {
+            cir: self.cir.clone(),
+        }
+    }
+
+    /// Every wire and virtual wire in order of first appearance
+    fn wires(&self) -> Vec<PyWire> {
+        let mut idents = Vec::new();
+        for stmt in &self.cir.stmts {
+            if let Stmt::Local(target, _) = stmt {
+                idents.push(target.clone());
+            }
+            collect_idents(stmt_expression(stmt), &mut idents);
+        }
+
+        dedup_by_var(idents)
+            .into_iter()
+            .filter_map(|ident| PyWire::new(&self.cir, ident))
+            .collect()
+    }
+
+    #[getter]
+    fn public_inputs(&self) -> Vec<PyWire> {
+        self.cir
+            .public_wire_inputs
+            .iter()
+            .map(|wire| Ident::Wire(*wire))
+            .chain(
+                self.cir
+                    .public_virtual_wire_inputs
+                    .iter()
+                    .map(|virtual_wire| Ident::VirtualWire(*virtual_wire)),
+            )
+            .filter_map(|ident| PyWire::new(&self.cir, ident))
+            .collect()
+    }
+
+    /// `(name, start, end)` of each gadget, where `end` is one past its last statement
+    #[getter]
+    fn gadgets(&self) -> Vec<(String, usize, usize)> {
+        self.cir
+            .gadgets
+            .iter()
+            .map(|gadget| (gadget.name.clone(), gadget.start, gadget.end))
+            .collect()
+    }
+
+    /// `(name, direction, wire)` of each public value in the order of the proof's public inputs, where `direction`
+    /// is `input` or `output`
+    #[getter]
+    fn signature(&self) -> Vec<(String, String, PyWire)> {
+        self.cir
+            .signature
+            .iter()
+            .filter_map(|public_value| {
+                let ident = match public_value.wire_ref {
+                    WireRef::Wire { row, column } => Ident::Wire(Wire::new_public(row, column)),
+                    WireRef::VirtualWire { index } => {
+                        Ident::VirtualWire(VirtualWire::new_public(index))
+                    }
+                };
+
+                Some((
+                    public_value.name.clone(),
+                    public_value.direction.to_string(),
+                    PyWire::new(&self.cir, ident)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// `(name, kind)` of each random source, where `kind` is `challenge`, `blinding` or `test_rng`
+    #[getter]
+    fn random_sources(&self) -> Vec<(String, String)> {
+        self.cir
+            .random_sources
+            .iter()
+            .map(|source| (source.name.clone(), source.kind.to_string()))
+            .collect()
+    }
+
+    /// Fiat-Shamir transcript operations in order, like `absorb!(wire::public(row: 0, column: 0))` or `squeeze!(0)`
+    #[getter]
+    fn transcript(&self) -> Vec<String> {
+        self.cir
+            .transcript
+            .iter()
+            .map(TranscriptOp::to_code_ir)
+            .collect()
+    }
+
+    /// Dependency graph with a node per statement, named like `stmt 3`, and per wire or local, named like its
+    /// source. Edges go from each variable to the statements reading it, and from a `let` to the variable it
+    /// assigns:
+    ///
+    /// ```python
+    /// graph = networkx.DiGraph()
+    /// nodes, edges = cir.graph()
+    /// graph.add_nodes_from(nodes)
+    /// graph.add_edges_from(edges)
+    /// ```
+    fn graph(&self, py: Python<'_>) -> PyResult<Graph> {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut vars = Vec::<Var>::new();
+
+        let mut add_var = |nodes: &mut Vec<_>, ident: &Ident| -> PyResult<String> {
+            let var = Var::from(ident);
+            let id = var.to_code_ir();
+
+            if !vars.contains(&var) {
+                let attrs = PyDict::new_bound(py);
+                match ident {
+                    Ident::String(_) => attrs.set_item("kind", "local")?,
+                    Ident::Wire(wire) => {
+                        attrs.set_item("kind", "wire")?;
+                        attrs.set_item("wiretype", wire.wiretype.to_string())?;
+                    }
+                    Ident::VirtualWire(virtual_wire) => {
+                        attrs.set_item("kind", "virtual_wire")?;
+                        attrs.set_item("wiretype", virtual_wire.wiretype.to_string())?;
+                    }
+                }
+                if let Var::Wire(wire_ref) = &var {
+                    if let Some(name) = self
+                        .cir
+                        .annotation(*wire_ref)
+                        .and_then(|annotation| annotation.name.clone())
+                    {
+                        attrs.set_item("name", name)?;
+                    }
+                }
+
+                nodes.push((id.clone(), attrs.unbind()));
+                vars.push(var);
+            }
+
+            Ok(id)
+        };
+
+        for (index, stmt) in self.cir.stmts.iter().enumerate() {
+            let stmt_id = format!("stmt {index}");
+
+            let attrs = PyDict::new_bound(py);
+            attrs.set_item("kind", "stmt")?;
+            attrs.set_item("index", index)?;
+            attrs.set_item("code", stmt.to_code_ir())?;
+            attrs.set_item("gadget", gadget(&self.cir, index))?;
+            nodes.push((stmt_id.clone(), attrs.unbind()));
+
+            let mut uses = Vec::new();
+            collect_idents(stmt_expression(stmt), &mut uses);
+
+            for ident in dedup_by_var(uses) {
+                let var_id = add_var(&mut nodes, &ident)?;
+                edges.push((var_id, stmt_id.clone(), edge_attrs(py, "use")?));
+            }
+
+            if let Stmt::Local(target, _) = stmt {
+                let var_id = add_var(&mut nodes, target)?;
+                edges.push((stmt_id.clone(), var_id, edge_attrs(py, "def")?));
+            }
+        }
+
+        Ok((nodes, edges))
+    }
+
+    fn __len__(&self) -> usize {
+        self.cir.stmts.len()
+    }
+
+    fn __eq__(&self, other: &Bound<'_, PyAny>) -> bool {
+        other
+            .downcast::<PyCir>()
+            .is_ok_and(|other| other.get().cir == self.cir)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Cir(stmts={}, wires={})",
+            self.cir.stmts.len(),
+            self.wires().len()
+        )
+    }
+}
+
+impl PyCir {
+    fn new(cir: Cir) -> Self {
+        Self { cir: Arc::new(cir) }
+    }
+}
+
+/// Statements of a circuit, wrapped as they're indexed
+#[pyclass(module = "zkcir", name = "Stmts", frozen, sequence)]
+struct PyStmts {
+    cir: Arc<Cir>,
+}
+
+#[pymethods]
+impl PyStmts {
+    fn __len__(&self) -> usize {
+        self.cir.stmts.len()
+    }
+
+    /// Negative indices count from the end
+    fn __getitem__(&self, index: isize) -> PyResult<PyStmt> {
+        let len = self.cir.stmts.len();
+        let resolved = if index < 0 {
+            len.checked_sub(index.unsigned_abs())
+        } else {
+            Some(index.unsigned_abs())
+        };
+
+        match resolved {
+            Some(index) if index < len => Ok(PyStmt {
+                cir: self.cir.clone(),
+                index,
+            }),
+            _ => Err(PyIndexError::new_err(format!(
+                "stmt index {index} out of range for {len} statements"
+            ))),
+        }
+    }
+
+    fn __iter__(&self) -> PyStmtIter {
+        PyStmtIter {
+            cir: self.cir.clone(),
+            next: 0,
+        }
+    }
+}
+
+#[pyclass(module = "zkcir", name = "StmtIter")]
+struct PyStmtIter {
+    cir: Arc<Cir>,
+    next: usize,
+}
+
+#[pymethods]
+impl PyStmtIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<PyStmt> {
+        if self.next >= self.cir.stmts.len() {
+            return None;
+        }
+
+        self.next += 1;
+        Some(PyStmt {
+            cir: self.cir.clone(),
+            index: self.next - 1,
+        })
+    }
+}
+
+#[pyclass(module = "zkcir", name = "Stmt", frozen)]
+struct PyStmt {
+    cir: Arc<Cir>,
+    index: usize,
+}
+
+#[pymethods]
+impl PyStmt {
+    /// Index into `Cir.stmts`
+    #[getter]
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    /// `"verify"` or `"local"`
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.stmt() {
+            Stmt::Verify(_) => "verify",
+            Stmt::Local(..) => "local",
+        }
+    }
+
+    /// Wire or local name a `let` assigns, or `None` for a `verify!`
+    #[getter]
+    fn target(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        match self.stmt() {
+            Stmt::Local(target, _) => var_object(py, &self.cir, target).map(Some),
+            Stmt::Verify(_) => Ok(None),
+        }
+    }
+
+    /// Expression a `verify!` asserts or a `let` assigns
+    #[getter]
+    fn expr(&self) -> PyExpression {
+        PyExpression {
+            cir: self.cir.clone(),
+            expr: stmt_expression(self.stmt()).clone(),
+        }
+    }
+
+    /// Name of the innermost gadget emitting this statement
+    #[getter]
+    fn gadget(&self) -> Option<String> {
+        gadget(&self.cir, self.index)
+    }
+
+    /// Wires and local names the statement reads, in order of first appearance
+    fn uses(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let mut idents = Vec::new();
+        collect_idents(stmt_expression(self.stmt()), &mut idents);
+
+        dedup_by_var(idents)
+            .iter()
+            .map(|ident| var_object(py, &self.cir, ident))
+            .collect()
+    }
+
+    fn to_code_ir(&self) -> String {
+        self.stmt().to_code_ir()
+    }
+
+    fn __str__(&self) -> String {
+        self.to_code_ir()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Stmt({}, {:?})", self.index, self.to_code_ir())
+    }
+}
+
+impl PyStmt {
+    fn stmt(&self) -> &Stmt {
+        &self.cir.stmts[self.index]
+    }
+}
+
+#[pyclass(module = "zkcir", name = "Expression", frozen)]
+struct PyExpression {
+    cir: Arc<Cir>,
+    expr: Expression,
+}
+
+#[pymethods]
+impl PyExpression {
+    /// `"binary"`, `"unary"`, `"ident"` or `"value"`
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match &self.expr {
+            Expression::BinaryOperator { .. } => "binary",
+            Expression::Unary { .. } => "unary",
+            Expression::Ident(_) => "ident",
+            Expression::Value(_) => "value",
+        }
+    }
+
+    /// Operator as written in source ir, like `"*"` or `"=="`
+    #[getter]
+    fn op(&self) -> Option<String> {
+        match &self.expr {
+            Expression::BinaryOperator { binop, .. } => Some(binop.to_string()),
+            Expression::Unary { op, .. } => Some(op.to_code_ir()),
+            Expression::Ident(_) | Expression::Value(_) => None,
+        }
+    }
+
+    #[getter]
+    fn lhs(&self) -> Option<PyExpression> {
+        match &self.expr {
+            Expression::BinaryOperator { lhs, .. } => Some(self.child(lhs)),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn rhs(&self) -> Option<PyExpression> {
+        match &self.expr {
+            Expression::BinaryOperator { rhs, .. } => Some(self.child(rhs)),
+            _ => None,
+        }
+    }
+
+    /// Operand of a unary expression
+    #[getter]
+    fn operand(&self) -> Option<PyExpression> {
+        match &self.expr {
+            Expression::Unary { expr, .. } => Some(self.child(expr)),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn children(&self) -> Vec<PyExpression> {
+        match &self.expr {
+            Expression::BinaryOperator { lhs, rhs, .. } => vec![self.child(lhs), self.child(rhs)],
+            Expression::Unary { expr, .. } => vec![self.child(expr)],
+            Expression::Ident(_) | Expression::Value(_) => Vec::new(),
+        }
+    }
+
+    /// Wire or local name of an ident expression
+    #[getter]
+    fn var(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        match &self.expr {
+            Expression::Ident(ident) => var_object(py, &self.cir, ident).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Value of a value expression, or `None` for one hidden as `random!()`
+    #[getter]
+    fn value(&self) -> Option<u64> {
+        match &self.expr {
+            Expression::Value(value) => value_int(*value),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn is_random(&self) -> bool {
+        matches!(self.expr, Expression::Value(value) if value.is_random())
+    }
+
+    /// Index into `Cir.random_sources` of the source a random value was drawn from, if the frontend tagged it
+    #[getter]
+    fn random_source(&self) -> Option<usize> {
+        match self.expr {
+            Expression::Value(Value::Sampled { source, .. } | Value::OmittedSample { source }) => {
+                Some(source)
+            }
+            _ => None,
+        }
+    }
+
+    /// Wires and local names read anywhere in the expression, in order of first appearance
+    fn vars(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let mut idents = Vec::new();
+        collect_idents(&self.expr, &mut idents);
+
+        dedup_by_var(idents)
+            .iter()
+            .map(|ident| var_object(py, &self.cir, ident))
+            .collect()
+    }
+
+    fn to_code_ir(&self) -> String {
+        self.expr.to_code_ir()
+    }
+
+    fn __str__(&self) -> String {
+        self.to_code_ir()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Expression({:?})", self.to_code_ir())
+    }
+}
+
+impl PyExpression {
+    fn child(&self, expr: &Expression) -> PyExpression {
+        PyExpression {
+            cir: self.cir.clone(),
+            expr: expr.clone(),
+        }
+    }
+}
+
+/// Wire or virtual wire. Equal to another if they have the same coordinates, regardless of value or type.
+#[pyclass(module = "zkcir", name = "Wire", frozen)]
+struct PyWire {
+    cir: Arc<Cir>,
+
+    /// Always an `Ident::Wire` or `Ident::VirtualWire`
+    ident: Ident,
+}
+
+#[pymethods]
+impl PyWire {
+    #[getter]
+    fn row(&self) -> Option<usize> {
+        match self.wire_ref() {
+            WireRef::Wire { row, .. } => Some(row),
+            WireRef::VirtualWire { .. } => None,
+        }
+    }
+
+    #[getter]
+    fn column(&self) -> Option<usize> {
+        match self.wire_ref() {
+            WireRef::Wire { column, .. } => Some(column),
+            WireRef::VirtualWire { .. } => None,
+        }
+    }
+
+    /// Index of a virtual wire
+    #[getter]
+    fn index(&self) -> Option<usize> {
+        match self.wire_ref() {
+            WireRef::VirtualWire { index } => Some(index),
+            WireRef::Wire { .. } => None,
+        }
+    }
+
+    #[getter]
+    fn is_virtual(&self) -> bool {
+        matches!(self.ident, Ident::VirtualWire(_))
+    }
+
+    /// `"public"`, `"private"` or `"const"`
+    #[getter]
+    fn wiretype(&self) -> String {
+        match &self.ident {
+            Ident::Wire(wire) => wire.wiretype.to_string(),
+            Ident::VirtualWire(virtual_wire) => virtual_wire.wiretype.to_string(),
+            Ident::String(_) => unreachable!("`PyWire` only wraps wires"),
+        }
+    }
+
+    /// Value the wire was assigned when the ir was emitted, or `None` if unknown or hidden as `random!()`
+    #[getter]
+    fn value(&self) -> Option<u64> {
+        match &self.ident {
+            Ident::Wire(wire) => wire.value.and_then(value_int),
+            Ident::VirtualWire(virtual_wire) => virtual_wire.value.and_then(value_int),
+            Ident::String(_) => None,
+        }
+    }
+
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.cir
+            .annotation(self.wire_ref())
+            .and_then(|annotation| annotation.name.clone())
+    }
+
+    #[getter]
+    fn doc(&self) -> Option<String> {
+        self.cir
+            .annotation(self.wire_ref())
+            .and_then(|annotation| annotation.doc.clone())
+    }
+
+    #[getter]
+    fn tags(&self) -> Vec<String> {
+        self.cir
+            .annotation(self.wire_ref())
+            .map(|annotation| annotation.tags.clone())
+            .unwrap_or_default()
+    }
+
+    fn to_code_ir(&self) -> String {
+        self.ident.to_code_ir()
+    }
+
+    fn __str__(&self) -> String {
+        self.to_code_ir()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Wire({:?})", self.to_code_ir())
+    }
+
+    fn __eq__(&self, other: &Bound<'_, PyAny>) -> bool {
+        other
+            .downcast::<PyWire>()
+            .is_ok_and(|other| other.get().wire_ref() == self.wire_ref())
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self.wire_ref() {
+            WireRef::Wire { row, column } => (0, row, column).hash(&mut hasher),
+            WireRef::VirtualWire { index } => (1, index, 0).hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+}
+
+impl PyWire {
+    /// `None` for a local
+    fn new(cir: &Arc<Cir>, ident: Ident) -> Option<Self> {
+        match ident {
+            Ident::Wire(_) | Ident::VirtualWire(_) => Some(Self {
+                cir: cir.clone(),
+                ident,
+            }),
+            Ident::String(_) => None,
+        }
+    }
+
+    fn wire_ref(&self) -> WireRef {
+        match Var::from(&self.ident) {
+            Var::Wire(wire_ref) => wire_ref,
+            Var::Local(_) => unreachable!("`PyWire` only wraps wires"),
+        }
+    }
+}
+
+/// `Wire` for a wire, or `str` for a local name
+fn var_object(py: Python<'_>, cir: &Arc<Cir>, ident: &Ident) -> PyResult<PyObject> {
+    match PyWire::new(cir, ident.clone()) {
+        Some(wire) => Ok(Py::new(py, wire)?.into_py(py)),
+        None => Ok(Var::from(ident).to_code_ir().into_py(py)),
+    }
+}
+
+fn edge_attrs(py: Python<'_>, kind: &str) -> PyResult<Py<PyDict>> {
+    let attrs = PyDict::new_bound(py);
+    attrs.set_item("kind", kind)?;
+    Ok(attrs.unbind())
+}
+
+fn stmt_expression(stmt: &Stmt) -> &Expression {
+    match stmt {
+        Stmt::Verify(expr) | Stmt::Local(_, expr) => expr,
+    }
+}
+
+fn collect_idents(expr: &Expression, idents: &mut Vec<Ident>) {
+    match expr {
+        Expression::BinaryOperator { lhs, rhs, .. } => {
+            collect_idents(lhs, idents);
+            collect_idents(rhs, idents);
+        }
+        Expression::Unary { expr, .. } => collect_idents(expr, idents),
+        Expression::Ident(ident) => idents.push(ident.clone()),
+        Expression::Value(_) => {}
+    }
+}
+
+/// First occurrence of each variable
+fn dedup_by_var(idents: Vec<Ident>) -> Vec<Ident> {
+    let mut vars = Vec::new();
+
+    idents
+        .into_iter()
+        .filter(|ident| {
+            let var = Var::from(ident);
+            if vars.contains(&var) {
+                false
+            } else {
+                vars.push(var);
+                true
+            }
+        })
+        .collect()
+}
+
+/// Innermost gadget containing the statement at `index`
+fn gadget(cir: &Cir, index: usize) -> Option<String> {
+    cir.gadgets
+        .iter()
+        .filter(|gadget| gadget.start <= index && index < gadget.end)
+        .min_by_key(|gadget| gadget.end - gadget.start)
+        .map(|gadget| gadget.name.clone())
+}
+
+fn value_int(value: Value) -> Option<u64> {
+    value.as_u64()
+}
+
+fn is_code_ir(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "cir")
+}
+
+#[pymodule]
+#[pyo3(name = "zkcir")]
+fn zkcir_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyCir>()?;
+    module.add_class::<PyStmts>()?;
+    module.add_class::<PyStmt>()?;
+    module.add_class::<PyExpression>()?;
+    module.add_class::<PyWire>()?;
+    Ok(())
+}
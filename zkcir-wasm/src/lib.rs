@@ -0,0 +1,243 @@
+//! JavaScript bindings for `zkcir`, built with `wasm-pack build zkcir-wasm`
+//!
+//! The TypeScript declarations of the serde format (`Cir`, `Stmt`, `Finding`, ...) are derived from the Rust types, so
+//! they can't drift from what `Cir::to_string` emits.
+
+#![warn(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::needless_pass_by_value)]
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+use zkcir::{
+    analysis::{
+        cost::CostReport,
+        lint::Finding,
+        range::RangeAnalysis,
+        rules::{lint_with_rules, RuleSet},
+    },
+    ir::Cir,
+    parse::parse,
+};
+
+/// Circuit kept in wasm memory. Values too large for a JavaScript number only survive through `toJson` and
+/// `toCodeIr`, since `toObject` fails on them.
+#[wasm_bindgen]
+pub struct Circuit {
+    cir: Cir,
+}
+
+#[wasm_bindgen]
+impl Circuit {
+    /// Parses the json emitted by `zkcir --json`
+    ///
+    /// # Errors
+    ///
+    /// Errors if `json` isn't a json ir
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<Circuit, JsError> {
+        Ok(Self {
+            cir: Cir::from_json(json)?,
+        })
+    }
+
+    /// Parses the source ir emitted by `zkcir --source`
+    ///
+    /// # Errors
+    ///
+    /// Errors with the location of the first syntax error
+    #[wasm_bindgen(js_name = fromCodeIr)]
+    pub fn from_code_ir(source: &str) -> Result<Circuit, JsError> {
+        Ok(Self {
+            cir: Cir::from_code_ir(source).map_err(|e| JsError::new(&e.to_string()))?,
+        })
+    }
+
+    #[wasm_bindgen(js_name = fromObject)]
+    #[must_use]
+    pub fn from_object(cir: Cir) -> Circuit {
+        Self { cir }
+    }
+
+    /// Serde form of the circuit. Throws if a value doesn't fit in a JavaScript number
+    #[wasm_bindgen(js_name = toObject)]
+    #[must_use]
+    pub fn to_object(&self) -> Cir {
+        self.cir.clone()
+    }
+
+    /// # Errors
+    ///
+    /// Errors if serializing fails
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        self.cir.to_string().map_err(JsError::new)
+    }
+
+    #[wasm_bindgen(js_name = toCodeIr)]
+    #[must_use]
+    pub fn to_code_ir(&self) -> String {
+        self.cir.to_code_ir()
+    }
+
+    /// Source ir annotated with the value range inferred for each wire and local
+    #[wasm_bindgen(js_name = toCodeIrWithRanges)]
+    #[must_use]
+    pub fn to_code_ir_with_ranges(&self) -> String {
+        RangeAnalysis::new(&self.cir).to_code_ir(&self.cir)
+    }
+
+    /// See `Cir::fingerprint`
+    ///
+    /// # Errors
+    ///
+    /// Errors if serializing the canonical form fails
+    pub fn fingerprint(&self) -> Result<String, JsError> {
+        self.cir.fingerprint().map_err(JsError::new)
+    }
+
+    /// Built-in lints, plus `rules` if given, sorted by statement
+    ///
+    /// # Errors
+    ///
+    /// Errors on the first rule with an invalid pattern
+    pub fn lint(&self, rules: Option<RuleSet>) -> Result<Findings, JsError> {
+        lint_with_rules(&self.cir, &rules.unwrap_or_default())
+            .map(Findings)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[must_use]
+    pub fn cost(&self) -> CostReport {
+        CostReport::new(&self.cir)
+    }
+}
+
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct Findings(pub Vec<Finding>);
+
+/// Where and why an ir is invalid, with a 1-based line and column
+#[derive(Serialize, Deserialize, Tsify)]
+pub struct ValidationError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Empty when the ir is valid
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+/// Checks that `json` is a json ir without throwing
+#[wasm_bindgen]
+#[must_use]
+pub fn validate(json: &str) -> ValidationErrors {
+    ValidationErrors(match Cir::from_json(json) {
+        Ok(_) => Vec::new(),
+        Err(e) => vec![ValidationError {
+            line: e.line(),
+            column: e.column(),
+            message: e.to_string(),
+        }],
+    })
+}
+
+/// Checks that `source` is a source ir without throwing
+#[wasm_bindgen(js_name = validateCodeIr)]
+#[must_use]
+pub fn validate_code_ir(source: &str) -> ValidationErrors {
+    ValidationErrors(match parse(source) {
+        Ok(_) => Vec::new(),
+        Err(e) => vec![ValidationError {
+            line: e.line,
+            column: e.column,
+            message: e.message,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use zkcir::analysis::{
+        lint::Severity,
+        rules::{Allow, Rule},
+    };
+
+    use super::*;
+
+    const SOURCE: &str = "let x = 1u64 / wire::public(row: 0, column: 0);\n\nverify!(x == 3u64);";
+
+    #[test]
+    fn test_round_trip() {
+        let circuit = Circuit::from_code_ir(SOURCE).unwrap();
+        assert_eq!(circuit.to_code_ir(), SOURCE);
+
+        let json = circuit.to_json().unwrap();
+        let from_json = Circuit::from_json(&json).unwrap();
+        assert_eq!(from_json.to_object(), circuit.to_object());
+        assert_eq!(
+            Circuit::from_object(circuit.to_object())
+                .fingerprint()
+                .unwrap(),
+            circuit.to_object().fingerprint().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_analyses() {
+        let circuit = Circuit::from_code_ir(SOURCE).unwrap();
+
+        assert_eq!(circuit.cost(), CostReport::new(&circuit.to_object()));
+        assert!(circuit
+            .to_code_ir_with_ranges()
+            .contains("let x = 1u64 / wire::public(row: 0, column: 0);"));
+
+        let rules = RuleSet {
+            rules: vec![Rule {
+                name: "public-division".to_string(),
+                pattern: "$a / $b:wire".to_string(),
+                message: "divides by $b".to_string(),
+                severity: Severity::Error,
+                explanation: String::new(),
+                allow: Allow::default(),
+            }],
+        };
+
+        let builtin = circuit.lint(None).unwrap().0;
+        let with_rules = circuit.lint(Some(rules)).unwrap().0;
+        assert_eq!(
+            builtin
+                .iter()
+                .map(|finding| finding.rule.as_str())
+                .collect::<Vec<_>>(),
+            ["division-by-zero"]
+        );
+        assert_eq!(with_rules.len(), 2);
+        assert!(with_rules
+            .iter()
+            .any(|finding| finding.rule == "public-division"
+                && finding.message == "divides by wire::public(row: 0, column: 0)"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(
+            validate(&Circuit::from_code_ir(SOURCE).unwrap().to_json().unwrap())
+                .0
+                .is_empty()
+        );
+
+        let errors = validate("{\n  \"stmts\": 3\n}").0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (2, 12));
+
+        assert!(validate_code_ir(SOURCE).0.is_empty());
+
+        let errors = validate_code_ir("let x = 1u64;\nverify!(x ==);\n").0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 2);
+    }
+}
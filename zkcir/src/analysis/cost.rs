@@ -0,0 +1,343 @@
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{BinOp, Expression, Ident, Stmt, Value, WireRef, Wiretype};
+use crate::ir::Cir;
+
+/// Algebraic degree of a single `Stmt::Verify`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct VerifyDegree {
+    /// Index into `Cir::stmts`
+    pub stmt_index: usize,
+    pub degree: u64,
+}
+
+/// Number of distinct wires and virtual wires of each `Wiretype`
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct WireCounts {
+    pub public: usize,
+    pub private: usize,
+    pub constant: usize,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct Cost {
+    pub num_verifies: usize,
+    pub num_locals: usize,
+
+    /// Highest degree over all `Stmt::Verify`. For plonky2 this bounds the gate degree and for halo2 this drives the
+    /// extended domain size
+    pub max_degree: u64,
+
+    /// Number of `Stmt::Verify` per degree
+    pub degree_histogram: BTreeMap<u64, usize>,
+
+    pub multiplications: usize,
+
+    /// Additions and subtractions
+    pub additions: usize,
+
+    pub divisions: usize,
+    pub exponentiations: usize,
+    pub wires: WireCounts,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct GadgetCost {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub cost: Cost,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify), tsify(into_wasm_abi))]
+pub struct CostReport {
+    pub circuit: Cost,
+    pub gadgets: Vec<GadgetCost>,
+    pub verify_degrees: Vec<VerifyDegree>,
+}
+
+impl CostReport {
+    #[must_use]
+    pub fn new(cir: &Cir) -> Self {
+        let stmt_degrees = stmt_degrees(cir);
+
+        let verify_degrees = cir
+            .stmts
+            .iter()
+            .zip(&stmt_degrees)
+            .enumerate()
+            .filter(|(_, (stmt, _))| matches!(stmt, Stmt::Verify(_)))
+            .map(|(stmt_index, (_, &degree))| VerifyDegree { stmt_index, degree })
+            .collect();
+
+        // Gadgets can come from deserialized input, so clamp their bounds to the statements that exist
+        let gadgets = cir
+            .gadgets
+            .iter()
+            .map(|gadget| {
+                let end = gadget.end.min(cir.stmts.len());
+                let start = gadget.start.min(end);
+
+                GadgetCost {
+                    name: gadget.name.clone(),
+                    start,
+                    end,
+                    cost: Cost::new(cir, &stmt_degrees, start, end),
+                }
+            })
+            .collect();
+
+        Self {
+            circuit: Cost::new(cir, &stmt_degrees, 0, cir.stmts.len()),
+            gadgets,
+            verify_degrees,
+        }
+    }
+}
+
+impl Cost {
+    fn new(cir: &Cir, stmt_degrees: &[u64], start: usize, end: usize) -> Self {
+        let mut cost = Cost::default();
+        let mut wires = BTreeMap::new();
+
+        for (stmt, &degree) in cir.stmts[start..end].iter().zip(&stmt_degrees[start..end]) {
+            match stmt {
+                Stmt::Verify(expr) => {
+                    cost.num_verifies += 1;
+                    cost.max_degree = cost.max_degree.max(degree);
+                    *cost.degree_histogram.entry(degree).or_default() += 1;
+                    cost.count_operations(expr, &mut wires);
+                }
+                Stmt::Local(ident, expr) => {
+                    cost.num_locals += 1;
+                    count_ident_wire(ident, &mut wires);
+                    cost.count_operations(expr, &mut wires);
+                }
+            }
+        }
+
+        for wiretype in wires.values() {
+            match wiretype {
+                Wiretype::Public => cost.wires.public += 1,
+                Wiretype::Private => cost.wires.private += 1,
+                Wiretype::Constant => cost.wires.constant += 1,
+            }
+        }
+
+        cost
+    }
+
+    fn count_operations(&mut self, expr: &Expression, wires: &mut BTreeMap<WireRef, Wiretype>) {
+        match expr {
+            Expression::BinaryOperator { lhs, binop, rhs } => {
+                match binop {
+                    BinOp::Multiply => self.multiplications += 1,
+                    BinOp::Add | BinOp::Subtract => self.additions += 1,
+                    BinOp::Divide => self.divisions += 1,
+                    BinOp::Exponent => self.exponentiations += 1,
+                    _ => {}
+                }
+
+                self.count_operations(lhs, wires);
+                self.count_operations(rhs, wires);
+            }
+            Expression::Ident(ident) => count_ident_wire(ident, wires),
+            Expression::Value(_) => {}
+            Expression::Unary { expr, .. } => self.count_operations(expr, wires),
+        }
+    }
+}
+
+fn count_ident_wire(ident: &Ident, wires: &mut BTreeMap<WireRef, Wiretype>) {
+    match ident {
+        Ident::Wire(wire) => {
+            wires.insert(wire.wire_ref(), wire.wiretype);
+        }
+        Ident::VirtualWire(virtual_wire) => {
+            wires.insert(virtual_wire.wire_ref(), virtual_wire.wiretype);
+        }
+        Ident::String(_) => {}
+    }
+}
+
+/// Degree of every statement's expression, in the same order as `Cir::stmts`. Locals named by strings are inlined
+/// into their uses, while wires are always degree 1 since they are committed to.
+#[must_use]
+pub fn stmt_degrees(cir: &Cir) -> Vec<u64> {
+    let mut locals = BTreeMap::new();
+
+    cir.stmts
+        .iter()
+        .map(|stmt| match stmt {
+            Stmt::Verify(expr) => expression_degree(expr, &locals),
+            Stmt::Local(ident, expr) => {
+                let degree = expression_degree(expr, &locals);
+
+                if let Ident::String(name) = ident {
+                    locals.insert(name.clone(), degree);
+                }
+
+                degree
+            }
+        })
+        .collect()
+}
+
+/// Degree of `expr` where `locals` maps local names to the degree of their definitions. Division is treated as
+/// multiplication by the inverse, and exponents that aren't constant are ignored.
+#[must_use]
+pub fn expression_degree(expr: &Expression, locals: &BTreeMap<String, u64>) -> u64 {
+    match expr {
+        Expression::BinaryOperator { lhs, binop, rhs } => {
+            let lhs_degree = expression_degree(lhs, locals);
+            let rhs_degree = expression_degree(rhs, locals);
+
+            match binop {
+                BinOp::Multiply | BinOp::Divide => lhs_degree.saturating_add(rhs_degree),
+                BinOp::Exponent => match **rhs {
+                    Expression::Value(Value::U64(exponent)) => lhs_degree.saturating_mul(exponent),
+                    _ => lhs_degree,
+                },
+                _ => lhs_degree.max(rhs_degree),
+            }
+        }
+        Expression::Ident(Ident::String(name)) => locals.get(name).copied().unwrap_or(1),
+        Expression::Ident(_) => 1,
+        Expression::Value(_) => 0,
+        Expression::Unary { expr, .. } => expression_degree(expr, locals),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+    use alloc::vec;
+
+    use crate::{
+        ast::{VirtualWire, Wire},
+        ir::{CirBuilder, Gadget},
+    };
+
+    use super::*;
+
+    fn mul(lhs: Expression, rhs: Expression) -> Expression {
+        Expression::BinaryOperator {
+            lhs: Box::new(lhs),
+            binop: BinOp::Multiply,
+            rhs: Box::new(rhs),
+        }
+    }
+
+    fn eq(lhs: Expression, rhs: Expression) -> Expression {
+        Expression::BinaryOperator {
+            lhs: Box::new(lhs),
+            binop: BinOp::Equal,
+            rhs: Box::new(rhs),
+        }
+    }
+
+    #[test]
+    fn test_cost_report() {
+        let cir = CirBuilder::new()
+            .begin_gadget("square")
+            .add_stmt(Stmt::Local(
+                "square".into(),
+                mul(
+                    Wire::new_private(0, 0).into(),
+                    Wire::new_private(0, 0).into(),
+                ),
+            ))
+            .end_gadget()
+            .begin_gadget("cube")
+            .add_stmt(Stmt::Verify(eq(
+                mul(
+                    Expression::Ident("square".into()),
+                    Wire::new_private(0, 0).into(),
+                ),
+                VirtualWire::new_public(1).into(),
+            )))
+            .end_gadget()
+            .add_stmt(Stmt::Verify(eq(
+                Wire::new_constant(2, 0).into(),
+                Expression::Value(Value::U64(3)),
+            )))
+            .build();
+
+        let report = CostReport::new(&cir);
+
+        assert_eq!(
+            report.verify_degrees,
+            [
+                VerifyDegree {
+                    stmt_index: 1,
+                    degree: 3
+                },
+                VerifyDegree {
+                    stmt_index: 2,
+                    degree: 1
+                }
+            ]
+        );
+        assert_eq!(report.circuit.max_degree, 3);
+        assert_eq!(report.circuit.multiplications, 2);
+        assert_eq!(
+            report.circuit.wires,
+            WireCounts {
+                public: 1,
+                private: 1,
+                constant: 1
+            }
+        );
+
+        assert_eq!(report.gadgets.len(), 2);
+        assert_eq!(report.gadgets[0].cost.num_locals, 1);
+        assert_eq!(report.gadgets[0].cost.num_verifies, 0);
+        assert_eq!(report.gadgets[1].cost.max_degree, 3);
+        assert_eq!(report.gadgets[1].cost.wires.public, 1);
+    }
+
+    #[test]
+    fn test_out_of_range_gadgets() {
+        let mut cir = CirBuilder::new()
+            .add_stmt(Stmt::Verify(eq(
+                Wire::new_private(0, 0).into(),
+                Expression::Value(Value::U64(3)),
+            )))
+            .build();
+        cir.gadgets = vec![
+            Gadget {
+                name: "past_end".into(),
+                start: 0,
+                end: 5,
+            },
+            Gadget {
+                name: "reversed".into(),
+                start: 3,
+                end: 1,
+            },
+        ];
+
+        let report = CostReport::new(&cir);
+
+        assert_eq!((report.gadgets[0].start, report.gadgets[0].end), (0, 1));
+        assert_eq!(report.gadgets[0].cost.num_verifies, 1);
+        assert_eq!((report.gadgets[1].start, report.gadgets[1].end), (1, 1));
+        assert_eq!(report.gadgets[1].cost, Cost::default());
+    }
+}
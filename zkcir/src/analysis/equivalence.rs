@@ -0,0 +1,688 @@
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_more::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::eval::{
+    check_field, constant_value, evaluate, free_vars, solvable_equality, EvalError, Fp,
+    GOLDILOCKS_ORDER,
+};
+use crate::analysis::range::constant;
+use crate::analysis::{public_values, Var};
+use crate::ast::{BinOp, Expression, Op, Stmt, Value, WireRef};
+use crate::ir::Cir;
+
+/// Largest constant exponent expanded into multiplications in SMT queries
+const MAX_SMT_EXPONENT: u64 = 64;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EquivalenceOptions {
+    /// Number of random witnesses to try. The first two are all zeros and all ones.
+    pub trials: usize,
+
+    /// Seed for the witness generator, so results are reproducible
+    pub seed: u64,
+}
+
+impl Default for EquivalenceOptions {
+    fn default() -> Self {
+        Self {
+            trials: 64,
+            seed: 0x5EED,
+        }
+    }
+}
+
+/// Witness two circuits disagree on
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Counterexample {
+    /// Input values given to the first circuit
+    pub inputs_a: BTreeMap<Var, Fp>,
+
+    /// Input values given to the second circuit. Matched inputs share their value with `inputs_a`.
+    pub inputs_b: BTreeMap<Var, Fp>,
+
+    pub a_accepts: bool,
+    pub b_accepts: bool,
+
+    /// Public inputs of the first circuit that end with a different value than their match in the second, while both
+    /// circuits accept
+    pub differing_public_inputs: Vec<WireRef>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Equivalence {
+    /// No distinguishing witness was found in `trials` evaluations. This is evidence, not a proof.
+    ProbablyEquivalent {
+        trials: usize,
+    },
+    Different(Box<Counterexample>),
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Display)]
+pub enum EquivalenceError {
+    #[display(fmt = "circuits have a different number of public inputs ({a} and {b})")]
+    PublicInputCount { a: usize, b: usize },
+
+    #[display(fmt = "{_0}")]
+    Eval(EvalError),
+
+    #[display(fmt = "stmt {stmt_index} uses {operation}, which has no SMT encoding")]
+    UnsupportedSmt {
+        stmt_index: usize,
+        operation: String,
+    },
+}
+
+impl From<EvalError> for EquivalenceError {
+    fn from(error: EvalError) -> Self {
+        EquivalenceError::Eval(error)
+    }
+}
+
+/// Inputs of two circuits that receive the same value
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InputMatching {
+    /// Public inputs of the first circuit and their match in the second
+    pub public: Vec<(WireRef, WireRef)>,
+
+    /// Other free variables of the first circuit and their match in the second
+    pub private: Vec<(Var, Var)>,
+}
+
+/// Pairs the inputs of `a` and `b`. Public inputs are paired by annotated name when both circuits name them, and
+/// the rest by position. Other free variables (see `eval::free_vars`) are paired by annotated name, then by identical
+/// coordinates or local name. Unpaired free variables are given independent values.
+///
+/// # Errors
+///
+/// Errors if the circuits have a different number of public inputs
+pub fn match_inputs(a: &Cir, b: &Cir) -> Result<InputMatching, EquivalenceError> {
+    let (public_a, public_b) = (public_values(a), public_values(b));
+
+    if public_a.len() != public_b.len() {
+        return Err(EquivalenceError::PublicInputCount {
+            a: public_a.len(),
+            b: public_b.len(),
+        });
+    }
+
+    let name = |cir: &Cir, wire_ref: WireRef| {
+        cir.annotation(wire_ref)
+            .and_then(|annotation| annotation.name.clone())
+    };
+
+    let mut public = Vec::new();
+    let mut unmatched_b = public_b.clone();
+
+    for &wire_a in &public_a {
+        let by_name = name(a, wire_a).and_then(|name_a| {
+            unmatched_b
+                .iter()
+                .position(|&wire_b| name(b, wire_b).as_ref() == Some(&name_a))
+        });
+
+        if let Some(position) = by_name {
+            public.push((wire_a, unmatched_b.remove(position)));
+        }
+    }
+
+    let mut remaining_b = unmatched_b.into_iter();
+    for &wire_a in &public_a {
+        if !public.iter().any(|(matched, _)| *matched == wire_a) {
+            if let Some(wire_b) = remaining_b.next() {
+                public.push((wire_a, wire_b));
+            }
+        }
+    }
+
+    let public_vars = |wires: &[WireRef]| wires.iter().copied().map(Var::Wire).collect::<Vec<_>>();
+    let (public_vars_a, public_vars_b) = (public_vars(&public_a), public_vars(&public_b));
+
+    let free_a = free_vars(a)
+        .into_iter()
+        .filter(|var| !public_vars_a.contains(var))
+        .collect::<Vec<_>>();
+    let mut free_b = free_vars(b)
+        .into_iter()
+        .filter(|var| !public_vars_b.contains(var))
+        .collect::<Vec<_>>();
+
+    let mut private = Vec::new();
+    for var_a in free_a {
+        let named = match &var_a {
+            Var::Wire(wire_a) => name(a, *wire_a).and_then(|name_a| {
+                free_b.iter().position(|var_b| match var_b {
+                    Var::Wire(wire_b) => name(b, *wire_b).as_ref() == Some(&name_a),
+                    Var::Local(_) => false,
+                })
+            }),
+            Var::Local(_) => None,
+        };
+
+        if let Some(position) = named.or_else(|| free_b.iter().position(|var_b| *var_b == var_a)) {
+            private.push((var_a, free_b.remove(position)));
+        }
+    }
+
+    Ok(InputMatching { public, private })
+}
+
+/// Checks whether `a` and `b` accept the same witnesses by evaluating both over the Goldilocks field on random
+/// inputs. Inputs paired by `match_inputs` share a value, and public inputs `b` reads before solving take the final
+/// value from `a`. Circuits differ on a witness when exactly one accepts it, or both accept but a matched public input
+/// ends with different values.
+///
+/// # Errors
+///
+/// Errors if the public inputs can't be matched or a circuit can't be evaluated
+pub fn check_equivalence(
+    a: &Cir,
+    b: &Cir,
+    options: &EquivalenceOptions,
+) -> Result<Equivalence, EquivalenceError> {
+    check_field(a)?;
+    check_field(b)?;
+
+    let matching = match_inputs(a, b)?;
+    let (free_a, free_b) = (free_vars(a), free_vars(b));
+    let mut rng = SplitMix64(options.seed);
+
+    for trial in 0..options.trials {
+        let mut sample = || match trial {
+            0 => Fp::ZERO,
+            1 => Fp::ONE,
+            _ => Fp::new(rng.next()),
+        };
+
+        let inputs_a = free_a
+            .iter()
+            .map(|var| (var.clone(), sample()))
+            .collect::<BTreeMap<_, _>>();
+        let evaluation_a = evaluate(a, &inputs_a)?;
+
+        let mut inputs_b = free_b
+            .iter()
+            .map(|var| (var.clone(), sample()))
+            .collect::<BTreeMap<_, _>>();
+
+        let shared = matching
+            .public
+            .iter()
+            .map(|(wire_a, wire_b)| (Var::Wire(*wire_a), Var::Wire(*wire_b)))
+            .chain(matching.private.iter().cloned());
+
+        for (var_a, var_b) in shared {
+            if let (Some(value), Some(input)) =
+                (evaluation_a.values.get(&var_a), inputs_b.get_mut(&var_b))
+            {
+                *input = *value;
+            }
+        }
+
+        let evaluation_b = evaluate(b, &inputs_b)?;
+        let (a_accepts, b_accepts) = (evaluation_a.accepts(), evaluation_b.accepts());
+
+        let differing_public_inputs = if a_accepts && b_accepts {
+            matching
+                .public
+                .iter()
+                .filter(|(wire_a, wire_b)| {
+                    evaluation_a.values.get(&Var::Wire(*wire_a))
+                        != evaluation_b.values.get(&Var::Wire(*wire_b))
+                })
+                .map(|(wire_a, _)| *wire_a)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        if a_accepts != b_accepts || !differing_public_inputs.is_empty() {
+            return Ok(Equivalence::Different(Box::new(Counterexample {
+                inputs_a,
+                inputs_b,
+                a_accepts,
+                b_accepts,
+                differing_public_inputs,
+            })));
+        }
+    }
+
+    Ok(Equivalence::ProbablyEquivalent {
+        trials: options.trials,
+    })
+}
+
+/// SMT-LIB query over the finite field theory (`QF_FF`, supported by cvc5) that is satisfiable exactly when some
+/// witness distinguishes `a` and `b`, with the same input matching and notion of difference as `check_equivalence`.
+/// A model of the query is a distinguishing witness, and `unsat` proves equivalence.
+///
+/// # Errors
+///
+/// Errors if a circuit isn't over Goldilocks, the public inputs can't be matched, or a circuit uses comparisons,
+/// equalities nested inside other expressions, non-constant or large exponents, or omitted random values
+pub fn smt_query(a: &Cir, b: &Cir) -> Result<String, EquivalenceError> {
+    check_field(a)?;
+    check_field(b)?;
+
+    let matching = match_inputs(a, b)?;
+
+    let mut shared_b = BTreeMap::new();
+    for (i, (wire_a, wire_b)) in matching.public.iter().enumerate() {
+        shared_b.insert(
+            Var::Wire(*wire_b),
+            (Var::Wire(*wire_a), format!("public_{i}")),
+        );
+    }
+    for (i, (var_a, var_b)) in matching.private.iter().enumerate() {
+        shared_b.insert(var_b.clone(), (var_a.clone(), format!("input_{i}")));
+    }
+    let shared_a = shared_b
+        .values()
+        .cloned()
+        .collect::<BTreeMap<Var, String>>();
+    let shared_b = shared_b
+        .into_iter()
+        .map(|(var_b, (_, symbol))| (var_b, symbol))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut declared = BTreeSet::new();
+    let mut lines = Vec::from([
+        "(set-logic QF_FF)".to_string(),
+        format!("(define-sort F () (_ FiniteField {GOLDILOCKS_ORDER}))"),
+    ]);
+
+    let encoding_a = SmtEncoder::encode(a, "a", &shared_a, &mut declared, &mut lines)?;
+    let encoding_b = SmtEncoder::encode(b, "b", &shared_b, &mut declared, &mut lines)?;
+
+    let mut differences = Vec::from([format!(
+        "(not (= {} {}))",
+        encoding_a.accepts, encoding_b.accepts
+    )]);
+
+    for (wire_a, wire_b) in &matching.public {
+        differences.push(format!(
+            "(and {} {} (not (= {} {})))",
+            encoding_a.accepts,
+            encoding_b.accepts,
+            encoding_a.symbols[&Var::Wire(*wire_a)],
+            encoding_b.symbols[&Var::Wire(*wire_b)]
+        ));
+    }
+
+    lines.push(format!("(assert (or {}))", differences.join(" ")));
+    lines.push("(check-sat)".to_string());
+    lines.push("(get-model)".to_string());
+
+    Ok(lines.join("\n"))
+}
+
+struct SmtEncoding {
+    /// Symbol holding the final value of every variable
+    symbols: BTreeMap<Var, String>,
+
+    /// Boolean term that holds when the circuit accepts
+    accepts: String,
+}
+
+struct SmtEncoder<'a> {
+    prefix: &'static str,
+    symbols: BTreeMap<Var, String>,
+    versions: BTreeMap<String, usize>,
+    declared: &'a mut BTreeSet<String>,
+    lines: &'a mut Vec<String>,
+    conditions: Vec<String>,
+    stmt_index: usize,
+}
+
+impl SmtEncoder<'_> {
+    fn encode(
+        cir: &Cir,
+        prefix: &'static str,
+        shared: &BTreeMap<Var, String>,
+        declared: &mut BTreeSet<String>,
+        lines: &mut Vec<String>,
+    ) -> Result<SmtEncoding, EquivalenceError> {
+        let mut encoder = SmtEncoder {
+            prefix,
+            symbols: BTreeMap::new(),
+            versions: BTreeMap::new(),
+            declared,
+            lines,
+            conditions: Vec::new(),
+            stmt_index: 0,
+        };
+
+        for var in free_vars(cir) {
+            let symbol = match shared.get(&var) {
+                Some(symbol) => {
+                    encoder.declare(symbol);
+                    symbol.clone()
+                }
+                None => encoder.fresh(&var),
+            };
+            encoder.symbols.insert(var, symbol);
+        }
+
+        for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+            encoder.stmt_index = stmt_index;
+
+            match stmt {
+                Stmt::Local(ident, expr) => encoder.define(ident.into(), expr)?,
+                Stmt::Verify(expr) => {
+                    if let Some((var, expr)) = encoder.solvable(expr) {
+                        encoder.define(var, expr)?;
+                        continue;
+                    }
+
+                    let condition = match expr {
+                        Expression::BinaryOperator {
+                            lhs,
+                            binop: BinOp::Equal,
+                            rhs,
+                        } => format!("(= {} {})", encoder.term(lhs)?, encoder.term(rhs)?),
+                        expr => format!("(not (= {} {}))", encoder.term(expr)?, field_constant(0)),
+                    };
+                    encoder.conditions.push(condition);
+                }
+            }
+        }
+
+        // Matched public wires the circuit never reads are still compared, so they need their shared symbol
+        for (var, symbol) in shared {
+            if !encoder.symbols.contains_key(var) {
+                encoder.declare(symbol);
+                encoder.symbols.insert(var.clone(), symbol.clone());
+            }
+        }
+
+        let accepts = match encoder.conditions.len() {
+            0 => "true".to_string(),
+            1 => encoder.conditions[0].clone(),
+            _ => format!("(and {})", encoder.conditions.join(" ")),
+        };
+
+        Ok(SmtEncoding {
+            symbols: encoder.symbols,
+            accepts,
+        })
+    }
+
+    fn solvable<'e>(&self, expr: &'e Expression) -> Option<(Var, &'e Expression)> {
+        solvable_equality(expr, &|var| self.symbols.contains_key(var))
+    }
+
+    fn define(&mut self, var: Var, expr: &Expression) -> Result<(), EquivalenceError> {
+        let term = self.term(expr)?;
+        let symbol = self.fresh(&var);
+        self.lines.push(format!("(assert (= {symbol} {term}))"));
+        self.symbols.insert(var, symbol);
+        Ok(())
+    }
+
+    fn fresh(&mut self, var: &Var) -> String {
+        let base = format!(
+            "{}_{}",
+            self.prefix,
+            match var {
+                Var::Wire(WireRef::Wire { row, column }) => format!("wire_{row}_{column}"),
+                Var::Wire(WireRef::VirtualWire { index }) => format!("virtual_wire_{index}"),
+                // Can't appear in a quoted symbol. Symbols are versioned per base, so this can't merge two locals.
+                Var::Local(name) => format!("local_{}", name.replace(['|', '\\'], "_")),
+            }
+        );
+
+        let version = self.versions.entry(base.clone()).or_default();
+        *version += 1;
+
+        let symbol = format!("{base}_{version}");
+        // Names from frontends, like Circom's `in[0]`, aren't always valid simple symbols
+        let symbol = if symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "~!@$%^&*_-+=<>.?/".contains(c))
+        {
+            symbol
+        } else {
+            format!("|{symbol}|")
+        };
+
+        self.declare(&symbol);
+        symbol
+    }
+
+    fn declare(&mut self, symbol: &str) {
+        if self.declared.insert(symbol.to_string()) {
+            self.lines.push(format!("(declare-const {symbol} F)"));
+        }
+    }
+
+    fn term(&mut self, expr: &Expression) -> Result<String, EquivalenceError> {
+        Ok(match expr {
+            Expression::Value(value) => self.value(*value)?,
+            Expression::Ident(ident) => match constant_value(ident) {
+                Some(value) => self.value(value)?,
+                None => self.symbols.get(&ident.into()).cloned().ok_or_else(|| {
+                    EvalError::Unassigned {
+                        stmt_index: self.stmt_index,
+                        var: ident.into(),
+                    }
+                })?,
+            },
+            Expression::Unary { op: Op::Sub, expr } => format!("(ff.neg {})", self.term(expr)?),
+            Expression::BinaryOperator { lhs, binop, rhs } => match binop {
+                BinOp::Add => format!("(ff.add {} {})", self.term(lhs)?, self.term(rhs)?),
+                BinOp::Subtract => {
+                    format!("(ff.add {} (ff.neg {}))", self.term(lhs)?, self.term(rhs)?)
+                }
+                BinOp::Multiply => format!("(ff.mul {} {})", self.term(lhs)?, self.term(rhs)?),
+                BinOp::Divide => {
+                    // `lhs / rhs` is `lhs * inverse` where `inverse * rhs == 1`, which fails when `rhs` is zero
+                    let (lhs, rhs) = (self.term(lhs)?, self.term(rhs)?);
+                    let inverse = self.fresh(&Var::Local(format!("inverse{}", self.stmt_index)));
+                    self.conditions.push(format!(
+                        "(= (ff.mul {inverse} {rhs}) {})",
+                        field_constant(1)
+                    ));
+                    format!("(ff.mul {lhs} {inverse})")
+                }
+                BinOp::Exponent => match constant(rhs) {
+                    Some(0) => field_constant(1),
+                    Some(exponent) if exponent <= MAX_SMT_EXPONENT => {
+                        let base = self.term(lhs)?;
+                        let factors = (0..exponent).map(|_| base.as_str()).collect::<Vec<_>>();
+
+                        if exponent == 1 {
+                            base.clone()
+                        } else {
+                            format!("(ff.mul {})", factors.join(" "))
+                        }
+                    }
+                    _ => return Err(self.unsupported("a non-constant or large exponent")),
+                },
+                BinOp::Equal
+                | BinOp::LessThan
+                | BinOp::LessThanEqual
+                | BinOp::GreaterThan
+                | BinOp::GreaterThanEqual => {
+                    return Err(self.unsupported(&format!("`{binop}` inside an expression")))
+                }
+            },
+        })
+    }
+
+    fn value(&self, value: Value) -> Result<String, EquivalenceError> {
+        match value {
+            Value::U64(value) | Value::RandomU64(value) | Value::Sampled { value, .. } => {
+                Ok(field_constant(value))
+            }
+            Value::Random | Value::OmittedSample { .. } => {
+                Err(self.unsupported("an omitted random value"))
+            }
+        }
+    }
+
+    fn unsupported(&self, operation: &str) -> EquivalenceError {
+        EquivalenceError::UnsupportedSmt {
+            stmt_index: self.stmt_index,
+            operation: operation.to_string(),
+        }
+    }
+}
+
+fn field_constant(value: u64) -> String {
+    format!("(as ff{} F)", Fp::new(value))
+}
+
+/// Small deterministic generator, since `no_std` rules out system randomness
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{VirtualWire, Wire},
+        ir::{CirBuilder, Field},
+        test_util::{binop, test_code_ir},
+    };
+
+    use super::*;
+
+    fn value(value: u64) -> Expression {
+        Expression::Value(Value::U64(value))
+    }
+
+    /// `out == x * 2`, written either as an addition or a multiplication
+    fn double(as_addition: bool, x: Wire) -> Cir {
+        let doubled = if as_addition {
+            binop(x, BinOp::Add, x)
+        } else {
+            binop(x, BinOp::Multiply, value(2))
+        };
+
+        CirBuilder::new()
+            .add_stmt(Stmt::Local("doubled".into(), doubled))
+            .add_stmt(Stmt::Verify(binop(
+                VirtualWire::new_public(0),
+                BinOp::Equal,
+                Expression::Ident("doubled".into()),
+            )))
+            .build()
+    }
+
+    #[test]
+    fn test_equivalent() {
+        let a = double(true, Wire::new_private(0, 0));
+        let b = double(false, Wire::new_private(0, 0));
+
+        assert_eq!(
+            check_equivalence(&a, &b, &EquivalenceOptions::default()),
+            Ok(Equivalence::ProbablyEquivalent { trials: 64 })
+        );
+
+        test_code_ir("equivalence_smt_query", &smt_query(&a, &b).unwrap());
+    }
+
+    #[test]
+    fn test_different() {
+        let a = double(true, Wire::new_private(0, 0));
+        let b = CirBuilder::new()
+            .add_stmt(Stmt::Verify(binop(
+                VirtualWire::new_public(0),
+                BinOp::Equal,
+                binop(Wire::new_private(0, 0), BinOp::Multiply, value(3)),
+            )))
+            .build();
+
+        let Ok(Equivalence::Different(counterexample)) =
+            check_equivalence(&a, &b, &EquivalenceOptions::default())
+        else {
+            panic!("expected a counterexample");
+        };
+
+        // All zeros satisfy both, so the all ones witness is the first to tell them apart
+        let x = Var::Wire(WireRef::Wire { row: 0, column: 0 });
+        assert_eq!(counterexample.inputs_a[&x], Fp::ONE);
+        assert_eq!(counterexample.inputs_b[&x], Fp::ONE);
+        assert_ne!(counterexample.a_accepts, counterexample.b_accepts);
+    }
+
+    #[test]
+    fn test_unread_public_input() {
+        let cir = || {
+            CirBuilder::new()
+                .public_input(WireRef::Wire { row: 5, column: 5 }, "x")
+                .add_stmt(Stmt::Verify(binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Equal,
+                    value(1),
+                )))
+                .build()
+        };
+
+        let query = smt_query(&cir(), &cir()).unwrap();
+
+        assert!(query.contains("(declare-const public_0 F)"));
+        assert!(query.contains("(not (= public_0 public_0))"));
+    }
+
+    #[test]
+    fn test_quoted_symbols() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local("in[0]".into(), Wire::new_private(0, 0).into()))
+            .add_stmt(Stmt::Verify(binop(
+                Expression::Ident("in[0]".into()),
+                BinOp::Equal,
+                value(1),
+            )))
+            .build();
+
+        let query = smt_query(&cir, &cir).unwrap();
+
+        assert!(query.contains("(declare-const |a_local_in[0]_1| F)"));
+        assert!(query.contains("(= |a_local_in[0]_1| input_0)"));
+        assert!(!query.contains(" a_local_in[0]"));
+    }
+
+    #[test]
+    fn test_unsupported_field() {
+        let a = double(true, Wire::new_private(0, 0));
+        let mut b = a.clone();
+        b.config = CirBuilder::new().field(Field::Other).build().config;
+
+        let error = EquivalenceError::Eval(EvalError::UnsupportedField(Field::Other));
+        assert_eq!(smt_query(&a, &b), Err(error.clone()));
+        assert_eq!(
+            check_equivalence(&a, &b, &EquivalenceOptions::default()),
+            Err(error)
+        );
+    }
+
+    #[test]
+    fn test_public_input_count() {
+        let a = double(true, Wire::new_private(0, 0));
+
+        assert_eq!(
+            match_inputs(&a, &CirBuilder::new().build()),
+            Err(EquivalenceError::PublicInputCount { a: 1, b: 0 })
+        );
+    }
+}
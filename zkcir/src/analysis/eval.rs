@@ -0,0 +1,458 @@
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use core::ops::{Add, Mul, Neg, Sub};
+use derive_more::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{expression_vars, Var};
+use crate::ast::{BinOp, Expression, Ident, Op, Stmt, Value, Wiretype};
+use crate::ir::{Cir, Field};
+
+/// Order of plonky2's Goldilocks field, `2^64 - 2^32 + 1`
+pub const GOLDILOCKS_ORDER: u64 = 0xFFFF_FFFF_0000_0001;
+
+/// Element of the Goldilocks field, always kept in canonical form
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Default, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[display(fmt = "{_0}")]
+pub struct Fp(u64);
+
+impl Fp {
+    pub const ZERO: Fp = Fp(0);
+    pub const ONE: Fp = Fp(1);
+
+    #[must_use]
+    pub fn new(value: u64) -> Self {
+        Self(value % GOLDILOCKS_ORDER)
+    }
+
+    /// Canonical representative in `[0, GOLDILOCKS_ORDER)`
+    #[must_use]
+    pub fn value(self) -> u64 {
+        self.0
+    }
+
+    #[must_use]
+    pub fn pow(self, mut exponent: u64) -> Self {
+        let (mut base, mut result) = (self, Fp::ONE);
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exponent >>= 1;
+        }
+
+        result
+    }
+
+    /// `None` for zero
+    #[must_use]
+    pub fn inverse(self) -> Option<Self> {
+        (self != Fp::ZERO).then(|| self.pow(GOLDILOCKS_ORDER - 2))
+    }
+
+    fn from_bool(value: bool) -> Self {
+        if value {
+            Fp::ONE
+        } else {
+            Fp::ZERO
+        }
+    }
+}
+
+impl Add for Fp {
+    type Output = Fp;
+
+    fn add(self, rhs: Fp) -> Fp {
+        let sum = u128::from(self.0) + u128::from(rhs.0);
+        // Always below the order after reduction, so the cast can't truncate
+        #[allow(clippy::cast_possible_truncation)]
+        Fp((sum % u128::from(GOLDILOCKS_ORDER)) as u64)
+    }
+}
+
+impl Sub for Fp {
+    type Output = Fp;
+
+    fn sub(self, rhs: Fp) -> Fp {
+        self + -rhs
+    }
+}
+
+impl Mul for Fp {
+    type Output = Fp;
+
+    fn mul(self, rhs: Fp) -> Fp {
+        let product = u128::from(self.0) * u128::from(rhs.0);
+        #[allow(clippy::cast_possible_truncation)]
+        Fp((product % u128::from(GOLDILOCKS_ORDER)) as u64)
+    }
+}
+
+impl Neg for Fp {
+    type Output = Fp;
+
+    fn neg(self) -> Fp {
+        if self.0 == 0 {
+            self
+        } else {
+            Fp(GOLDILOCKS_ORDER - self.0)
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EvalError {
+    /// A variable was read before it had a value
+    #[display(
+        fmt = "stmt {stmt_index} reads `{}` before it has a value",
+        "var.to_code_ir()"
+    )]
+    Unassigned { stmt_index: usize, var: Var },
+
+    /// A `random!()` value with the concrete value omitted, which can't be evaluated
+    #[display(fmt = "stmt {stmt_index} reads a random value that was omitted")]
+    OmittedRandom { stmt_index: usize },
+
+    /// Evaluation is only implemented over Goldilocks
+    #[display(fmt = "circuit is over {_0}, but only Goldilocks is supported")]
+    UnsupportedField(Field),
+}
+
+/// Outcome of running a circuit on concrete inputs
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Evaluation {
+    /// Final value of every variable, including inputs
+    pub values: BTreeMap<Var, Fp>,
+
+    /// Statements that don't hold, in order. Evaluation stops at a division by zero, so it is always last.
+    pub failed: Vec<usize>,
+}
+
+impl Evaluation {
+    /// Whether every statement holds
+    #[must_use]
+    pub fn accepts(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Runs `cir` over the Goldilocks field. `inputs` gives values to variables the circuit doesn't compute itself, see
+/// `free_vars`. Constant wires with a recorded value use that value.
+///
+/// Statements run in order: `Stmt::Local` assigns its variable and `Stmt::Verify` holds when its expression is
+/// nonzero. A verify like `verify!(x == e)` where `x` has no value yet solves `x` instead, the way frameworks copy
+/// a computed value into a wire. Comparisons compare canonical representatives and evaluate to `0` or `1`.
+///
+/// # Errors
+///
+/// Errors if the circuit isn't over Goldilocks, a variable is read before it has a value or a random value was omitted
+pub fn evaluate(cir: &Cir, inputs: &BTreeMap<Var, Fp>) -> Result<Evaluation, EvalError> {
+    check_field(cir)?;
+
+    let mut evaluator = Evaluator {
+        values: inputs.clone(),
+        stmt_index: 0,
+    };
+    let mut failed = Vec::new();
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        evaluator.stmt_index = stmt_index;
+
+        let (def, expr) = match stmt {
+            Stmt::Local(ident, expr) => (Some(Var::from(ident)), expr),
+            Stmt::Verify(expr) => match evaluator.solvable(expr) {
+                Some((var, expr)) => (Some(var), expr),
+                None => (None, expr),
+            },
+        };
+
+        let Some(value) = evaluator.eval(expr)? else {
+            failed.push(stmt_index);
+            break;
+        };
+
+        match def {
+            Some(var) => {
+                evaluator.values.insert(var, value);
+            }
+            None if value == Fp::ZERO => failed.push(stmt_index),
+            None => {}
+        }
+    }
+
+    Ok(Evaluation {
+        values: evaluator.values,
+        failed,
+    })
+}
+
+/// Variables that need an input value for `evaluate` to run `cir`: every variable read before the circuit assigns
+/// or solves it. Public inputs the circuit solves, like outputs, aren't included.
+#[must_use]
+pub fn free_vars(cir: &Cir) -> BTreeSet<Var> {
+    let mut free = BTreeSet::new();
+    let mut known = BTreeSet::new();
+
+    for stmt in &cir.stmts {
+        let (def, expr) = match stmt {
+            Stmt::Local(ident, expr) => (Some(Var::from(ident)), expr),
+            Stmt::Verify(expr) => match solvable_equality(expr, &|var| known.contains(var)) {
+                Some((var, expr)) => (Some(var), expr),
+                None => (None, expr),
+            },
+        };
+
+        for var in expression_vars(expr) {
+            if !known.contains(&var) && !is_fixed_constant(expr, &var) {
+                free.insert(var.clone());
+                known.insert(var);
+            }
+        }
+
+        known.extend(def);
+    }
+
+    free
+}
+
+/// Statement giving each variable its value under the same rules as `evaluate`: the `Stmt::Local` assigning a local,
+/// or the `verify!(x == e)` solving a wire. Free variables aren't included.
+#[must_use]
+pub fn definitions(cir: &Cir) -> BTreeMap<Var, usize> {
+    let free = free_vars(cir);
+    let mut definitions = BTreeMap::new();
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        let def = match stmt {
+            Stmt::Local(ident, _) => Some(Var::from(ident)),
+            Stmt::Verify(expr) => solvable_equality(expr, &|var| {
+                free.contains(var) || definitions.contains_key(var)
+            })
+            .map(|(var, _)| var),
+        };
+
+        if let Some(var) = def {
+            definitions.entry(var).or_insert(stmt_index);
+        }
+    }
+
+    definitions
+}
+
+struct Evaluator {
+    values: BTreeMap<Var, Fp>,
+    stmt_index: usize,
+}
+
+impl Evaluator {
+    fn solvable<'a>(&self, expr: &'a Expression) -> Option<(Var, &'a Expression)> {
+        solvable_equality(expr, &|var| self.values.contains_key(var))
+    }
+
+    /// `None` on division by zero
+    fn eval(&self, expr: &Expression) -> Result<Option<Fp>, EvalError> {
+        Ok(Some(match expr {
+            Expression::Value(value) => self.value(*value)?,
+            Expression::Ident(ident) => self.ident(ident)?,
+            Expression::Unary { op: Op::Sub, expr } => match self.eval(expr)? {
+                Some(value) => -value,
+                None => return Ok(None),
+            },
+            Expression::BinaryOperator { lhs, binop, rhs } => {
+                let (Some(lhs), Some(rhs)) = (self.eval(lhs)?, self.eval(rhs)?) else {
+                    return Ok(None);
+                };
+
+                match binop {
+                    BinOp::Add => lhs + rhs,
+                    BinOp::Subtract => lhs - rhs,
+                    BinOp::Multiply => lhs * rhs,
+                    BinOp::Divide => match rhs.inverse() {
+                        Some(inverse) => lhs * inverse,
+                        None => return Ok(None),
+                    },
+                    BinOp::Exponent => lhs.pow(rhs.value()),
+                    BinOp::Equal => Fp::from_bool(lhs == rhs),
+                    BinOp::LessThan => Fp::from_bool(lhs < rhs),
+                    BinOp::LessThanEqual => Fp::from_bool(lhs <= rhs),
+                    BinOp::GreaterThan => Fp::from_bool(lhs > rhs),
+                    BinOp::GreaterThanEqual => Fp::from_bool(lhs >= rhs),
+                }
+            }
+        }))
+    }
+
+    fn value(&self, value: Value) -> Result<Fp, EvalError> {
+        match value {
+            Value::U64(value) | Value::RandomU64(value) | Value::Sampled { value, .. } => {
+                Ok(Fp::new(value))
+            }
+            Value::Random | Value::OmittedSample { .. } => Err(EvalError::OmittedRandom {
+                stmt_index: self.stmt_index,
+            }),
+        }
+    }
+
+    fn ident(&self, ident: &Ident) -> Result<Fp, EvalError> {
+        let var = Var::from(ident);
+
+        if let Some(value) = self.values.get(&var) {
+            return Ok(*value);
+        }
+
+        match constant_value(ident) {
+            Some(value) => self.value(value),
+            None => Err(EvalError::Unassigned {
+                stmt_index: self.stmt_index,
+                var,
+            }),
+        }
+    }
+}
+
+/// Errors unless `cir` is over Goldilocks, the only field `Fp` implements
+pub(crate) fn check_field(cir: &Cir) -> Result<(), EvalError> {
+    match cir.config.field() {
+        Field::Goldilocks => Ok(()),
+        Field::Other => Err(EvalError::UnsupportedField(Field::Other)),
+    }
+}
+
+/// `verify!(x == e)` or `verify!(e == x)` where `x` has no value yet but everything `e` reads does
+pub(crate) fn solvable_equality<'a>(
+    expr: &'a Expression,
+    is_known: &dyn Fn(&Var) -> bool,
+) -> Option<(Var, &'a Expression)> {
+    let Expression::BinaryOperator {
+        lhs,
+        binop: BinOp::Equal,
+        rhs,
+    } = expr
+    else {
+        return None;
+    };
+
+    [(&**lhs, &**rhs), (&**rhs, &**lhs)]
+        .into_iter()
+        .find_map(|(side, other)| {
+            let Expression::Ident(ident) = side else {
+                return None;
+            };
+
+            let var = Var::from(ident);
+            let solvable = !is_known(&var)
+                && constant_value(ident).is_none()
+                && expression_vars(other)
+                    .iter()
+                    .all(|var| is_known(var) || is_fixed_constant(other, var));
+
+            solvable.then_some((var, other))
+        })
+}
+
+/// Recorded value of a constant wire
+pub(crate) fn constant_value(ident: &Ident) -> Option<Value> {
+    match ident {
+        Ident::Wire(wire) if wire.wiretype == Wiretype::Constant => wire.value,
+        Ident::VirtualWire(virtual_wire) if virtual_wire.wiretype == Wiretype::Constant => {
+            virtual_wire.value
+        }
+        _ => None,
+    }
+}
+
+/// Whether `var` appears in `expr` as a constant wire with a recorded value
+fn is_fixed_constant(expr: &Expression, var: &Var) -> bool {
+    match expr {
+        Expression::BinaryOperator { lhs, rhs, .. } => {
+            is_fixed_constant(lhs, var) || is_fixed_constant(rhs, var)
+        }
+        Expression::Ident(ident) => Var::from(ident) == *var && constant_value(ident).is_some(),
+        Expression::Value(_) => false,
+        Expression::Unary { expr, .. } => is_fixed_constant(expr, var),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Wire, WireRef},
+        ir::CirBuilder,
+        test_util::binop,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_field_arithmetic() {
+        let minus_one = Fp::new(GOLDILOCKS_ORDER - 1);
+
+        assert_eq!(minus_one + Fp::ONE, Fp::ZERO);
+        assert_eq!(Fp::ZERO - Fp::ONE, minus_one);
+        assert_eq!(minus_one * minus_one, Fp::ONE);
+        assert_eq!(
+            Fp::new(7).inverse().map(|inverse| inverse * Fp::new(7)),
+            Some(Fp::ONE)
+        );
+        assert_eq!(Fp::ZERO.inverse(), None);
+        assert_eq!(Fp::new(3).pow(4), Fp::new(81));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                "square".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Multiply,
+                    Wire::new_private(0, 0),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 1),
+                BinOp::Equal,
+                Expression::Ident("square".into()),
+            )))
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 1),
+                BinOp::Equal,
+                Expression::Value(Value::U64(9)),
+            )))
+            .build();
+
+        let x = Var::Wire(WireRef::Wire { row: 0, column: 0 });
+        assert_eq!(free_vars(&cir), [x.clone()].into());
+        assert_eq!(
+            definitions(&cir),
+            [
+                (Var::Local("square".into()), 0),
+                (Var::Wire(WireRef::Wire { row: 0, column: 1 }), 1)
+            ]
+            .into()
+        );
+
+        let accepted = evaluate(&cir, &[(x.clone(), Fp::new(3))].into()).unwrap();
+        assert!(accepted.accepts());
+        assert_eq!(accepted.values[&Var::Local("square".into())], Fp::new(9));
+
+        let rejected = evaluate(&cir, &[(x, Fp::new(4))].into()).unwrap();
+        assert_eq!(rejected.failed, [2]);
+
+        assert_eq!(
+            evaluate(&cir, &BTreeMap::new()),
+            Err(EvalError::Unassigned {
+                stmt_index: 0,
+                var: Var::Wire(WireRef::Wire { row: 0, column: 0 }),
+            })
+        );
+    }
+}
@@ -0,0 +1,429 @@
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_more::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::range::{
+    binary, constant, equality, multiplication, range_checks, RangeCheck,
+};
+use crate::analysis::transcript::challenge_bindings;
+use crate::analysis::{expression_vars, Var};
+use crate::ast::{BinOp, Expression, Stmt};
+use crate::ir::Cir;
+use crate::node::Node;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum Severity {
+    #[cfg_attr(feature = "serde", serde(alias = "info"))]
+    #[display(fmt = "info")]
+    Info,
+
+    #[cfg_attr(feature = "serde", serde(alias = "warning"))]
+    #[display(fmt = "warning")]
+    Warning,
+
+    #[cfg_attr(feature = "serde", serde(alias = "error"))]
+    #[display(fmt = "error")]
+    Error,
+}
+
+/// Problem found in a statement
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct Finding {
+    /// Name of the rule that produced this finding, like `division-by-zero`
+    pub rule: String,
+    pub severity: Severity,
+
+    /// Index into `Cir::stmts`, or `stmts.len()` for a finding no statement is responsible for, like a challenge
+    /// that is never read
+    pub stmt_index: usize,
+
+    /// What is wrong with this specific statement
+    pub message: String,
+
+    /// Why the rule matters and how to fix it, suitable for audit reports
+    pub explanation: String,
+}
+
+pub const DIVISION_BY_ZERO: &str = "division-by-zero";
+pub const UNCHECKED_COMPARISON: &str = "unchecked-comparison";
+pub const UNBOUND_CHALLENGE: &str = "unbound-challenge";
+
+const DIVISION_BY_ZERO_EXPLANATION: &str = "Division has no defined result when the divisor is zero. Over a \
+    prime field `a / b` is computed as `a * b^-1`, and when `b == 0` the prover may be free to choose the quotient \
+    unless another constraint rules it out. Prove the divisor is nonzero with a constraint like \
+    `verify!(b * b_inv == 1)`.";
+
+const UNCHECKED_COMPARISON_EXPLANATION: &str = "Ordering comparisons have no meaning over a prime field since \
+    values wrap around the modulus, so a large value can compare as smaller than a small one. `a < b` is only sound \
+    when both operands are range checked to fit well below the modulus, for example with a bit decomposition, \
+    before the comparison.";
+
+const UNBOUND_CHALLENGE_EXPLANATION: &str = "A Fiat-Shamir challenge is only unpredictable to the prover if every \
+    value the prover could still choose was absorbed into the transcript before it was squeezed. A public input or \
+    commitment left out can be picked after seeing the challenge, which often lets a false statement be proven. \
+    Absorb every public input and commitment before squeezing the challenge.";
+
+/// Runs every built-in lint rule, sorted by statement
+#[must_use]
+pub fn lint(cir: &Cir) -> Vec<Finding> {
+    let mut findings = lint_division_by_zero(cir);
+    findings.extend(lint_unchecked_comparison(cir));
+    findings.extend(lint_unbound_challenge(cir));
+    findings.sort_by_key(|finding| finding.stmt_index);
+    findings
+}
+
+/// Flags divisions whose divisor isn't a nonzero constant and isn't proven nonzero by another `verify!`, like
+/// `verify!(b * b_inv == 1)` or `verify!(b == 5)`
+#[must_use]
+pub fn lint_division_by_zero(cir: &Cir) -> Vec<Finding> {
+    let nonzero = nonzero_expressions(cir);
+    let mut findings = Vec::new();
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        let mut divisors = Vec::new();
+        collect_divisors(stmt_expression(stmt), &mut divisors);
+
+        for divisor in divisors {
+            let divisor = normalize(divisor);
+
+            if constant(&divisor).is_some_and(|value| value != 0) {
+                continue;
+            }
+
+            let proven = nonzero
+                .iter()
+                .any(|(proof_index, expr)| *proof_index != stmt_index && *expr == divisor);
+
+            if !proven {
+                findings.push(Finding {
+                    rule: DIVISION_BY_ZERO.to_string(),
+                    severity: Severity::Warning,
+                    stmt_index,
+                    message: format!("divisor `{}` is not proven nonzero", divisor.to_code_ir()),
+                    explanation: DIVISION_BY_ZERO_EXPLANATION.to_string(),
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+/// Flags `<`, `<=`, `>` and `>=` where an operand isn't constant and a variable it reads isn't range checked by an
+/// earlier statement
+#[must_use]
+pub fn lint_unchecked_comparison(cir: &Cir) -> Vec<Finding> {
+    let checks = range_checks(cir);
+    let mut findings = Vec::new();
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        let mut comparisons = Vec::new();
+        collect_comparisons(stmt_expression(stmt), &mut comparisons);
+
+        for (lhs, binop, rhs) in comparisons {
+            let unchecked = [lhs, rhs]
+                .into_iter()
+                .filter(|operand| constant(operand).is_none())
+                .flat_map(expression_vars)
+                .filter(|var| !is_checked_before(&checks, var, stmt_index))
+                .collect::<Vec<_>>();
+
+            if unchecked.is_empty() {
+                continue;
+            }
+
+            findings.push(Finding {
+                rule: UNCHECKED_COMPARISON.to_string(),
+                severity: Severity::Warning,
+                stmt_index,
+                message: format!(
+                    "`{} {binop} {}` compares {} without a preceding range check",
+                    normalize(lhs).to_code_ir(),
+                    normalize(rhs).to_code_ir(),
+                    unchecked
+                        .iter()
+                        .map(|var| format!("`{}`", var.to_code_ir()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                explanation: UNCHECKED_COMPARISON_EXPLANATION.to_string(),
+            });
+        }
+    }
+
+    findings
+}
+
+/// Flags challenges squeezed before every public input and wire tagged `commitment` was absorbed, at the first
+/// statement reading the challenge, or at `cir.stmts.len()` if no statement reads it
+#[must_use]
+pub fn lint_unbound_challenge(cir: &Cir) -> Vec<Finding> {
+    challenge_bindings(cir)
+        .into_iter()
+        .filter(|binding| !binding.unbound.is_empty())
+        .map(|binding| {
+            let name = cir.random_source(binding.source).map_or_else(
+                || format!("random!({})", binding.source),
+                |source| source.name.clone(),
+            );
+
+            Finding {
+                rule: UNBOUND_CHALLENGE.to_string(),
+                severity: Severity::Warning,
+                stmt_index: binding.first_use.unwrap_or(cir.stmts.len()),
+                message: format!(
+                    "challenge `{name}` is squeezed before absorbing {}",
+                    binding
+                        .unbound
+                        .iter()
+                        .map(|wire_ref| format!("`{}`", cir.var_name(&Var::Wire(*wire_ref))))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                explanation: UNBOUND_CHALLENGE_EXPLANATION.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn is_checked_before(checks: &BTreeMap<Var, RangeCheck>, var: &Var, stmt_index: usize) -> bool {
+    checks
+        .get(var)
+        .is_some_and(|check| check.stmt_index < stmt_index)
+}
+
+fn stmt_expression(stmt: &Stmt) -> &Expression {
+    match stmt {
+        Stmt::Verify(expr) | Stmt::Local(_, expr) => expr,
+    }
+}
+
+/// Expressions proven nonzero by a `verify!`, along with the index of that statement
+fn nonzero_expressions(cir: &Cir) -> Vec<(usize, Expression)> {
+    let mut nonzero = Vec::new();
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        let Stmt::Verify(expr) = stmt else {
+            continue;
+        };
+
+        let Some((lhs, rhs)) = equality(expr) else {
+            continue;
+        };
+
+        for (side, other) in [(lhs, rhs), (rhs, lhs)] {
+            if constant(other).is_some_and(|value| value != 0) {
+                // a * b == c with nonzero c proves both factors nonzero
+                if let Some((a, b)) = multiplication(side) {
+                    nonzero.push((stmt_index, normalize(a)));
+                    nonzero.push((stmt_index, normalize(b)));
+                }
+
+                nonzero.push((stmt_index, normalize(side)));
+            }
+        }
+    }
+
+    nonzero
+}
+
+fn collect_divisors<'a>(expr: &'a Expression, divisors: &mut Vec<&'a Expression>) {
+    match expr {
+        Expression::BinaryOperator { lhs, binop, rhs } => {
+            if *binop == BinOp::Divide {
+                divisors.push(rhs);
+            }
+
+            collect_divisors(lhs, divisors);
+            collect_divisors(rhs, divisors);
+        }
+        Expression::Unary { expr, .. } => collect_divisors(expr, divisors),
+        Expression::Ident(_) | Expression::Value(_) => {}
+    }
+}
+
+fn collect_comparisons<'a>(
+    expr: &'a Expression,
+    comparisons: &mut Vec<(&'a Expression, BinOp, &'a Expression)>,
+) {
+    if let Some((lhs, binop, rhs)) = binary(expr) {
+        if matches!(
+            binop,
+            BinOp::LessThan | BinOp::LessThanEqual | BinOp::GreaterThan | BinOp::GreaterThanEqual
+        ) {
+            comparisons.push((lhs, binop, rhs));
+        }
+
+        collect_comparisons(lhs, comparisons);
+        collect_comparisons(rhs, comparisons);
+    } else if let Expression::Unary { expr, .. } = expr {
+        collect_comparisons(expr, comparisons);
+    }
+}
+
+/// Copy of `expr` without wire values so expressions can be compared structurally
+fn normalize(expr: &Expression) -> Expression {
+    let mut expr = expr.clone();
+    expr.visit_wires(&mut |wire| wire.value = None);
+    expr.visit_virtual_wires(&mut |virtual_wire| virtual_wire.value = None);
+    expr
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::transcript::COMMITMENT_TAG,
+        ast::{Value, Wire},
+        ir::CirBuilder,
+        test_util::binop,
+    };
+
+    use super::*;
+
+    fn value(value: u64) -> Expression {
+        Expression::Value(Value::U64(value))
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let divide = Stmt::Local(
+            "quotient".into(),
+            binop(
+                Wire::new_private(0, 0),
+                BinOp::Divide,
+                Wire::new_private(0, 1),
+            ),
+        );
+
+        let findings = lint(&CirBuilder::new().add_stmt(divide.clone()).build());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, DIVISION_BY_ZERO);
+        assert_eq!(
+            findings[0].message,
+            "divisor `wire::private(row: 0, column: 1)` is not proven nonzero"
+        );
+
+        let proven = CirBuilder::new()
+            .add_stmt(divide)
+            .add_stmt(Stmt::Verify(binop(
+                binop(
+                    Wire::new_private(0, 1),
+                    BinOp::Multiply,
+                    Wire::new_private(0, 2),
+                ),
+                BinOp::Equal,
+                value(1),
+            )))
+            .build();
+        assert!(lint(&proven).is_empty());
+    }
+
+    #[test]
+    fn test_unchecked_comparison() {
+        let compare = Stmt::Verify(binop(Wire::new_private(0, 0), BinOp::LessThan, value(4)));
+
+        let findings = lint(&CirBuilder::new().add_stmt(compare.clone()).build());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, UNCHECKED_COMPARISON);
+
+        let booleanity = |column| {
+            Stmt::Verify(binop(
+                binop(
+                    Wire::new_private(1, column),
+                    BinOp::Multiply,
+                    binop(Wire::new_private(1, column), BinOp::Subtract, value(1)),
+                ),
+                BinOp::Equal,
+                value(0),
+            ))
+        };
+
+        let checked = CirBuilder::new()
+            .add_stmt(booleanity(0))
+            .add_stmt(booleanity(1))
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 0),
+                BinOp::Equal,
+                binop(
+                    Wire::new_private(1, 0),
+                    BinOp::Add,
+                    binop(Wire::new_private(1, 1), BinOp::Multiply, value(2)),
+                ),
+            )))
+            .add_stmt(compare)
+            .build();
+        assert!(lint(&checked).is_empty());
+    }
+
+    #[test]
+    fn test_unbound_challenge() {
+        let circuit = |absorb_commitment: bool| {
+            let mut builder = CirBuilder::new();
+            builder
+                .name_wire(0, 1, "commitment")
+                .tag_wire(0, 1, COMMITMENT_TAG)
+                .absorb(Wire::new_public(0, 0));
+            if absorb_commitment {
+                builder.absorb(Wire::new_private(0, 1));
+            }
+            let alpha = builder.squeeze("alpha");
+
+            builder
+                .add_stmt(Stmt::Verify(binop(
+                    Wire::new_public(0, 0),
+                    BinOp::Equal,
+                    binop(
+                        Wire::new_private(0, 1),
+                        BinOp::Multiply,
+                        Expression::Value(Value::Sampled {
+                            source: alpha,
+                            value: 3,
+                        }),
+                    ),
+                )))
+                .build()
+        };
+
+        let findings = lint(&circuit(false));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, UNBOUND_CHALLENGE);
+        assert_eq!(
+            findings[0].message,
+            "challenge `alpha` is squeezed before absorbing `commitment`"
+        );
+
+        assert!(lint(&circuit(true)).is_empty());
+    }
+
+    #[test]
+    fn test_unused_unbound_challenge() {
+        let mut builder = CirBuilder::new();
+        builder.add_stmt(Stmt::Verify(binop(
+            Wire::new_public(0, 0),
+            BinOp::Equal,
+            Expression::Value(Value::U64(3)),
+        )));
+        builder.squeeze("alpha");
+        let cir = builder.build();
+
+        let findings = lint(&cir);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, UNBOUND_CHALLENGE);
+        assert_eq!(findings[0].stmt_index, 1);
+        assert_eq!(
+            findings[0].message,
+            "challenge `alpha` is squeezed before absorbing `wire(row: 0, column: 0)`"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Static analyses over a built `Cir`
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{Expression, Ident, Stmt, VirtualWire, Wire, WireRef, Wiretype};
+use crate::ir::Cir;
+use crate::node::Node;
+
+pub mod cost;
+pub mod equivalence;
+pub mod eval;
+pub mod lint;
+pub mod range;
+pub mod rules;
+pub mod slice;
+pub mod taint;
+pub mod transcript;
+
+/// Anything that holds a value in the circuit: a wire, a virtual wire or a local named by a string
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Var {
+    Wire(WireRef),
+    Local(String),
+}
+
+impl Var {
+    /// Short description for reports, like `wire(row: 1, column: 2)` or `x`
+    #[must_use]
+    pub fn to_code_ir(&self) -> String {
+        match self {
+            Var::Wire(WireRef::Wire { row, column }) => {
+                format!("wire(row: {row}, column: {column})")
+            }
+            Var::Wire(WireRef::VirtualWire { index }) => format!("virtual_wire(index: {index})"),
+            Var::Local(name) => name.clone(),
+        }
+    }
+}
+
+impl From<&Ident> for Var {
+    fn from(ident: &Ident) -> Self {
+        match ident {
+            Ident::String(name) => Var::Local(name.clone()),
+            Ident::Wire(wire) => Var::Wire(wire.wire_ref()),
+            Ident::VirtualWire(virtual_wire) => Var::Wire(virtual_wire.wire_ref()),
+        }
+    }
+}
+
+impl From<Wire> for Var {
+    fn from(wire: Wire) -> Self {
+        Var::Wire(wire.wire_ref())
+    }
+}
+
+impl From<VirtualWire> for Var {
+    fn from(virtual_wire: VirtualWire) -> Self {
+        Var::Wire(virtual_wire.wire_ref())
+    }
+}
+
+impl From<WireRef> for Var {
+    fn from(wire_ref: WireRef) -> Self {
+        Var::Wire(wire_ref)
+    }
+}
+
+/// Every variable read by `expr`
+#[must_use]
+pub fn expression_vars(expr: &Expression) -> BTreeSet<Var> {
+    let mut vars = BTreeSet::new();
+    collect_expression_vars(expr, &mut vars);
+    vars
+}
+
+fn collect_expression_vars(expr: &Expression, vars: &mut BTreeSet<Var>) {
+    match expr {
+        Expression::BinaryOperator { lhs, rhs, .. } => {
+            collect_expression_vars(lhs, vars);
+            collect_expression_vars(rhs, vars);
+        }
+        Expression::Ident(ident) => {
+            vars.insert(ident.into());
+        }
+        Expression::Value(_) => {}
+        Expression::Unary { expr, .. } => collect_expression_vars(expr, vars),
+    }
+}
+
+/// Variable assigned by `stmt`, if any
+#[must_use]
+pub fn stmt_def(stmt: &Stmt) -> Option<Var> {
+    match stmt {
+        Stmt::Local(ident, _) => Some(ident.into()),
+        Stmt::Verify(_) => None,
+    }
+}
+
+/// Every variable read by `stmt`
+#[must_use]
+pub fn stmt_uses(stmt: &Stmt) -> BTreeSet<Var> {
+    match stmt {
+        Stmt::Verify(expr) | Stmt::Local(_, expr) => expression_vars(expr),
+    }
+}
+
+/// Public inputs and outputs of `cir` in declaration order: the entries of `signature`, then `public_wire_inputs`,
+/// then `public_virtual_wire_inputs`, then any other wire typed `Wiretype::Public` in order of first appearance. With a
+/// complete signature this is the proof's public input vector.
+#[must_use]
+pub fn public_values(cir: &Cir) -> Vec<WireRef> {
+    let mut public_values = Vec::new();
+
+    let mut push = |wire_ref| {
+        if !public_values.contains(&wire_ref) {
+            public_values.push(wire_ref);
+        }
+    };
+
+    for wire_ref in cir
+        .signature
+        .iter()
+        .map(|public_value| public_value.wire_ref)
+        .chain(cir.public_wire_inputs.iter().map(Wire::wire_ref))
+        .chain(
+            cir.public_virtual_wire_inputs
+                .iter()
+                .map(VirtualWire::wire_ref),
+        )
+    {
+        push(wire_ref);
+    }
+
+    for stmt in &cir.stmts {
+        let mut stmt = stmt.clone();
+        stmt.visit_wires(&mut |wire| {
+            if wire.wiretype == Wiretype::Public {
+                push(wire.wire_ref());
+            }
+        });
+        stmt.visit_virtual_wires(&mut |virtual_wire| {
+            if virtual_wire.wiretype == Wiretype::Public {
+                push(virtual_wire.wire_ref());
+            }
+        });
+    }
+
+    public_values
+}
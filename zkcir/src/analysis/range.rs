@@ -0,0 +1,611 @@
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::Var;
+use crate::ast::{BinOp, Expression, Stmt, Value, WireRef};
+use crate::ir::Cir;
+
+/// Maximum passes over the statements before giving up on narrowing further. Cyclic constraints like
+/// `verify!(x == y + 1)` and `verify!(y == x - 1)` could otherwise narrow by one per pass.
+const MAX_PASSES: usize = 8;
+
+/// Inclusive range of integer values
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Interval {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl Interval {
+    #[must_use]
+    pub fn new(min: u64, max: u64) -> Self {
+        Self { min, max }
+    }
+
+    #[must_use]
+    pub fn constant(value: u64) -> Self {
+        Self::new(value, value)
+    }
+
+    /// Values that fit in `bits` bits
+    #[must_use]
+    pub fn bits(bits: u32) -> Self {
+        Self::new(0, u64::MAX.checked_shr(64 - bits.min(64)).unwrap_or(0))
+    }
+
+    /// Number of bits needed to represent `max`
+    #[must_use]
+    pub fn num_bits(&self) -> u32 {
+        u64::BITS - self.max.leading_zeros()
+    }
+
+    /// `None` if the intervals don't overlap
+    #[must_use]
+    pub fn intersect(&self, other: &Interval) -> Option<Interval> {
+        let (min, max) = (self.min.max(other.min), self.max.min(other.max));
+        (min <= max).then_some(Interval::new(min, max))
+    }
+
+    fn add(&self, other: &Interval) -> Option<Interval> {
+        Some(Interval::new(
+            self.min.checked_add(other.min)?,
+            self.max.checked_add(other.max)?,
+        ))
+    }
+
+    /// `None` if the result could be negative and wrap around the field
+    fn sub(&self, other: &Interval) -> Option<Interval> {
+        Some(Interval::new(
+            self.min.checked_sub(other.max)?,
+            self.max.checked_sub(other.min)?,
+        ))
+    }
+
+    fn mul(&self, other: &Interval) -> Option<Interval> {
+        Some(Interval::new(
+            self.min.checked_mul(other.min)?,
+            self.max.checked_mul(other.max)?,
+        ))
+    }
+
+    fn pow(&self, exponent: u64) -> Option<Interval> {
+        let exponent = u32::try_from(exponent).ok()?;
+
+        Some(Interval::new(
+            self.min.checked_pow(exponent)?,
+            self.max.checked_pow(exponent)?,
+        ))
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}, {}]", self.min, self.max)
+    }
+}
+
+/// Value ranges of wires and locals inferred by abstract interpretation over intervals. Ranges come from booleanity
+/// checks, bit decompositions and equalities in `Stmt::Verify`, and propagate through `Stmt::Local` with interval
+/// arithmetic. Comparisons like `verify!(x < 100)` don't establish a range, since field comparisons are only sound on
+/// operands that are already range checked. Arithmetic that could reach the field's modulus or go negative is
+/// treated as unknown, since it could wrap around the field.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct RangeAnalysis {
+    ranges: BTreeMap<Var, Interval>,
+
+    /// Order of the field, or `None` if it doesn't fit in a `u64`
+    modulus: Option<u64>,
+
+    /// Statement that last narrowed each range
+    origins: BTreeMap<Var, usize>,
+}
+
+impl RangeAnalysis {
+    #[must_use]
+    pub fn new(cir: &Cir) -> Self {
+        let mut analysis = Self {
+            modulus: cir.config.field().modulus(),
+            ..Self::default()
+        };
+
+        for _ in 0..MAX_PASSES {
+            let mut changed = false;
+
+            for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+                for (var, interval) in analysis.facts(stmt) {
+                    changed |= analysis.narrow(var, interval, stmt_index);
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        analysis
+    }
+
+    #[must_use]
+    pub fn range(&self, var: impl Into<Var>) -> Option<Interval> {
+        self.ranges.get(&var.into()).copied()
+    }
+
+    #[must_use]
+    pub fn wire(&self, row: usize, column: usize) -> Option<Interval> {
+        self.range(WireRef::Wire { row, column })
+    }
+
+    #[must_use]
+    pub fn virtual_wire(&self, index: usize) -> Option<Interval> {
+        self.range(WireRef::VirtualWire { index })
+    }
+
+    #[must_use]
+    pub fn local(&self, name: &str) -> Option<Interval> {
+        self.range(Var::Local(name.into()))
+    }
+
+    /// Index of the statement that established the final range of `var`
+    #[must_use]
+    pub fn origin(&self, var: impl Into<Var>) -> Option<usize> {
+        self.origins.get(&var.into()).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Var, &Interval)> {
+        self.ranges.iter()
+    }
+
+    /// Code IR of `cir` where every statement is followed by the ranges it established, like
+    /// `verify!(...); // x: [0, 255]`
+    #[must_use]
+    pub fn to_code_ir(&self, cir: &Cir) -> String {
+        let mut comments: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+
+        for (var, stmt_index) in &self.origins {
+            comments.entry(*stmt_index).or_default().push(format!(
+                "{}: {}",
+                cir.var_name(var),
+                self.ranges[var]
+            ));
+        }
+
+        cir.to_code_ir_with_comments(&|stmt_index| {
+            comments
+                .get(&stmt_index)
+                .map(|comments| comments.join(", "))
+        })
+    }
+
+    /// Returns whether the range of `var` changed
+    fn narrow(&mut self, var: Var, interval: Interval, stmt_index: usize) -> bool {
+        let narrowed = match self.ranges.get(&var) {
+            // Contradictory constraints make the circuit unsatisfiable, which is out of scope here
+            Some(existing) => match existing.intersect(&interval) {
+                Some(narrowed) if narrowed != *existing => narrowed,
+                _ => return false,
+            },
+            None => interval,
+        };
+
+        self.ranges.insert(var.clone(), narrowed);
+        self.origins.insert(var, stmt_index);
+        true
+    }
+
+    fn facts(&self, stmt: &Stmt) -> Vec<(Var, Interval)> {
+        let mut facts = Vec::new();
+
+        match stmt {
+            Stmt::Local(ident, expr) => {
+                if let Some(interval) = self.eval(expr) {
+                    facts.push((ident.into(), interval));
+                }
+            }
+            Stmt::Verify(expr) => {
+                if let Some(var) = boolean_var(expr) {
+                    facts.push((var, Interval::bits(1)));
+                }
+
+                if let Some((lhs, rhs)) = equality(expr) {
+                    for (x, other) in [(lhs, rhs), (rhs, lhs)] {
+                        if let (Some(var), Some(bound)) = (ident(x), self.eval(other)) {
+                            facts.push((var, bound));
+                        }
+                    }
+                }
+            }
+        }
+
+        facts
+    }
+
+    /// `None` if the value could be at least the modulus, where it would wrap around
+    fn eval(&self, expr: &Expression) -> Option<Interval> {
+        let interval = match expr {
+            Expression::Value(Value::U64(value)) => Some(Interval::constant(*value)),
+            Expression::Ident(ident) => self.range(ident),
+            Expression::BinaryOperator { lhs, binop, rhs } => match binop {
+                BinOp::Add => self.eval(lhs)?.add(&self.eval(rhs)?),
+                BinOp::Subtract => self.eval(lhs)?.sub(&self.eval(rhs)?),
+                BinOp::Multiply => self.eval(lhs)?.mul(&self.eval(rhs)?),
+                BinOp::Exponent => self.eval(lhs)?.pow(constant(rhs)?),
+                BinOp::Equal
+                | BinOp::LessThan
+                | BinOp::LessThanEqual
+                | BinOp::GreaterThan
+                | BinOp::GreaterThanEqual => Some(Interval::bits(1)),
+                BinOp::Divide => None,
+            },
+            Expression::Value(_) | Expression::Unary { .. } => None,
+        }?;
+
+        match self.modulus {
+            Some(modulus) if interval.max >= modulus => None,
+            _ => Some(interval),
+        }
+    }
+}
+
+/// Evidence that a variable fits in `bits` bits
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RangeCheck {
+    /// Index of the statement completing the range check. For bit decompositions this is the latest of the
+    /// decomposition and every booleanity check of its bits.
+    pub stmt_index: usize,
+    pub bits: u32,
+}
+
+/// Earliest range check of every variable found through booleanity checks like `verify!(b * (b - 1) == 0)` and bit
+/// decompositions like `verify!(x == b0 + b1 * 2 + b2 * 4)` whose bits are boolean checked
+#[must_use]
+pub fn range_checks(cir: &Cir) -> BTreeMap<Var, RangeCheck> {
+    let mut checks = BTreeMap::new();
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        if let Stmt::Verify(expr) = stmt {
+            if let Some(var) = boolean_var(expr) {
+                checks.entry(var).or_insert(RangeCheck {
+                    stmt_index,
+                    bits: 1,
+                });
+            }
+        }
+    }
+
+    let booleans = checks.clone();
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        let Stmt::Verify(expr) = stmt else {
+            continue;
+        };
+
+        let Some((var, bits)) = bit_decomposition(expr) else {
+            continue;
+        };
+
+        let Some(completed_at) = bits.iter().try_fold(stmt_index, |latest, (bit, _)| {
+            booleans.get(bit).map(|check| latest.max(check.stmt_index))
+        }) else {
+            continue;
+        };
+
+        // The largest value is the sum of every weight, which can take more bits than the largest weight when weights
+        // repeat, like `x == b0 + b1`
+        let max = bits.iter().fold(0u128, |sum, (_, exponent)| {
+            sum.saturating_add(1u128.checked_shl(*exponent).unwrap_or(u128::MAX))
+        });
+
+        let check = RangeCheck {
+            stmt_index: completed_at,
+            bits: u128::BITS - max.leading_zeros(),
+        };
+
+        checks
+            .entry(var)
+            .and_modify(|existing| {
+                if completed_at < existing.stmt_index {
+                    *existing = check;
+                }
+            })
+            .or_insert(check);
+    }
+
+    checks
+}
+
+/// Variable constrained to be 0 or 1 by `expr`, matching `x * (x - 1) == 0`, `x * (1 - x) == 0` and `x * x == x` in
+/// any operand order
+#[must_use]
+pub fn boolean_var(expr: &Expression) -> Option<Var> {
+    let (lhs, rhs) = equality(expr)?;
+
+    for (product, other) in [(lhs, rhs), (rhs, lhs)] {
+        let Some((a, b)) = multiplication(product) else {
+            continue;
+        };
+
+        // x * (x - 1) == 0
+        if constant(other) == Some(0) {
+            for (x, shifted) in [(a, b), (b, a)] {
+                if let (Some(var), Some((y, BinOp::Subtract, z))) = (ident(x), binary(shifted)) {
+                    if (ident(y).as_ref() == Some(&var) && constant(z) == Some(1))
+                        || (constant(y) == Some(1) && ident(z).as_ref() == Some(&var))
+                    {
+                        return Some(var);
+                    }
+                }
+            }
+        }
+
+        // x * x == x
+        if let (Some(a), Some(b), Some(other)) = (ident(a), ident(b), ident(other)) {
+            if a == b && b == other {
+                return Some(a);
+            }
+        }
+    }
+
+    None
+}
+
+/// Variable and its bits with their exponents, matching `x == b0 + b1 * 2 + b2 * 4 + ...` in any operand order. The
+/// bits aren't checked to be boolean.
+#[must_use]
+pub fn bit_decomposition(expr: &Expression) -> Option<(Var, Vec<(Var, u32)>)> {
+    let (lhs, rhs) = equality(expr)?;
+
+    for (x, sum) in [(lhs, rhs), (rhs, lhs)] {
+        let Some(var) = ident(x) else {
+            continue;
+        };
+
+        let mut bits = Vec::new();
+        if collect_weighted_bits(sum, &mut bits) && bits.len() > 1 {
+            return Some((var, bits));
+        }
+    }
+
+    None
+}
+
+fn collect_weighted_bits(expr: &Expression, bits: &mut Vec<(Var, u32)>) -> bool {
+    if let Some((lhs, BinOp::Add, rhs)) = binary(expr) {
+        return collect_weighted_bits(lhs, bits) && collect_weighted_bits(rhs, bits);
+    }
+
+    if let Some(var) = ident(expr) {
+        bits.push((var, 0));
+        return true;
+    }
+
+    if let Some((a, b)) = multiplication(expr) {
+        for (bit, weight) in [(a, b), (b, a)] {
+            if let (Some(var), Some(weight)) = (ident(bit), constant(weight)) {
+                if weight.is_power_of_two() {
+                    bits.push((var, weight.trailing_zeros()));
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Sides of `lhs == rhs`
+pub(crate) fn equality(expr: &Expression) -> Option<(&Expression, &Expression)> {
+    match binary(expr)? {
+        (lhs, BinOp::Equal, rhs) => Some((lhs, rhs)),
+        _ => None,
+    }
+}
+
+/// Operands of `lhs * rhs`
+pub(crate) fn multiplication(expr: &Expression) -> Option<(&Expression, &Expression)> {
+    match binary(expr)? {
+        (lhs, BinOp::Multiply, rhs) => Some((lhs, rhs)),
+        _ => None,
+    }
+}
+
+pub(crate) fn binary(expr: &Expression) -> Option<(&Expression, BinOp, &Expression)> {
+    match expr {
+        Expression::BinaryOperator { lhs, binop, rhs } => Some((lhs, *binop, rhs)),
+        _ => None,
+    }
+}
+
+pub(crate) fn ident(expr: &Expression) -> Option<Var> {
+    match expr {
+        Expression::Ident(ident) => Some(ident.into()),
+        _ => None,
+    }
+}
+
+/// Value of `expr` if it is a constant, folding arithmetic like `2 ^ 8` as long as it doesn't overflow
+pub(crate) fn constant(expr: &Expression) -> Option<u64> {
+    match expr {
+        Expression::Value(Value::U64(value)) => Some(*value),
+        Expression::BinaryOperator { lhs, binop, rhs } => {
+            let (lhs, rhs) = (constant(lhs)?, constant(rhs)?);
+
+            match binop {
+                BinOp::Add => lhs.checked_add(rhs),
+                BinOp::Subtract => lhs.checked_sub(rhs),
+                BinOp::Multiply => lhs.checked_mul(rhs),
+                BinOp::Exponent => lhs.checked_pow(u32::try_from(rhs).ok()?),
+                _ => None,
+            }
+        }
+        Expression::Unary { .. } | Expression::Value(_) | Expression::Ident(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::eval::GOLDILOCKS_ORDER,
+        ast::{Ident, Wire, WireRef},
+        ir::CirBuilder,
+        test_util::{binop, test_code_ir},
+    };
+
+    use super::*;
+
+    fn bit(column: usize) -> Wire {
+        Wire::new_private(1, column)
+    }
+
+    fn booleanity(column: usize) -> Stmt {
+        Stmt::Verify(binop(
+            binop(
+                bit(column),
+                BinOp::Multiply,
+                binop(
+                    bit(column),
+                    BinOp::Subtract,
+                    Expression::Value(Value::U64(1)),
+                ),
+            ),
+            BinOp::Equal,
+            Expression::Value(Value::U64(0)),
+        ))
+    }
+
+    #[test]
+    fn test_bit_decomposition() {
+        let cir = CirBuilder::new()
+            .add_stmt(booleanity(0))
+            .add_stmt(booleanity(1))
+            .add_stmt(Stmt::Verify(binop(
+                Expression::Ident(Ident::Wire(Wire::new_private(0, 0))),
+                BinOp::Equal,
+                binop(
+                    bit(0),
+                    BinOp::Add,
+                    binop(bit(1), BinOp::Multiply, Expression::Value(Value::U64(2))),
+                ),
+            )))
+            .add_stmt(booleanity(2))
+            .build();
+
+        let checks = range_checks(&cir);
+
+        assert_eq!(
+            checks.get(&Var::Wire(WireRef::Wire { row: 0, column: 0 })),
+            Some(&RangeCheck {
+                stmt_index: 2,
+                bits: 2
+            })
+        );
+        assert_eq!(
+            checks.get(&Var::Wire(WireRef::Wire { row: 1, column: 2 })),
+            Some(&RangeCheck {
+                stmt_index: 3,
+                bits: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_repeated_weights() {
+        let cir = CirBuilder::new()
+            .add_stmt(booleanity(0))
+            .add_stmt(booleanity(1))
+            .add_stmt(Stmt::Verify(binop(
+                Expression::Ident(Ident::Wire(Wire::new_private(0, 0))),
+                BinOp::Equal,
+                binop(bit(0), BinOp::Add, bit(1)),
+            )))
+            .build();
+
+        assert_eq!(
+            range_checks(&cir).get(&Var::Wire(WireRef::Wire { row: 0, column: 0 })),
+            Some(&RangeCheck {
+                stmt_index: 2,
+                bits: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_wraparound() {
+        let cir = CirBuilder::new()
+            .add_stmt(booleanity(0))
+            .add_stmt(Stmt::Local(
+                "small".into(),
+                binop(bit(0), BinOp::Add, Expression::Value(Value::U64(1))),
+            ))
+            .add_stmt(Stmt::Local(
+                "wrapped".into(),
+                binop(
+                    bit(0),
+                    BinOp::Add,
+                    Expression::Value(Value::U64(GOLDILOCKS_ORDER - 1)),
+                ),
+            ))
+            .build();
+
+        let analysis = RangeAnalysis::new(&cir);
+
+        assert_eq!(analysis.local("small"), Some(Interval::new(1, 2)));
+        // `1 + (p - 1)` wraps around to 0
+        assert_eq!(analysis.local("wrapped"), None);
+    }
+
+    #[test]
+    fn test_range_analysis() {
+        let cir = CirBuilder::new()
+            .add_stmt(booleanity(0))
+            .add_stmt(booleanity(1))
+            .add_stmt(Stmt::Verify(binop(
+                Expression::Ident(Ident::Wire(Wire::new_private(0, 0))),
+                BinOp::Equal,
+                binop(
+                    bit(0),
+                    BinOp::Add,
+                    binop(bit(1), BinOp::Multiply, Expression::Value(Value::U64(2))),
+                ),
+            )))
+            .add_stmt(Stmt::Local(
+                "shifted".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Add,
+                    Expression::Value(Value::U64(10)),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                Expression::Value(Value::U64(100)),
+                BinOp::GreaterThan,
+                Wire::new_private(2, 0),
+            )))
+            .add_stmt(Stmt::Local(
+                "quotient".into(),
+                binop(Wire::new_private(2, 0), BinOp::Divide, bit(0)),
+            ))
+            .name_wire(0, 0, "x")
+            .build();
+
+        let analysis = RangeAnalysis::new(&cir);
+
+        assert_eq!(analysis.wire(1, 0), Some(Interval::new(0, 1)));
+        assert_eq!(analysis.wire(0, 0), Some(Interval::new(0, 3)));
+        assert_eq!(analysis.local("shifted"), Some(Interval::new(10, 13)));
+        // An unchecked comparison doesn't prove a range
+        assert_eq!(analysis.wire(2, 0), None);
+        assert_eq!(analysis.local("quotient"), None);
+        assert_eq!(analysis.origin(Wire::new_private(0, 0)), Some(2));
+
+        test_code_ir("range_analysis", &analysis.to_code_ir(&cir));
+    }
+}
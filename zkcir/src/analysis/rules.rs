@@ -0,0 +1,337 @@
+//! Project-specific lint rules loaded from configuration, like a `zkcir.toml`
+//!
+//! Each rule is a `query` pattern, and every statement it matches is reported as a `Finding`:
+//!
+//! ```toml
+//! [[rule]]
+//! name = "public-division"
+//! pattern = "$a / $b:wire"
+//! message = "`$a` is divided by wire `$b`"
+//! severity = "error"
+//! allow = { stmts = [4], names = ["fee_rate"], tags = ["reviewed"], gadgets = ["range_check"] }
+//! ```
+//!
+//! `$name` in the message is replaced by the expression the metavariable matched. A match is allowed, and not
+//! reported, if its statement is listed in `stmts` or belongs to a gadget in `gadgets`, or if a metavariable matched
+//! an expression reading a wire or local named in `names` or a wire tagged with one of `tags`.
+
+extern crate alloc;
+
+use alloc::string::String;
+// Used by the code `tsify(from_wasm_abi)` generates
+#[cfg(feature = "tsify")]
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use derive_more::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::lint::{lint, Finding, Severity};
+use crate::analysis::{expression_vars, Var};
+use crate::ir::Cir;
+use crate::node::Node;
+use crate::parse::ParseError;
+use crate::query::{query, Bindings, Pattern};
+
+/// Name of the rule file, which the CLI discovers in the current directory or its ancestors and the server reads from
+/// the repository root
+pub const RULES_FILE_NAME: &str = "zkcir.toml";
+
+/// Rules declared in a configuration file
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify), tsify(from_wasm_abi))]
+pub struct RuleSet {
+    #[cfg_attr(feature = "serde", serde(default, rename = "rule"))]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct Rule {
+    /// Reported as `Finding::rule`, like `public-division`
+    pub name: String,
+
+    /// `query` pattern, like `$a / $b:wire`
+    pub pattern: String,
+
+    /// Reported for each match, with `$name` replaced by what the metavariable matched
+    pub message: String,
+
+    #[cfg_attr(feature = "serde", serde(default = "default_severity"))]
+    pub severity: Severity,
+
+    /// Why the rule matters, reported as `Finding::explanation`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub explanation: String,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub allow: Allow,
+}
+
+/// Matches a rule doesn't report, like code that was already reviewed
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct Allow {
+    /// Indices into `Cir::stmts`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stmts: Vec<usize>,
+
+    /// Wire or local names
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub names: Vec<String>,
+
+    /// Wire tags
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tags: Vec<String>,
+
+    /// Gadget names
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub gadgets: Vec<String>,
+}
+
+/// Rule whose pattern couldn't be parsed
+#[derive(PartialEq, Eq, Clone, Debug, Display)]
+#[display(fmt = "rule `{rule}`: {error}")]
+pub struct RuleError {
+    pub rule: String,
+    pub error: ParseError,
+}
+
+#[cfg(feature = "serde")]
+fn default_severity() -> Severity {
+    Severity::Warning
+}
+
+impl RuleSet {
+    /// Findings of every rule, in rule order then statement order
+    ///
+    /// # Errors
+    ///
+    /// Errors on the first rule with an invalid pattern
+    pub fn check(&self, cir: &Cir) -> Result<Vec<Finding>, RuleError> {
+        let mut findings = Vec::new();
+
+        for rule in &self.rules {
+            findings.extend(rule.check(cir)?);
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Rule {
+    /// # Errors
+    ///
+    /// Errors if `pattern` is invalid
+    pub fn check(&self, cir: &Cir) -> Result<Vec<Finding>, RuleError> {
+        let pattern = Pattern::parse(&self.pattern).map_err(|error| RuleError {
+            rule: self.name.clone(),
+            error,
+        })?;
+
+        Ok(query(cir, &pattern)
+            .into_iter()
+            .filter(|found| !self.allow.allows(cir, found.stmt_index, &found.bindings))
+            .map(|found| Finding {
+                rule: self.name.clone(),
+                severity: self.severity,
+                stmt_index: found.stmt_index,
+                message: interpolate(&self.message, &found.bindings),
+                explanation: self.explanation.clone(),
+            })
+            .collect())
+    }
+}
+
+impl Allow {
+    #[must_use]
+    pub fn allows(&self, cir: &Cir, stmt_index: usize, bindings: &Bindings) -> bool {
+        if self.stmts.contains(&stmt_index) {
+            return true;
+        }
+
+        let in_gadget = cir.gadgets.iter().any(|gadget| {
+            (gadget.start..gadget.end).contains(&stmt_index) && self.gadgets.contains(&gadget.name)
+        });
+        if in_gadget {
+            return true;
+        }
+
+        bindings
+            .values()
+            .flat_map(expression_vars)
+            .any(|var| self.allows_var(cir, &var))
+    }
+
+    fn allows_var(&self, cir: &Cir, var: &Var) -> bool {
+        match var {
+            Var::Local(name) => self.names.contains(name),
+            Var::Wire(wire_ref) => cir.annotation(*wire_ref).is_some_and(|annotation| {
+                annotation
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| self.names.contains(name))
+                    || annotation.tags.iter().any(|tag| self.tags.contains(tag))
+            }),
+        }
+    }
+}
+
+/// Built-in lints followed by the findings of `rules`, sorted by statement
+///
+/// # Errors
+///
+/// Errors on the first rule with an invalid pattern
+pub fn lint_with_rules(cir: &Cir, rules: &RuleSet) -> Result<Vec<Finding>, RuleError> {
+    let mut findings = lint(cir);
+    findings.extend(rules.check(cir)?);
+    findings.sort_by_key(|finding| finding.stmt_index);
+    Ok(findings)
+}
+
+/// Replaces each `$name` in `message` with the code IR of its binding, leaving unbound names as is
+fn interpolate(message: &str, bindings: &Bindings) -> String {
+    let mut interpolated = String::new();
+    let mut rest = message;
+
+    while let Some(start) = rest.find('$') {
+        interpolated.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+
+        match bindings.get(&rest[..end]) {
+            Some(expr) if end > 0 => interpolated.push_str(&expr.to_code_ir()),
+            _ => {
+                interpolated.push('$');
+                interpolated.push_str(&rest[..end]);
+            }
+        }
+
+        rest = &rest[end..];
+    }
+
+    interpolated.push_str(rest);
+    interpolated
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{BinOp, Stmt, Wire},
+        ir::CirBuilder,
+        test_util::binop,
+    };
+
+    use super::*;
+
+    fn rule(pattern: &str, allow: Allow) -> Rule {
+        Rule {
+            name: "public-division".into(),
+            pattern: pattern.into(),
+            message: "`$a` is divided by `$b`".into(),
+            severity: Severity::Error,
+            explanation: String::new(),
+            allow,
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                "ratio".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Divide,
+                    Wire::new_public(0, 1),
+                ),
+            ))
+            .begin_gadget("range_check")
+            .add_stmt(Stmt::Local(
+                "scaled".into(),
+                binop(
+                    Wire::new_private(0, 2),
+                    BinOp::Divide,
+                    Wire::new_public(0, 3),
+                ),
+            ))
+            .end_gadget()
+            .name_wire(0, 1, "fee_rate")
+            .tag_wire(0, 3, "reviewed")
+            .build();
+
+        let findings = rule("$a / $b:wire", Allow::default()).check(&cir).unwrap();
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| (finding.stmt_index, finding.message.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (0, "`wire::private(row: 0, column: 0)` is divided by `wire::public(row: 0, column: 1)`"),
+                (1, "`wire::private(row: 0, column: 2)` is divided by `wire::public(row: 0, column: 3)`")
+            ]
+        );
+
+        for allow in [
+            Allow {
+                stmts: alloc::vec![0, 1],
+                ..Allow::default()
+            },
+            Allow {
+                stmts: alloc::vec![1],
+                names: alloc::vec!["fee_rate".into()],
+                ..Allow::default()
+            },
+            Allow {
+                names: alloc::vec!["fee_rate".into()],
+                tags: alloc::vec!["reviewed".into()],
+                ..Allow::default()
+            },
+            Allow {
+                stmts: alloc::vec![0],
+                gadgets: alloc::vec!["range_check".into()],
+                ..Allow::default()
+            },
+        ] {
+            assert_eq!(rule("$a / $b:wire", allow).check(&cir).unwrap(), []);
+        }
+
+        let error = rule("$a / $b:number", Allow::default())
+            .check(&cir)
+            .unwrap_err();
+        assert_eq!(error.rule, "public-division");
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_defaults() {
+        let rules: RuleSet = serde_json::from_str(
+            r#"{"rule": [{"name": "no-exp", "pattern": "$a ^ $b", "message": "exponent", "severity": "info"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(rules.rules[0].severity, Severity::Info);
+        assert_eq!(rules.rules[0].allow, Allow::default());
+        assert_eq!(
+            serde_json::from_str::<RuleSet>("{}").unwrap(),
+            RuleSet::default()
+        );
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let mut bindings = Bindings::new();
+        bindings.insert("a".into(), Wire::new_private(0, 0).into());
+
+        assert_eq!(
+            interpolate("$a costs $5, $b and $", &bindings),
+            "wire::private(row: 0, column: 0) costs $5, $b and $"
+        );
+    }
+}
@@ -0,0 +1,492 @@
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::analysis::{expression_vars, stmt_def, stmt_uses, Var};
+use crate::ast::{Stmt, Value, WireRef};
+use crate::ir::{Cir, TranscriptOp};
+use crate::node::Node;
+
+/// Which statements a statement is connected to through its variables
+struct DependencyIndex {
+    defs: Vec<Option<Var>>,
+    uses: Vec<BTreeSet<Var>>,
+
+    /// Locals assigning each variable
+    defined_by: BTreeMap<Var, Vec<usize>>,
+
+    /// Locals reading each variable
+    read_by_locals: BTreeMap<Var, Vec<usize>>,
+
+    /// Verifies reading each variable
+    verified_by: BTreeMap<Var, Vec<usize>>,
+
+    /// Locals whose value ends up in a verify, directly or through other locals
+    constraining: BTreeSet<usize>,
+}
+
+impl DependencyIndex {
+    fn new(cir: &Cir) -> Self {
+        let defs = cir.stmts.iter().map(stmt_def).collect::<Vec<_>>();
+        let uses = cir.stmts.iter().map(stmt_uses).collect::<Vec<_>>();
+
+        let mut defined_by: BTreeMap<Var, Vec<usize>> = BTreeMap::new();
+        let mut read_by_locals: BTreeMap<Var, Vec<usize>> = BTreeMap::new();
+        let mut verified_by: BTreeMap<Var, Vec<usize>> = BTreeMap::new();
+
+        for (i, stmt) in cir.stmts.iter().enumerate() {
+            let readers = match stmt {
+                Stmt::Local(..) => &mut read_by_locals,
+                Stmt::Verify(_) => &mut verified_by,
+            };
+
+            for var in &uses[i] {
+                readers.entry(var.clone()).or_default().push(i);
+            }
+
+            if let Some(def) = &defs[i] {
+                defined_by.entry(def.clone()).or_default().push(i);
+            }
+        }
+
+        let mut constraining = BTreeSet::new();
+        loop {
+            let before = constraining.len();
+
+            for (i, def) in defs.iter().enumerate() {
+                let Some(def) = def else {
+                    continue;
+                };
+
+                if verified_by.contains_key(def)
+                    || read_by_locals
+                        .get(def)
+                        .into_iter()
+                        .flatten()
+                        .any(|reader| constraining.contains(reader))
+                {
+                    constraining.insert(i);
+                }
+            }
+
+            if constraining.len() == before {
+                break;
+            }
+        }
+
+        Self {
+            defs,
+            uses,
+            defined_by,
+            read_by_locals,
+            verified_by,
+            constraining,
+        }
+    }
+}
+
+/// Indices of statements that define or constrain `target`, directly or transitively. Locals pull in the variables
+/// they read, and verifies pull in every variable they constrain together with `target`, including through locals
+/// that read `target` and end up in a verify, like `let x = target + 1; verify!(x == 5);`.
+#[must_use]
+pub fn backward_slice_indices(cir: &Cir, target: impl Into<Var>) -> Vec<usize> {
+    backward_indices(cir, [target.into()].into(), BTreeSet::new())
+}
+
+/// Same as `backward_slice_indices` but starting from the statement at `stmt_index`
+#[must_use]
+pub fn backward_slice_indices_from_stmt(cir: &Cir, stmt_index: usize) -> Vec<usize> {
+    let Some(stmt) = cir.stmts.get(stmt_index) else {
+        return Vec::new();
+    };
+
+    backward_indices(cir, stmt_uses(stmt), [stmt_index].into())
+}
+
+/// Indices of statements whose value or outcome depends on `target`, directly or transitively through locals
+#[must_use]
+pub fn forward_slice_indices(cir: &Cir, target: impl Into<Var>) -> Vec<usize> {
+    forward_indices(cir, [target.into()].into(), BTreeSet::new())
+}
+
+/// Same as `forward_slice_indices` but starting from the statement at `stmt_index`
+#[must_use]
+pub fn forward_slice_indices_from_stmt(cir: &Cir, stmt_index: usize) -> Vec<usize> {
+    let Some(stmt) = cir.stmts.get(stmt_index) else {
+        return Vec::new();
+    };
+
+    forward_indices(
+        cir,
+        stmt_def(stmt).into_iter().collect(),
+        [stmt_index].into(),
+    )
+}
+
+/// Minimal sub-circuit of statements influencing `target`. See `backward_slice_indices`.
+#[must_use]
+pub fn backward_slice(cir: &Cir, target: impl Into<Var>) -> Cir {
+    sub_cir(cir, &backward_slice_indices(cir, target))
+}
+
+/// Sub-circuit of statements influenced by `target`. See `forward_slice_indices`.
+#[must_use]
+pub fn forward_slice(cir: &Cir, target: impl Into<Var>) -> Cir {
+    sub_cir(cir, &forward_slice_indices(cir, target))
+}
+
+fn backward_indices(cir: &Cir, seeds: BTreeSet<Var>, mut included: BTreeSet<usize>) -> Vec<usize> {
+    let index = DependencyIndex::new(cir);
+    let mut visited = BTreeSet::new();
+    let mut worklist = seeds.into_iter().collect::<Vec<_>>();
+
+    while let Some(var) = worklist.pop() {
+        if !visited.insert(var.clone()) {
+            continue;
+        }
+
+        let sources = index.defined_by.get(&var).into_iter().flatten();
+        let constraints = index.verified_by.get(&var).into_iter().flatten();
+        let constrained_readers = index
+            .read_by_locals
+            .get(&var)
+            .into_iter()
+            .flatten()
+            .filter(|i| index.constraining.contains(i));
+
+        for &i in sources.chain(constraints).chain(constrained_readers) {
+            if included.insert(i) {
+                worklist.extend(index.uses[i].iter().cloned());
+                worklist.extend(index.defs[i].iter().cloned());
+            }
+        }
+    }
+
+    included.into_iter().collect()
+}
+
+fn forward_indices(cir: &Cir, seeds: BTreeSet<Var>, mut included: BTreeSet<usize>) -> Vec<usize> {
+    let index = DependencyIndex::new(cir);
+    let mut visited = BTreeSet::new();
+    let mut worklist = seeds.into_iter().collect::<Vec<_>>();
+
+    while let Some(var) = worklist.pop() {
+        if !visited.insert(var.clone()) {
+            continue;
+        }
+
+        for &i in index.read_by_locals.get(&var).into_iter().flatten() {
+            if included.insert(i) {
+                worklist.extend(index.defs[i].iter().cloned());
+            }
+        }
+
+        for &i in index.verified_by.get(&var).into_iter().flatten() {
+            included.insert(i);
+        }
+    }
+
+    included.into_iter().collect()
+}
+
+/// Copy of `cir` with only the statements at `indices`, ignoring indices out of range. Public inputs, the signature and
+/// annotations are kept only for wires that still appear, and gadgets are dropped since their ranges no longer apply.
+/// Absorbs are kept only if everything they read still appears, and random sources only if a kept statement or absorb
+/// samples them, renumbered in their original order.
+#[must_use]
+pub fn sub_cir(cir: &Cir, indices: &[usize]) -> Cir {
+    let stmts = indices
+        .iter()
+        .filter_map(|&i| cir.stmts.get(i).cloned())
+        .collect::<Vec<_>>();
+
+    let vars = stmts
+        .iter()
+        .flat_map(|stmt| stmt_uses(stmt).into_iter().chain(stmt_def(stmt)))
+        .collect::<BTreeSet<Var>>();
+
+    let wires = vars
+        .iter()
+        .filter_map(|var| match var {
+            Var::Wire(wire_ref) => Some(*wire_ref),
+            Var::Local(_) => None,
+        })
+        .collect::<BTreeSet<WireRef>>();
+
+    let mut sub = Cir {
+        config: cir.config,
+        stmts,
+        public_wire_inputs: cir
+            .public_wire_inputs
+            .iter()
+            .filter(|wire| wires.contains(&wire.wire_ref()))
+            .copied()
+            .collect(),
+        public_virtual_wire_inputs: cir
+            .public_virtual_wire_inputs
+            .iter()
+            .filter(|wire| wires.contains(&wire.wire_ref()))
+            .copied()
+            .collect(),
+        signature: cir
+            .signature
+            .iter()
+            .filter(|public_value| wires.contains(&public_value.wire_ref))
+            .cloned()
+            .collect(),
+        annotations: cir
+            .annotations
+            .iter()
+            .filter(|annotation| wires.contains(&annotation.wire_ref))
+            .cloned()
+            .collect(),
+        gadgets: Vec::new(),
+        random_sources: Vec::new(),
+        transcript: cir
+            .transcript
+            .iter()
+            .filter(|op| match op {
+                TranscriptOp::Absorb(expr) => expression_vars(expr).is_subset(&vars),
+                TranscriptOp::Squeeze { .. } => true,
+            })
+            .cloned()
+            .collect(),
+    };
+
+    let mut sampled = BTreeSet::new();
+    visit_sources(&mut sub, &mut |source| {
+        sampled.insert(*source);
+    });
+
+    let renumbered = sampled
+        .iter()
+        .enumerate()
+        .map(|(new, &old)| (old, new))
+        .collect::<BTreeMap<_, _>>();
+    visit_sources(&mut sub, &mut |source| *source = renumbered[source]);
+
+    sub.transcript.retain_mut(|op| match op {
+        TranscriptOp::Absorb(_) => true,
+        TranscriptOp::Squeeze { source } => match renumbered.get(source) {
+            Some(&new) => {
+                *source = new;
+                true
+            }
+            None => false,
+        },
+    });
+    sub.random_sources = sampled
+        .iter()
+        .filter_map(|&source| cir.random_sources.get(source).cloned())
+        .collect();
+
+    sub
+}
+
+/// Calls `f` with the source of every value in `cir` drawn from a random source, including the values of wires and
+/// absorbed expressions
+fn visit_sources(cir: &mut Cir, f: &mut impl FnMut(&mut usize)) {
+    let mut visit = |value: &mut Value| {
+        if let Value::Sampled { source, .. } | Value::OmittedSample { source } = value {
+            f(source);
+        }
+    };
+
+    let absorbed = cir.transcript.iter_mut().filter_map(|op| match op {
+        TranscriptOp::Absorb(expr) => Some(expr),
+        TranscriptOp::Squeeze { .. } => None,
+    });
+    for expr in absorbed {
+        expr.visit_values(&mut visit);
+        expr.visit_wires(&mut |wire| wire.value.iter_mut().for_each(&mut visit));
+        expr.visit_virtual_wires(&mut |wire| wire.value.iter_mut().for_each(&mut visit));
+    }
+
+    for stmt in &mut cir.stmts {
+        stmt.visit_values(&mut visit);
+        stmt.visit_wires(&mut |wire| wire.value.iter_mut().for_each(&mut visit));
+        stmt.visit_virtual_wires(&mut |wire| wire.value.iter_mut().for_each(&mut visit));
+    }
+
+    for value in cir
+        .public_wire_inputs
+        .iter_mut()
+        .filter_map(|wire| wire.value.as_mut())
+        .chain(
+            cir.public_virtual_wire_inputs
+                .iter_mut()
+                .filter_map(|wire| wire.value.as_mut()),
+        )
+    {
+        visit(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{BinOp, Expression, Ident, VirtualWire, Wire},
+        ir::CirBuilder,
+        test_util::{binop, test_code_ir},
+    };
+
+    use super::*;
+
+    fn example_cir() -> Cir {
+        CirBuilder::new()
+            // 0: unrelated to the output
+            .add_stmt(Stmt::Local(
+                "unrelated".into(),
+                binop(Wire::new_private(9, 0), BinOp::Add, Wire::new_private(9, 1)),
+            ))
+            // 1
+            .add_stmt(Stmt::Local(
+                "sum".into(),
+                binop(Wire::new_private(0, 0), BinOp::Add, Wire::new_private(0, 1)),
+            ))
+            // 2: constrains an input of `sum`
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 1),
+                BinOp::Equal,
+                Wire::new_private(1, 0),
+            )))
+            // 3
+            .add_stmt(Stmt::Local(
+                Ident::Wire(Wire::new_public(2, 0)),
+                binop(
+                    Expression::Ident("sum".into()),
+                    BinOp::Multiply,
+                    Wire::new_private(0, 0),
+                ),
+            ))
+            // 4: reads the output
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_public(2, 0),
+                BinOp::Equal,
+                VirtualWire::new_public(0),
+            )))
+            .build()
+    }
+
+    #[test]
+    fn test_backward_slice() {
+        let cir = example_cir();
+
+        assert_eq!(
+            backward_slice_indices(&cir, Wire::new_public(2, 0)),
+            [1, 2, 3, 4]
+        );
+        // `sum` is constrained by 4 through the local 3
+        assert_eq!(
+            backward_slice_indices(&cir, &Ident::from("sum")),
+            [1, 2, 3, 4]
+        );
+        assert_eq!(backward_slice_indices_from_stmt(&cir, 3), [1, 2, 3]);
+
+        test_code_ir(
+            "slice_backward",
+            &backward_slice(&cir, &Ident::from("sum")).to_code_ir(),
+        );
+    }
+
+    #[test]
+    fn test_backward_slice_through_locals() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                "x".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Add,
+                    Expression::Value(Value::U64(1)),
+                ),
+            ))
+            // Reads the wire but is never constrained
+            .add_stmt(Stmt::Local(
+                "y".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Multiply,
+                    Expression::Value(Value::U64(2)),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                Expression::Ident("x".into()),
+                BinOp::Equal,
+                Expression::Value(Value::U64(5)),
+            )))
+            .build();
+
+        assert_eq!(
+            backward_slice_indices(&cir, Wire::new_private(0, 0)),
+            [0, 2]
+        );
+    }
+
+    #[test]
+    fn test_forward_slice() {
+        let cir = example_cir();
+
+        assert_eq!(
+            forward_slice_indices(&cir, Wire::new_private(0, 1)),
+            [1, 2, 3, 4]
+        );
+        assert_eq!(forward_slice_indices(&cir, Wire::new_private(9, 0)), [0]);
+        assert_eq!(forward_slice_indices_from_stmt(&cir, 1), [1, 3, 4]);
+
+        test_code_ir(
+            "slice_forward",
+            &forward_slice(&cir, Wire::new_private(0, 1)).to_code_ir(),
+        );
+    }
+
+    #[test]
+    fn test_sub_cir_random_sources() {
+        let mut builder = CirBuilder::new();
+        builder.absorb(Wire::new_private(9, 0));
+        let alpha = builder.squeeze("alpha");
+        builder.absorb(Wire::new_private(0, 0));
+        let beta = builder.squeeze("beta");
+        let cir = builder
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(9, 0),
+                BinOp::Equal,
+                Expression::Value(Value::Sampled {
+                    source: alpha,
+                    value: 3,
+                }),
+            )))
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 0),
+                BinOp::Equal,
+                Expression::Value(Value::Sampled {
+                    source: beta,
+                    value: 5,
+                }),
+            )))
+            .build();
+
+        // Out of range indices are ignored
+        let sub = sub_cir(&cir, &[1, 5]);
+
+        assert_eq!(sub.random_sources, [cir.random_sources[beta].clone()]);
+        assert_eq!(
+            sub.transcript,
+            [
+                TranscriptOp::Absorb(Wire::new_private(0, 0).into()),
+                TranscriptOp::Squeeze { source: 0 }
+            ]
+        );
+        assert_eq!(
+            sub.stmts,
+            [Stmt::Verify(binop(
+                Wire::new_private(0, 0),
+                BinOp::Equal,
+                Expression::Value(Value::Sampled {
+                    source: 0,
+                    value: 5
+                }),
+            ))]
+        );
+    }
+}
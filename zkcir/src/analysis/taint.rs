@@ -0,0 +1,275 @@
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{expression_vars, public_values, Var};
+use crate::ast::{BinOp, Expression, Stmt, WireRef, Wiretype};
+use crate::ir::Cir;
+use crate::node::Node;
+
+/// One link in the chain explaining why a variable is determined by private data
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FlowStep {
+    pub var: Var,
+
+    /// Statement that determines `var`. `None` when `var` is a private wire itself.
+    pub stmt_index: Option<usize>,
+
+    /// Secret variables `var` is determined by through `stmt_index`
+    pub sources: Vec<Var>,
+}
+
+/// Public input whose value is determined solely by private data
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Leak {
+    pub public_input: WireRef,
+
+    /// Steps from the public input back to the private wires it is determined by
+    pub chain: Vec<FlowStep>,
+}
+
+impl Leak {
+    /// Human-readable explanation of the dependency chain
+    #[must_use]
+    pub fn explain(&self) -> String {
+        let mut lines = vec![format!(
+            "public input {} is determined solely by private data",
+            Var::Wire(self.public_input).to_code_ir()
+        )];
+
+        for step in &self.chain {
+            let sources = step
+                .sources
+                .iter()
+                .map(Var::to_code_ir)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            lines.push(match step.stmt_index {
+                Some(stmt_index) => format!(
+                    "  {} <- [{sources}] (stmt {stmt_index})",
+                    step.var.to_code_ir()
+                ),
+                None => format!("  {} is private", step.var.to_code_ir()),
+            });
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Finds public inputs whose value is determined solely by private witness data. Private wires are labeled secret,
+/// and the label propagates through `Stmt::Local` when every variable read is secret or constant, and through copy
+/// equalities like `verify!(a == b)` in both directions. Public inputs are `public_wire_inputs`,
+/// `public_virtual_wire_inputs` and any wire typed `Wiretype::Public`.
+#[must_use]
+pub fn find_leaks(cir: &Cir) -> Vec<Leak> {
+    let secrets = secret_vars(cir);
+
+    public_values(cir)
+        .into_iter()
+        .filter(|public_input| secrets.contains_key(&Var::Wire(*public_input)))
+        .map(|public_input| Leak {
+            public_input,
+            chain: chain(&secrets, &Var::Wire(public_input)),
+        })
+        .collect()
+}
+
+/// Maps every secret variable to how it became secret
+fn secret_vars(cir: &Cir) -> BTreeMap<Var, FlowStep> {
+    let mut wiretypes = BTreeMap::new();
+    for stmt in &cir.stmts {
+        stmt.clone().visit_wires(&mut |wire| {
+            wiretypes.insert(Var::Wire(wire.wire_ref()), wire.wiretype);
+        });
+        stmt.clone().visit_virtual_wires(&mut |virtual_wire| {
+            wiretypes.insert(Var::Wire(virtual_wire.wire_ref()), virtual_wire.wiretype);
+        });
+    }
+
+    let mut secrets = wiretypes
+        .iter()
+        .filter(|(_, &wiretype)| wiretype == Wiretype::Private)
+        .map(|(var, _)| {
+            (
+                var.clone(),
+                FlowStep {
+                    var: var.clone(),
+                    stmt_index: None,
+                    sources: Vec::new(),
+                },
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let is_constant = |var: &Var| wiretypes.get(var).copied() == Some(Wiretype::Constant);
+
+    loop {
+        let mut changed = false;
+
+        for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+            let determinations = match stmt {
+                Stmt::Local(ident, expr) => {
+                    let vars = expression_vars(expr);
+                    let sources = vars
+                        .iter()
+                        .filter(|var| secrets.contains_key(var))
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    if !sources.is_empty()
+                        && vars
+                            .iter()
+                            .all(|var| secrets.contains_key(var) || is_constant(var))
+                    {
+                        vec![(Var::from(ident), sources)]
+                    } else {
+                        Vec::new()
+                    }
+                }
+                Stmt::Verify(expr) => copy_equality(expr)
+                    .map(|(lhs, rhs)| {
+                        [(lhs.clone(), rhs.clone()), (rhs, lhs)]
+                            .into_iter()
+                            .filter(|(_, source)| secrets.contains_key(source))
+                            .map(|(var, source)| (var, vec![source]))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            };
+
+            for (var, sources) in determinations {
+                if !secrets.contains_key(&var) {
+                    secrets.insert(
+                        var.clone(),
+                        FlowStep {
+                            var,
+                            stmt_index: Some(stmt_index),
+                            sources,
+                        },
+                    );
+                    changed = true;
+                }
+            }
+        }
+
+        if !changed {
+            return secrets;
+        }
+    }
+}
+
+/// `verify!(a == b)` where both sides are plain identifiers
+fn copy_equality(expr: &Expression) -> Option<(Var, Var)> {
+    if let Expression::BinaryOperator {
+        lhs,
+        binop: BinOp::Equal,
+        rhs,
+    } = expr
+    {
+        if let (Expression::Ident(lhs), Expression::Ident(rhs)) = (&**lhs, &**rhs) {
+            return Some((lhs.into(), rhs.into()));
+        }
+    }
+
+    None
+}
+
+fn chain(secrets: &BTreeMap<Var, FlowStep>, var: &Var) -> Vec<FlowStep> {
+    let mut steps = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut worklist = vec![var.clone()];
+
+    while let Some(var) = worklist.pop() {
+        if !visited.insert(var.clone()) {
+            continue;
+        }
+
+        if let Some(step) = secrets.get(&var) {
+            worklist.extend(step.sources.iter().rev().cloned());
+            steps.push(step.clone());
+        }
+    }
+
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Ident, VirtualWire, Wire},
+        ir::CirBuilder,
+        test_util::binop,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_private_flows_to_public() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                "doubled".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Multiply,
+                    Wire::new_constant(0, 1),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                VirtualWire::new_public(0),
+                BinOp::Equal,
+                Expression::Ident("doubled".into()),
+            )))
+            .build();
+
+        let leaks = find_leaks(&cir);
+
+        assert_eq!(leaks.len(), 1);
+        assert_eq!(leaks[0].public_input, WireRef::VirtualWire { index: 0 });
+        assert_eq!(
+            leaks[0].chain,
+            [
+                FlowStep {
+                    var: Var::Wire(WireRef::VirtualWire { index: 0 }),
+                    stmt_index: Some(1),
+                    sources: vec![Var::Local("doubled".into())],
+                },
+                FlowStep {
+                    var: Var::Local("doubled".into()),
+                    stmt_index: Some(0),
+                    sources: vec![Var::Wire(WireRef::Wire { row: 0, column: 0 })],
+                },
+                FlowStep {
+                    var: Var::Wire(WireRef::Wire { row: 0, column: 0 }),
+                    stmt_index: None,
+                    sources: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mixed_public_private_is_not_leak() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                Ident::Wire(Wire::new_public(1, 0)),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Add,
+                    VirtualWire::new_public(0),
+                ),
+            ))
+            .build();
+
+        assert!(find_leaks(&cir).is_empty());
+    }
+}
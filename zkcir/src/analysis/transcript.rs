@@ -0,0 +1,138 @@
+//! What each Fiat-Shamir challenge is bound to, from the transcript recorded in `Cir::transcript`
+//!
+//! A challenge is only sound if everything the prover could otherwise choose after seeing it was absorbed first.
+//! This checks the public inputs and the wires tagged `commitment`, and only counts variables absorbed directly,
+//! so absorbing a hash of the public inputs computed in the circuit isn't recognized.
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{expression_vars, public_values, Var};
+use crate::ast::{Value, WireRef};
+use crate::ir::{Cir, TranscriptOp};
+use crate::node::Node;
+
+/// Tag marking a wire as a commitment that challenges must be bound to, as in `CirBuilder::tag_wire`
+pub const COMMITMENT_TAG: &str = "commitment";
+
+/// What was absorbed before a challenge was squeezed
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChallengeBinding {
+    /// Index into `Cir::random_sources` of the challenge
+    pub source: usize,
+
+    /// Variables absorbed before the squeeze
+    pub absorbed: BTreeSet<Var>,
+
+    /// Public inputs and commitments that weren't absorbed before the squeeze, in declaration order
+    pub unbound: Vec<WireRef>,
+
+    /// Index into `Cir::stmts` of the first statement reading the challenge, if any
+    pub first_use: Option<usize>,
+}
+
+/// Binding of every squeezed challenge, in transcript order
+#[must_use]
+pub fn challenge_bindings(cir: &Cir) -> Vec<ChallengeBinding> {
+    let mut required = public_values(cir);
+    for annotation in &cir.annotations {
+        if annotation.tags.iter().any(|tag| tag == COMMITMENT_TAG)
+            && !required.contains(&annotation.wire_ref)
+        {
+            required.push(annotation.wire_ref);
+        }
+    }
+
+    let mut absorbed = BTreeSet::new();
+    let mut bindings = Vec::new();
+
+    for op in &cir.transcript {
+        match op {
+            TranscriptOp::Absorb(expr) => absorbed.extend(expression_vars(expr)),
+            TranscriptOp::Squeeze { source } => bindings.push(ChallengeBinding {
+                source: *source,
+                absorbed: absorbed.clone(),
+                unbound: required
+                    .iter()
+                    .filter(|wire_ref| !absorbed.contains(&Var::Wire(**wire_ref)))
+                    .copied()
+                    .collect(),
+                first_use: first_use(cir, *source),
+            }),
+        }
+    }
+
+    bindings
+}
+
+/// Index of the first statement reading a value drawn from `source`, directly or as the value of a wire
+fn first_use(cir: &Cir, source: usize) -> Option<usize> {
+    let is_challenge = |value: Option<Value>| {
+        matches!(
+            value,
+            Some(Value::Sampled { source: s, .. } | Value::OmittedSample { source: s }) if s == source
+        )
+    };
+
+    cir.stmts.iter().position(|stmt| {
+        let mut stmt = stmt.clone();
+        let mut found = false;
+
+        stmt.visit_values(&mut |value| found |= is_challenge(Some(*value)));
+        stmt.visit_wires(&mut |wire| found |= is_challenge(wire.value));
+        stmt.visit_virtual_wires(&mut |virtual_wire| found |= is_challenge(virtual_wire.value));
+
+        found
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{BinOp, Expression, Stmt, Wire},
+        ir::CirBuilder,
+        test_util::binop,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_challenge_bindings() {
+        let mut builder = CirBuilder::new();
+        builder
+            .tag_wire(0, 1, COMMITMENT_TAG)
+            .absorb(Wire::new_public(0, 0));
+        let alpha = builder.squeeze("alpha");
+        builder.absorb(Wire::new_private(0, 1));
+        let beta = builder.squeeze("beta");
+
+        builder.add_stmt(Stmt::Verify(binop(
+            binop(
+                Wire::new_private(0, 1),
+                BinOp::Multiply,
+                Expression::Value(Value::Sampled {
+                    source: alpha,
+                    value: 3,
+                }),
+            ),
+            BinOp::Equal,
+            Wire::new_public(0, 0),
+        )));
+
+        let bindings = challenge_bindings(&builder.build());
+        assert_eq!(bindings.len(), 2);
+
+        assert_eq!(bindings[0].source, alpha);
+        assert_eq!(bindings[0].unbound, [WireRef::Wire { row: 0, column: 1 }]);
+        assert_eq!(bindings[0].first_use, Some(0));
+
+        assert_eq!(bindings[1].source, beta);
+        assert!(bindings[1].unbound.is_empty());
+        assert_eq!(bindings[1].first_use, None);
+    }
+}
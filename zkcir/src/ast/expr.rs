@@ -2,13 +2,16 @@ extern crate alloc;
 
 use alloc::{boxed::Box, format, string::ToString};
 use derive_more::Display;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::node::Node;
 
 use super::{Ident, Op};
 
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum Expression {
     BinaryOperator {
         lhs: Box<Expression>,
@@ -69,6 +72,7 @@ impl Node for Expression {
         }
     }
 
+    #[allow(clippy::replace_box)]
     fn visit_expressions_mut<F>(&mut self, f: &mut F)
     where
         F: FnMut(&mut Expression) -> Expression,
@@ -108,45 +112,98 @@ impl Node for Expression {
     }
 
     fn to_code_ir(&self) -> alloc::string::String {
+        self.to_labelled_code_ir(&|_| None)
+    }
+
+    fn to_labelled_code_ir(
+        &self,
+        label: &dyn Fn(&Ident) -> Option<alloc::string::String>,
+    ) -> alloc::string::String {
         match self {
             Expression::BinaryOperator { lhs, binop, rhs } => {
                 let lhs_str = if let Expression::BinaryOperator { .. } = **lhs {
-                    format!("({})", lhs.to_code_ir())
+                    format!("({})", lhs.to_labelled_code_ir(label))
                 } else {
-                    lhs.to_code_ir()
+                    lhs.to_labelled_code_ir(label)
                 };
 
                 let rhs_str = if let Expression::BinaryOperator { .. } = **rhs {
-                    format!("({})", rhs.to_code_ir())
+                    format!("({})", rhs.to_labelled_code_ir(label))
                 } else {
-                    rhs.to_code_ir()
+                    rhs.to_labelled_code_ir(label)
                 };
 
                 format!("{lhs_str} {binop} {rhs_str}")
             }
             Expression::Value(value) => value.to_code_ir(),
-            Expression::Ident(ident) => ident.to_code_ir(),
+            Expression::Ident(ident) => ident.to_labelled_code_ir(label),
             Expression::Unary { op, expr } => {
-                format!("{}{}", op.to_code_ir(), expr.to_code_ir())
+                if let Expression::BinaryOperator { .. } = **expr {
+                    format!("{}({})", op.to_code_ir(), expr.to_labelled_code_ir(label))
+                } else {
+                    format!("{}{}", op.to_code_ir(), expr.to_labelled_code_ir(label))
+                }
             }
         }
     }
 }
 
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Debug, Display)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum Value {
     #[display(fmt = "{_0}u64")]
     U64(u64),
 
+    /// Value the frontend recognized as random by comparing it with the random values it generated, which can false
+    /// positive. Prefer `Sampled`.
     #[display(fmt = "(random!() -> {_0}u64)")]
     RandomU64(u64),
 
     /// Enables generating deterministic IRs even when using random values. Useful for snapshot tests
     #[display(fmt = "random!()")]
     Random,
+
+    /// Value drawn from `Cir::random_sources[source]`
+    #[display(fmt = "(random!({source}) -> {value}u64)")]
+    Sampled { source: usize, value: u64 },
+
+    /// `Sampled` with the value omitted, like `Random` but keeping where the value came from
+    #[display(fmt = "random!({source})")]
+    OmittedSample { source: usize },
+}
+
+impl Value {
+    /// The concrete value, or `None` if it was omitted
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::U64(value) | Value::RandomU64(value) | Value::Sampled { value, .. } => {
+                Some(value)
+            }
+            Value::Random | Value::OmittedSample { .. } => None,
+        }
+    }
+
+    #[must_use]
+    pub fn is_random(&self) -> bool {
+        !matches!(self, Value::U64(_))
+    }
+
+    /// Same value with the concrete value omitted if it is random
+    #[must_use]
+    pub fn omit_random(self) -> Self {
+        match self {
+            Value::RandomU64(_) => Value::Random,
+            Value::Sampled { source, .. } => Value::OmittedSample { source },
+            value => value,
+        }
+    }
 }
 
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Debug, Display)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum BinOp {
     #[display(fmt = "+")]
     Add,
@@ -217,7 +274,9 @@ impl Node for Value {
 }
 
 /// `VirtualTarget` in plonky2
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub struct VirtualWire {
     pub index: usize,
     pub value: Option<Value>,
@@ -233,6 +292,11 @@ impl VirtualWire {
             wiretype: Wiretype::Public,
         }
     }
+
+    #[must_use]
+    pub fn wire_ref(&self) -> WireRef {
+        WireRef::VirtualWire { index: self.index }
+    }
 }
 
 impl Node for VirtualWire {
@@ -296,7 +360,9 @@ impl From<VirtualWire> for Expression {
     }
 }
 
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Debug, Display)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum Wiretype {
     /// * In halo2, instance columns
     /// * In plonky2, wires registered as public input
@@ -314,7 +380,9 @@ pub enum Wiretype {
 }
 
 /// `Target` in plonky2
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub struct Wire {
     pub row: usize,
     pub column: usize,
@@ -362,6 +430,14 @@ impl Wire {
             wiretype: Wiretype::Public,
         }
     }
+
+    #[must_use]
+    pub fn wire_ref(&self) -> WireRef {
+        WireRef::Wire {
+            row: self.row,
+            column: self.column,
+        }
+    }
 }
 
 impl Node for Wire {
@@ -432,6 +508,15 @@ impl From<Wire> for Expression {
     }
 }
 
+/// Identifies a `Wire` or `VirtualWire` by its position, regardless of its value or type
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum WireRef {
+    Wire { row: usize, column: usize },
+    VirtualWire { index: usize },
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::boxed::Box;
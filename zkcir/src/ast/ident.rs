@@ -1,6 +1,7 @@
 extern crate alloc;
 
 use alloc::string::{String, ToString};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::node::Node;
@@ -8,7 +9,9 @@ use crate::node::Node;
 use super::expr::{VirtualWire, Wire};
 
 /// Identifier. `x` in `let x = y;`
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum Ident {
     String(String),
 
@@ -65,6 +68,10 @@ impl Node for Ident {
             Ident::VirtualWire(virtual_wire) => virtual_wire.to_code_ir(),
         }
     }
+
+    fn to_labelled_code_ir(&self, label: &dyn Fn(&Ident) -> Option<String>) -> String {
+        label(self).unwrap_or_else(|| self.to_code_ir())
+    }
 }
 
 impl From<&str> for Ident {
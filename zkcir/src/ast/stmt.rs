@@ -1,6 +1,7 @@
 extern crate alloc;
 
 use alloc::{format, string::String};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::node::Node;
@@ -10,7 +11,9 @@ use super::{
     ident::Ident,
 };
 
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum Stmt {
     Verify(Expression),
 
@@ -95,10 +98,18 @@ impl Node for Stmt {
     }
 
     fn to_code_ir(&self) -> String {
+        self.to_labelled_code_ir(&|_| None)
+    }
+
+    fn to_labelled_code_ir(&self, label: &dyn Fn(&Ident) -> Option<String>) -> String {
         match self {
-            Stmt::Verify(stmt) => format!("verify!({});", stmt.to_code_ir()),
+            Stmt::Verify(stmt) => format!("verify!({});", stmt.to_labelled_code_ir(label)),
             Stmt::Local(ident, stmt) => {
-                format!("let {} = {};", ident.to_code_ir(), stmt.to_code_ir())
+                format!(
+                    "let {} = {};",
+                    ident.to_labelled_code_ir(label),
+                    stmt.to_labelled_code_ir(label)
+                )
             }
         }
     }
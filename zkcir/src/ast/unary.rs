@@ -1,13 +1,16 @@
 extern crate alloc;
 
 use alloc::string::{String, ToString};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::node::Node;
 
 use super::{Expression, Ident, Value, VirtualWire, Wire};
 
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum Op {
     Sub,
 }
@@ -0,0 +1,229 @@
+//! Canonical form of a `Cir`, so circuits that differ only cosmetically compare and hash equal
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "json")]
+use core::fmt::Write;
+#[cfg(feature = "json")]
+use sha2::{Digest, Sha256};
+
+use crate::ast::{BinOp, Expression, Ident, Stmt, Value, VirtualWire, Wire};
+use crate::ir::{Cir, PublicValue, RandomSource, TranscriptOp};
+use crate::node::Node;
+
+/// Hashed before the canonical json so the digest changes if the canonical form ever does
+#[cfg(feature = "json")]
+const FINGERPRINT_DOMAIN: &str = "zkcir-fingerprint-v1\n";
+
+/// Copy of `cir` without cosmetic differences:
+///
+/// - Locals are renamed `local_0`, `local_1`, ... in order of first appearance
+/// - Random values are omitted, see `Value::omit_random`
+/// - Random sources are renamed `random_0`, `random_1`, ... keeping their kind
+/// - Public values of the signature are renamed `public_0`, `public_1`, ... keeping their wire and direction
+/// - Operands of `+`, `*` and `==` are ordered by their code IR
+/// - Annotations and gadgets, which only describe the circuit, are dropped
+///
+/// Statement order and public input order are kept since they change what the circuit means.
+#[must_use]
+pub fn canonicalize(cir: &Cir) -> Cir {
+    let mut locals = BTreeMap::new();
+
+    let stmts = cir
+        .stmts
+        .iter()
+        .map(|stmt| match stmt {
+            Stmt::Local(ident, expr) => {
+                // The value is computed before it is assigned
+                let expr = expression(expr, &mut locals);
+                Stmt::Local(canonical_ident(ident, &mut locals), expr)
+            }
+            Stmt::Verify(expr) => Stmt::Verify(expression(expr, &mut locals)),
+        })
+        .collect();
+
+    let transcript = cir
+        .transcript
+        .iter()
+        .map(|op| match op {
+            TranscriptOp::Absorb(expr) => TranscriptOp::Absorb(expression(expr, &mut locals)),
+            TranscriptOp::Squeeze { source } => TranscriptOp::Squeeze { source: *source },
+        })
+        .collect();
+
+    Cir {
+        config: cir.config,
+        stmts,
+        public_wire_inputs: cir.public_wire_inputs.iter().map(wire).collect(),
+        public_virtual_wire_inputs: cir
+            .public_virtual_wire_inputs
+            .iter()
+            .map(virtual_wire)
+            .collect(),
+        signature: cir
+            .signature
+            .iter()
+            .enumerate()
+            .map(|(position, public_value)| PublicValue {
+                name: format!("public_{position}"),
+                ..public_value.clone()
+            })
+            .collect(),
+        annotations: Vec::new(),
+        gadgets: Vec::new(),
+        random_sources: cir
+            .random_sources
+            .iter()
+            .enumerate()
+            .map(|(source, random_source)| RandomSource {
+                name: format!("random_{source}"),
+                kind: random_source.kind,
+            })
+            .collect(),
+        transcript,
+    }
+}
+
+/// Hex SHA-256 digest of the canonical form of `cir`. Circuits with the same fingerprint are identical up to the
+/// differences `canonicalize` removes.
+///
+/// # Errors
+///
+/// Errors from `serde_json::to_string`
+#[cfg(feature = "json")]
+pub fn fingerprint(cir: &Cir) -> Result<String, &'static str> {
+    let json =
+        serde_json::to_string(&canonicalize(cir)).map_err(|_| "Failed serializing to json")?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(FINGERPRINT_DOMAIN);
+    hasher.update(json);
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        }))
+}
+
+fn expression(expr: &Expression, locals: &mut BTreeMap<String, String>) -> Expression {
+    match expr {
+        Expression::BinaryOperator { lhs, binop, rhs } => {
+            let mut lhs = expression(lhs, locals);
+            let mut rhs = expression(rhs, locals);
+
+            if matches!(binop, BinOp::Add | BinOp::Multiply | BinOp::Equal)
+                && lhs.to_code_ir() > rhs.to_code_ir()
+            {
+                core::mem::swap(&mut lhs, &mut rhs);
+            }
+
+            Expression::BinaryOperator {
+                lhs: Box::new(lhs),
+                binop: *binop,
+                rhs: Box::new(rhs),
+            }
+        }
+        Expression::Ident(ident) => Expression::Ident(canonical_ident(ident, locals)),
+        Expression::Value(value) => Expression::Value(value.omit_random()),
+        Expression::Unary { op, expr } => Expression::Unary {
+            op: op.clone(),
+            expr: Box::new(expression(expr, locals)),
+        },
+    }
+}
+
+fn canonical_ident(ident: &Ident, locals: &mut BTreeMap<String, String>) -> Ident {
+    match ident {
+        Ident::String(name) => {
+            let next = format!("local_{}", locals.len());
+            Ident::String(locals.entry(name.clone()).or_insert(next).clone())
+        }
+        Ident::Wire(w) => Ident::Wire(wire(w)),
+        Ident::VirtualWire(v) => Ident::VirtualWire(virtual_wire(v)),
+    }
+}
+
+fn wire(wire: &Wire) -> Wire {
+    Wire {
+        value: wire.value.map(Value::omit_random),
+        ..*wire
+    }
+}
+
+fn virtual_wire(virtual_wire: &VirtualWire) -> VirtualWire {
+    VirtualWire {
+        value: virtual_wire.value.map(Value::omit_random),
+        ..*virtual_wire
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use crate::{ast::Wiretype, ir::CirBuilder, test_util::binop};
+
+    use super::*;
+
+    fn circuit(local: &str, random: u64, swap: bool) -> Cir {
+        let (lhs, rhs): (Expression, Expression) = (
+            Wire::new_private(0, 0).into(),
+            Expression::Ident(local.into()),
+        );
+        let (lhs, rhs) = if swap { (rhs, lhs) } else { (lhs, rhs) };
+
+        CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                local.into(),
+                binop(
+                    Wire {
+                        row: 0,
+                        column: 1,
+                        value: Some(Value::RandomU64(random)),
+                        wiretype: Wiretype::Constant,
+                    },
+                    BinOp::Multiply,
+                    VirtualWire::new_public(0),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(lhs, BinOp::Add, rhs)))
+            .name_wire(0, 0, local)
+            .build()
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let original = circuit("challenge", 7, false);
+        let renamed = circuit("alpha", 9, true);
+
+        assert_eq!(canonicalize(&original), canonicalize(&renamed));
+        assert_eq!(
+            canonicalize(&original).to_code_ir(),
+            "let local_0 = virtual_wire::public(index: 0) * wire::const(row: 0, column: 1, value: random!());\n\nverify!(local_0 + wire::private(row: 0, column: 0));"
+        );
+
+        assert_eq!(
+            fingerprint(&original).unwrap(),
+            fingerprint(&renamed).unwrap()
+        );
+
+        // Pinned so the digest stays stable across releases
+        assert_eq!(
+            fingerprint(&original).unwrap(),
+            "e2607974c65dfd69f70b5b3ad0e6156dfd623a09853722cf5d250ea2753e4eaf"
+        );
+
+        let mut different = original.clone();
+        different.stmts.pop();
+        assert_ne!(
+            fingerprint(&original).unwrap(),
+            fingerprint(&different).unwrap()
+        );
+    }
+}
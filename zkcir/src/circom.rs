@@ -0,0 +1,420 @@
+//! Importer for circuits compiled by Circom, from the iden3 `.r1cs` format and the `.sym` file naming its signals
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_more::Display;
+
+use crate::analysis::eval::GOLDILOCKS_ORDER;
+use crate::ast::{BinOp, Expression, Ident, Op, Stmt, Value, VirtualWire, WireRef, Wiretype};
+use crate::ir::{Cir, CirBuilder, Field};
+
+#[derive(PartialEq, Eq, Clone, Debug, Display)]
+pub enum CircomError {
+    #[display(fmt = "malformed r1cs file: {_0}")]
+    MalformedR1cs(&'static str),
+
+    #[display(fmt = "malformed sym file at line {line}")]
+    MalformedSym { line: usize },
+
+    /// The IR only holds `u64` values, so coefficients must be small or small negatives
+    #[display(
+        fmt = "constraint {constraint_index} has a coefficient that doesn't fit in a u64, even negated"
+    )]
+    UnsupportedCoefficient { constraint_index: usize },
+
+    /// Primes that fit in a `u64` other than Goldilocks can't be represented by `Field`
+    #[display(
+        fmt = "unsupported prime, only Goldilocks and primes wider than 64 bits are supported"
+    )]
+    UnsupportedPrime,
+}
+
+/// Signal of a Circom circuit. Index 0 is always the constant `1`.
+type Term = (usize, Coefficient);
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Coefficient {
+    Positive(u64),
+
+    /// `prime - n`
+    Negative(u64),
+}
+
+struct Header {
+    field_size: usize,
+    prime: Vec<u8>,
+    num_wires: usize,
+    num_public_outputs: usize,
+    num_public_inputs: usize,
+}
+
+/// Imports a circuit compiled by Circom. Every signal becomes a virtual wire indexed by its Circom wire number, typed
+/// public for outputs and public inputs, and every constraint `A * B = C` becomes `verify!((A) * (B) == C)`. Names from
+/// `sym`, the contents of the `.sym` file, are attached to the wires, outputs are tagged `output`, and the signature
+/// lists the outputs then the public inputs.
+///
+/// # Errors
+///
+/// Errors if either file is malformed, the prime is unsupported, or a coefficient is too large for the IR's `u64`
+/// values
+pub fn import(r1cs: &[u8], sym: Option<&str>) -> Result<Cir, CircomError> {
+    let mut reader = Reader::new(r1cs);
+
+    if reader.take(4)? != b"r1cs" {
+        return Err(CircomError::MalformedR1cs("missing magic number"));
+    }
+    if reader.u32()? != 1 {
+        return Err(CircomError::MalformedR1cs("unsupported version"));
+    }
+
+    let mut sections = BTreeMap::new();
+    for _ in 0..reader.u32()? {
+        let section_type = reader.u32()?;
+        let size = usize::try_from(reader.u64()?)
+            .map_err(|_| CircomError::MalformedR1cs("section too large"))?;
+        sections.insert(section_type, reader.take(size)?);
+    }
+
+    let header = parse_header(
+        sections
+            .get(&1)
+            .ok_or(CircomError::MalformedR1cs("missing header section"))?,
+    )?;
+    let constraints = sections
+        .get(&2)
+        .ok_or(CircomError::MalformedR1cs("missing constraints section"))?;
+
+    let mut builder = CirBuilder::new();
+    builder.num_wires(header.num_wires as u64);
+    builder.field(match small(&header.prime) {
+        Some(GOLDILOCKS_ORDER) => Field::Goldilocks,
+        Some(_) => return Err(CircomError::UnsupportedPrime),
+        None => Field::Other,
+    });
+
+    let num_public = header.num_public_outputs + header.num_public_inputs;
+    builder.public_virtual_wire_inputs = (1..=num_public).map(VirtualWire::new_public).collect();
+
+    let mut reader = Reader::new(constraints);
+    let mut constraint_index = 0;
+    while !reader.is_empty() {
+        let mut lcs = Vec::new();
+        for _ in 0..3 {
+            lcs.push(read_lc(&mut reader, &header, constraint_index)?);
+        }
+
+        let (c, b, a) = (lcs.pop(), lcs.pop(), lcs.pop());
+        let (a, b, c) = (
+            a.unwrap_or_default(),
+            b.unwrap_or_default(),
+            c.unwrap_or_default(),
+        );
+        builder.add_stmt(Stmt::Verify(constraint(&a, &b, &c, num_public)));
+        constraint_index += 1;
+    }
+
+    let names = match sym {
+        Some(sym) => parse_sym(sym)?,
+        None => BTreeMap::new(),
+    };
+    for (wire, name) in &names {
+        builder.name_virtual_wire(*wire, name);
+    }
+
+    for wire in 1..=header.num_public_outputs {
+        builder.tag_virtual_wire(wire, "output");
+    }
+
+    // Circom lays out public outputs before public inputs
+    for wire in 1..=num_public {
+        let wire_ref = WireRef::VirtualWire { index: wire };
+        if wire <= header.num_public_outputs {
+            let name = names
+                .get(&wire)
+                .cloned()
+                .unwrap_or_else(|| format!("output_{wire}"));
+            builder.public_output(wire_ref, &name);
+        } else {
+            let name = names
+                .get(&wire)
+                .cloned()
+                .unwrap_or_else(|| format!("input_{wire}"));
+            builder.public_input(wire_ref, &name);
+        }
+    }
+
+    Ok(builder.build())
+}
+
+fn parse_header(bytes: &[u8]) -> Result<Header, CircomError> {
+    let mut reader = Reader::new(bytes);
+
+    let field_size = reader.u32()? as usize;
+    let prime = reader.take(field_size)?.to_vec();
+    let num_wires = reader.u32()? as usize;
+    let num_public_outputs = reader.u32()? as usize;
+    let num_public_inputs = reader.u32()? as usize;
+
+    Ok(Header {
+        field_size,
+        prime,
+        num_wires,
+        num_public_outputs,
+        num_public_inputs,
+    })
+}
+
+fn read_lc(
+    reader: &mut Reader,
+    header: &Header,
+    constraint_index: usize,
+) -> Result<Vec<Term>, CircomError> {
+    let mut terms = Vec::new();
+
+    for _ in 0..reader.u32()? {
+        let wire = reader.u32()? as usize;
+        let coefficient = coefficient(reader.take(header.field_size)?, &header.prime)
+            .ok_or(CircomError::UnsupportedCoefficient { constraint_index })?;
+        terms.push((wire, coefficient));
+    }
+
+    Ok(terms)
+}
+
+/// Reads a little-endian field element as a small positive or negative number, whichever is smaller
+fn coefficient(bytes: &[u8], prime: &[u8]) -> Option<Coefficient> {
+    // prime - bytes, with borrow
+    let mut difference = Vec::with_capacity(bytes.len());
+    let mut borrow = 0;
+    for (&p, &b) in prime.iter().zip(bytes) {
+        let (value, underflow) = p.overflowing_sub(b);
+        let (value, underflow_borrow) = value.overflowing_sub(borrow);
+        difference.push(value);
+        borrow = u8::from(underflow || underflow_borrow);
+    }
+
+    match (small(bytes), small(&difference)) {
+        (Some(positive), Some(negative)) if negative < positive => {
+            Some(Coefficient::Negative(negative))
+        }
+        (Some(positive), _) => Some(Coefficient::Positive(positive)),
+        (None, negative) => negative.map(Coefficient::Negative),
+    }
+}
+
+/// Little-endian bytes as a `u64` if every byte past the eighth is zero
+fn small(bytes: &[u8]) -> Option<u64> {
+    if bytes.iter().skip(8).any(|&byte| byte != 0) {
+        return None;
+    }
+
+    let mut le = [0u8; 8];
+    for (i, &byte) in bytes.iter().take(8).enumerate() {
+        le[i] = byte;
+    }
+
+    Some(u64::from_le_bytes(le))
+}
+
+/// Lines of `labelId,varIdx,componentIdx,name`. Signals optimized away have `varIdx` -1. When several labels share a
+/// wire the first name is kept.
+fn parse_sym(sym: &str) -> Result<BTreeMap<usize, String>, CircomError> {
+    let mut names = BTreeMap::new();
+
+    for (line_index, line) in sym.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields = line.splitn(4, ',').collect::<Vec<_>>();
+        let [_, wire, _, name] = fields[..] else {
+            return Err(CircomError::MalformedSym {
+                line: line_index + 1,
+            });
+        };
+
+        let wire = wire
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| CircomError::MalformedSym {
+                line: line_index + 1,
+            })?;
+
+        if let Ok(wire) = usize::try_from(wire) {
+            names.entry(wire).or_insert_with(|| name.trim().to_string());
+        }
+    }
+
+    Ok(names)
+}
+
+/// `verify!((A) * (B) == C)`, simplified when `A` or `B` is empty or the constant `1`
+fn constraint(a: &[Term], b: &[Term], c: &[Term], num_public: usize) -> Expression {
+    let product = match (a, b) {
+        ([], _) | (_, []) => None,
+        ([(0, Coefficient::Positive(1))], other) | (other, [(0, Coefficient::Positive(1))]) => {
+            Some(lc(other, num_public))
+        }
+        (a, b) => Some(binop(lc(a, num_public), BinOp::Multiply, lc(b, num_public))),
+    };
+
+    match product {
+        Some(product) => binop(product, BinOp::Equal, lc(c, num_public)),
+        None => binop(
+            lc(c, num_public),
+            BinOp::Equal,
+            Expression::Value(Value::U64(0)),
+        ),
+    }
+}
+
+/// Sum of terms like `a + 2 * b - c`
+fn lc(terms: &[Term], num_public: usize) -> Expression {
+    let mut terms = terms.iter();
+
+    let Some(&(wire, coefficient)) = terms.next() else {
+        return Expression::Value(Value::U64(0));
+    };
+
+    let first = match coefficient {
+        Coefficient::Positive(n) => term(wire, n, num_public),
+        Coefficient::Negative(n) => Expression::Unary {
+            op: Op::Sub,
+            expr: Box::new(term(wire, n, num_public)),
+        },
+    };
+
+    terms.fold(first, |sum, &(wire, coefficient)| match coefficient {
+        Coefficient::Positive(n) => binop(sum, BinOp::Add, term(wire, n, num_public)),
+        Coefficient::Negative(n) => binop(sum, BinOp::Subtract, term(wire, n, num_public)),
+    })
+}
+
+fn term(wire: usize, coefficient: u64, num_public: usize) -> Expression {
+    if wire == 0 {
+        return Expression::Value(Value::U64(coefficient));
+    }
+
+    let signal = Expression::Ident(Ident::VirtualWire(VirtualWire {
+        index: wire,
+        value: None,
+        wiretype: if wire <= num_public {
+            Wiretype::Public
+        } else {
+            Wiretype::Private
+        },
+    }));
+
+    if coefficient == 1 {
+        signal
+    } else {
+        binop(
+            Expression::Value(Value::U64(coefficient)),
+            BinOp::Multiply,
+            signal,
+        )
+    }
+}
+
+fn binop(lhs: Expression, binop: BinOp, rhs: Expression) -> Expression {
+    Expression::BinaryOperator {
+        lhs: Box::new(lhs),
+        binop,
+        rhs: Box::new(rhs),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CircomError> {
+        if self.bytes.len() < n {
+            return Err(CircomError::MalformedR1cs("unexpected end of file"));
+        }
+
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, CircomError> {
+        let mut le = [0u8; 4];
+        le.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(le))
+    }
+
+    fn u64(&mut self) -> Result<u64, CircomError> {
+        let mut le = [0u8; 8];
+        le.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(le))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Wire, ir::Direction, r1cs::R1cs, test_util::test_code_ir};
+
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        // Round trips through the r1cs exporter, which writes the same format with an 8 byte field
+        let original = CirBuilder::new()
+            .add_stmt(Stmt::Verify(binop(
+                binop(
+                    Expression::Ident(Ident::Wire(Wire::new_private(0, 0))),
+                    BinOp::Multiply,
+                    Expression::Ident(Ident::Wire(Wire::new_private(0, 1))),
+                ),
+                BinOp::Equal,
+                binop(
+                    Expression::Ident(Ident::VirtualWire(VirtualWire::new_public(0))),
+                    BinOp::Subtract,
+                    Expression::Value(Value::U64(3)),
+                ),
+            )))
+            .public_output(WireRef::VirtualWire { index: 0 }, "out")
+            .build();
+
+        let bytes = R1cs::new(&original).unwrap().to_iden3_bytes().unwrap();
+        let sym =
+            "1,1,0,main.out\n2,2,0,main.a\n3,3,0,main.b\n4,3,0,main.b_alias\n5,-1,0,main.removed\n";
+
+        let imported = import(&bytes, Some(sym)).unwrap();
+
+        assert_eq!(
+            imported.public_virtual_wire_inputs,
+            [VirtualWire::new_public(1)]
+        );
+        assert_eq!(imported.signature[0].direction, Direction::Output);
+        assert_eq!(imported.config.field(), Field::Goldilocks);
+        test_code_ir("circom_import", &imported.to_code_ir());
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(
+            import(b"r1cx", None),
+            Err(CircomError::MalformedR1cs("missing magic number"))
+        );
+        assert_eq!(
+            parse_sym("1,1,0,main.out\nnot a sym line"),
+            Err(CircomError::MalformedSym { line: 2 })
+        );
+    }
+}
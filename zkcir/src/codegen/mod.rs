@@ -0,0 +1,135 @@
+//! Backends that turn a `Cir` into source code for other tools
+
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use derive_more::Display;
+
+use crate::analysis::eval::check_field;
+use crate::analysis::{public_values, stmt_def, stmt_uses, Var};
+use crate::ast::WireRef;
+use crate::ir::{Cir, Field};
+
+pub mod plonky2;
+pub mod witness;
+
+#[derive(PartialEq, Eq, Clone, Debug, Display)]
+pub enum CodegenError {
+    #[display(fmt = "stmt {stmt_index} uses {operation}, which the backend can't express")]
+    Unsupported {
+        stmt_index: usize,
+        operation: String,
+    },
+
+    /// Both backends do arithmetic over Goldilocks, so generating code for another field would change the circuit
+    #[display(fmt = "circuit is over {_0}, but code generation only supports Goldilocks")]
+    UnsupportedField(Field),
+}
+
+/// Errors unless `cir` is over Goldilocks
+pub(crate) fn check_goldilocks(cir: &Cir) -> Result<(), CodegenError> {
+    check_field(cir).map_err(|_| CodegenError::UnsupportedField(cir.config.field()))
+}
+
+/// Rust identifier of every variable of a circuit, like `wire_1_2`, `virtual_wire_3` or `local_x`. Characters that
+/// can't appear in an identifier, like the `.` in Circom names, become `_`, and locals that end up with the same
+/// identifier, like `a.b` and `a_b`, get a suffix like `local_a_b_2`.
+pub(crate) struct RustIdents(BTreeMap<Var, String>);
+
+impl RustIdents {
+    pub(crate) fn new(cir: &Cir) -> Self {
+        let mut vars = public_values(cir)
+            .into_iter()
+            .map(Var::Wire)
+            .collect::<BTreeSet<_>>();
+        for stmt in &cir.stmts {
+            vars.extend(stmt_def(stmt));
+            vars.extend(stmt_uses(stmt));
+        }
+
+        let bases = vars
+            .into_iter()
+            .map(|var| {
+                let base = base_ident(&var);
+                (var, base)
+            })
+            .collect::<Vec<_>>();
+        let all_bases = bases
+            .iter()
+            .map(|(_, base)| base.clone())
+            .collect::<BTreeSet<_>>();
+
+        // Suffixed identifiers skip every unsuffixed one, so a later variable never loses its own
+        let mut taken = BTreeSet::new();
+        let mut idents = BTreeMap::new();
+        for (var, base) in bases {
+            let mut ident = base.clone();
+            let mut suffix = 2;
+            while taken.contains(&ident) || (ident != base && all_bases.contains(&ident)) {
+                ident = format!("{base}_{suffix}");
+                suffix += 1;
+            }
+            taken.insert(ident.clone());
+            idents.insert(var, ident);
+        }
+
+        RustIdents(idents)
+    }
+
+    pub(crate) fn get(&self, var: &Var) -> String {
+        self.0.get(var).cloned().unwrap_or_else(|| base_ident(var))
+    }
+}
+
+fn base_ident(var: &Var) -> String {
+    match var {
+        Var::Wire(WireRef::Wire { row, column }) => format!("wire_{row}_{column}"),
+        Var::Wire(WireRef::VirtualWire { index }) => format!("virtual_wire_{index}"),
+        Var::Local(name) => format!(
+            "local_{}",
+            name.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{
+        ast::{BinOp, Expression, Stmt, Wire},
+        ir::CirBuilder,
+        test_util::binop,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_colliding_idents() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local("a.b".into(), Wire::new_private(0, 0).into()))
+            .add_stmt(Stmt::Local("a_b".into(), Wire::new_private(0, 0).into()))
+            .add_stmt(Stmt::Local("a_b_2".into(), Wire::new_private(0, 0).into()))
+            .add_stmt(Stmt::Verify(binop(
+                Expression::Ident("a.b".into()),
+                BinOp::Equal,
+                Expression::Ident("a_b".into()),
+            )))
+            .build();
+
+        let idents = RustIdents::new(&cir);
+
+        assert_eq!(idents.get(&Var::Local("a.b".to_string())), "local_a_b");
+        assert_eq!(idents.get(&Var::Local("a_b".to_string())), "local_a_b_3");
+        assert_eq!(idents.get(&Var::Local("a_b_2".to_string())), "local_a_b_2");
+        assert_eq!(
+            idents.get(&Var::Wire(WireRef::Wire { row: 0, column: 0 })),
+            "wire_0_0"
+        );
+    }
+}
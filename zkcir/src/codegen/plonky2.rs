@@ -0,0 +1,400 @@
+//! Generates Rust source building a `Cir` with plonky2's `CircuitBuilder`, so a circuit edited as `.cir` can be
+//! proven again and checked against the original
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use super::{check_goldilocks, CodegenError, RustIdents};
+use crate::analysis::eval::{constant_value, free_vars, solvable_equality};
+use crate::analysis::{public_values, Var};
+use crate::ast::{BinOp, Expression, Op, Stmt, Value};
+use crate::ir::Cir;
+use crate::node::Node;
+
+const IMPORTS: &str = "use plonky2::field::extension::Extendable;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::Target;
+use plonky2::plonk::circuit_builder::CircuitBuilder;";
+
+/// Generates a module with `build_circuit`, which adds `cir` to a `CircuitBuilder` and returns the `Inputs` targets to
+/// assign in a `PartialWitness` before proving.
+///
+/// Every variable `cir` reads before computing it becomes an input target, see `eval::free_vars`. Locals become
+/// arithmetic on targets. A verify that solves a variable, like `verify!(out == e)`, binds it instead of adding a
+/// constraint, the way `evaluate` runs it. Other equalities use `connect`, or `assert_zero` against a constant zero,
+/// and any other verify constrains its expression to be nonzero by inverting it. Public inputs are registered in
+/// declaration order once computed.
+///
+/// # Errors
+///
+/// Errors if the circuit isn't over Goldilocks, and on comparisons, non-constant exponents and omitted random values,
+/// which have no single `CircuitBuilder` method
+pub fn generate(cir: &Cir) -> Result<String, CodegenError> {
+    check_goldilocks(cir)?;
+
+    let inputs = free_vars(cir);
+
+    let mut generator = Generator {
+        idents: RustIdents::new(cir),
+        known: inputs.clone(),
+        body: Vec::new(),
+        temporaries: 0,
+        stmt_index: 0,
+    };
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        generator.stmt_index = stmt_index;
+        generator.body.push(String::new());
+        generator.body.push(format!("// {}", stmt.to_code_ir()));
+        generator.stmt(stmt)?;
+    }
+
+    // Public inputs the circuit never reads or computes still need a target to register
+    let mut inputs = inputs.into_iter().collect::<Vec<_>>();
+    let public = public_values(cir);
+    for wire_ref in &public {
+        let var = Var::Wire(*wire_ref);
+        if !generator.known.contains(&var) {
+            inputs.push(var);
+        }
+    }
+
+    let mut source = format!("// Generated by zkcir\n\n{IMPORTS}\n\n");
+
+    source.push_str(
+        "/// Targets to assign in a `PartialWitness` before proving\npub struct Inputs {\n",
+    );
+    for var in &inputs {
+        let _ = writeln!(
+            source,
+            "    /// {}\n    pub {}: Target,",
+            cir.var_name(var),
+            generator.idents.get(var)
+        );
+    }
+    source.push_str("}\n\n");
+
+    source.push_str(
+        "pub fn build_circuit<F: RichField + Extendable<D>, const D: usize>(\n    builder: &mut CircuitBuilder<F, D>,\n) -> Inputs {\n",
+    );
+
+    for var in &inputs {
+        let _ = writeln!(
+            source,
+            "    let {} = builder.add_virtual_target();",
+            generator.idents.get(var)
+        );
+    }
+
+    for line in &generator.body {
+        if line.is_empty() {
+            source.push('\n');
+        } else {
+            let _ = writeln!(source, "    {line}");
+        }
+    }
+
+    if !public.is_empty() {
+        source.push('\n');
+    }
+    for wire_ref in &public {
+        let _ = writeln!(
+            source,
+            "    builder.register_public_input({});",
+            generator.idents.get(&Var::Wire(*wire_ref))
+        );
+    }
+
+    let fields = inputs
+        .iter()
+        .map(|var| generator.idents.get(var))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(source, "\n    Inputs {{ {fields} }}\n}}");
+
+    Ok(source)
+}
+
+struct Generator {
+    idents: RustIdents,
+    /// Variables that have a target bound to their Rust identifier
+    known: BTreeSet<Var>,
+    body: Vec<String>,
+    temporaries: usize,
+    stmt_index: usize,
+}
+
+impl Generator {
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), CodegenError> {
+        match stmt {
+            Stmt::Local(ident, expr) => {
+                let var = Var::from(ident);
+                let target = self.target(expr)?;
+
+                // Wires are assigned once in a real circuit, so assigning a wire that already has a target constrains
+                // the two to be equal. Locals named by strings are rebound.
+                if matches!(var, Var::Wire(_)) && self.known.contains(&var) {
+                    self.push(&format!(
+                        "builder.connect({}, {target});",
+                        self.idents.get(&var)
+                    ));
+                } else {
+                    self.push(&format!("let {} = {target};", self.idents.get(&var)));
+                    self.known.insert(var);
+                }
+            }
+            Stmt::Verify(expr) => {
+                if let Some((var, expr)) = solvable_equality(expr, &|var| self.known.contains(var))
+                {
+                    let target = self.target(expr)?;
+                    self.push(&format!("let {} = {target};", self.idents.get(&var)));
+                    self.known.insert(var);
+                } else if let Expression::BinaryOperator {
+                    lhs,
+                    binop: BinOp::Equal,
+                    rhs,
+                } = expr
+                {
+                    match (is_zero(lhs), is_zero(rhs)) {
+                        (false, true) => {
+                            let lhs = self.target(lhs)?;
+                            self.push(&format!("builder.assert_zero({lhs});"));
+                        }
+                        (true, false) => {
+                            let rhs = self.target(rhs)?;
+                            self.push(&format!("builder.assert_zero({rhs});"));
+                        }
+                        _ => {
+                            let (lhs, rhs) = (self.target(lhs)?, self.target(rhs)?);
+                            self.push(&format!("builder.connect({lhs}, {rhs});"));
+                        }
+                    }
+                } else {
+                    let target = self.target(expr)?;
+                    self.push(&format!(
+                        "// Only nonzero values have an inverse\nbuilder.inverse({target});"
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Identifier of a target holding `expr`, pushing whatever statements compute it
+    fn target(&mut self, expr: &Expression) -> Result<String, CodegenError> {
+        match expr {
+            Expression::Value(value) => {
+                let value = self.value(*value)?;
+                Ok(self.temporary(&format!(
+                    "builder.constant(F::from_noncanonical_u64({value}))"
+                )))
+            }
+            Expression::Ident(ident) => match constant_value(ident) {
+                Some(value) => self.target(&Expression::Value(value)),
+                None => Ok(self.idents.get(&Var::from(ident))),
+            },
+            Expression::Unary { op: Op::Sub, expr } => {
+                let target = self.target(expr)?;
+                Ok(self.temporary(&format!("builder.neg({target})")))
+            }
+            Expression::BinaryOperator {
+                lhs,
+                binop: BinOp::Exponent,
+                rhs,
+            } => {
+                let exponent = match &**rhs {
+                    Expression::Value(value) => self.value(*value)?,
+                    Expression::Ident(ident) => match constant_value(ident) {
+                        Some(value) => self.value(value)?,
+                        None => return Err(self.unsupported("a non-constant exponent")),
+                    },
+                    _ => return Err(self.unsupported("a non-constant exponent")),
+                };
+
+                let base = self.target(lhs)?;
+                Ok(self.temporary(&format!("builder.exp_u64({base}, {exponent})")))
+            }
+            Expression::BinaryOperator { lhs, binop, rhs } => {
+                let method = match binop {
+                    BinOp::Add => "add",
+                    BinOp::Subtract => "sub",
+                    BinOp::Multiply => "mul",
+                    BinOp::Divide => "div",
+                    BinOp::Equal => "is_equal",
+                    BinOp::Exponent => unreachable!("exponents are matched above"),
+                    BinOp::LessThan
+                    | BinOp::LessThanEqual
+                    | BinOp::GreaterThan
+                    | BinOp::GreaterThanEqual => {
+                        return Err(self.unsupported(&format!("the comparison `{binop}`")))
+                    }
+                };
+
+                let (lhs, rhs) = (self.target(lhs)?, self.target(rhs)?);
+
+                // `is_equal` returns a `BoolTarget`
+                Ok(if *binop == BinOp::Equal {
+                    self.temporary(&format!("builder.is_equal({lhs}, {rhs}).target"))
+                } else {
+                    self.temporary(&format!("builder.{method}({lhs}, {rhs})"))
+                })
+            }
+        }
+    }
+
+    fn value(&self, value: Value) -> Result<u64, CodegenError> {
+        match value {
+            Value::U64(value) | Value::RandomU64(value) | Value::Sampled { value, .. } => Ok(value),
+            Value::Random | Value::OmittedSample { .. } => {
+                Err(self.unsupported("an omitted random value"))
+            }
+        }
+    }
+
+    /// Binds `code` to a fresh identifier, since `builder` can't be borrowed mutably twice in one call
+    fn temporary(&mut self, code: &str) -> String {
+        let name = format!("t{}", self.temporaries);
+        self.temporaries += 1;
+        self.push(&format!("let {name} = {code};"));
+        name
+    }
+
+    fn push(&mut self, line: &str) {
+        self.body.extend(line.lines().map(ToString::to_string));
+    }
+
+    fn unsupported(&self, operation: &str) -> CodegenError {
+        CodegenError::Unsupported {
+            stmt_index: self.stmt_index,
+            operation: operation.to_string(),
+        }
+    }
+}
+
+fn is_zero(expr: &Expression) -> bool {
+    match expr {
+        Expression::Value(Value::U64(0)) => true,
+        Expression::Ident(ident) => constant_value(ident) == Some(Value::U64(0)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use crate::{
+        ast::{VirtualWire, Wire},
+        ir::{CirBuilder, Field},
+        test_util::{binop, test_code_ir},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                "cubed".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Exponent,
+                    Expression::Value(Value::U64(3)),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                VirtualWire::new_public(0),
+                BinOp::Equal,
+                binop(
+                    Expression::Ident("cubed".into()),
+                    BinOp::Add,
+                    binop(
+                        Wire::new_private(0, 0),
+                        BinOp::Divide,
+                        Expression::Value(Value::U64(2)),
+                    ),
+                ),
+            )))
+            .add_stmt(Stmt::Verify(binop(
+                binop(
+                    Wire::new_private(0, 1),
+                    BinOp::Subtract,
+                    Expression::Ident("cubed".into()),
+                ),
+                BinOp::Equal,
+                Expression::Value(Value::U64(0)),
+            )))
+            .add_stmt(Stmt::Verify(Expression::Unary {
+                op: Op::Sub,
+                expr: Box::new(Wire::new_private(0, 0).into()),
+            }))
+            .name_virtual_wire(0, "out")
+            .build();
+
+        test_code_ir("plonky2_codegen", &generate(&cir).unwrap());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 0),
+                BinOp::LessThan,
+                Expression::Value(Value::U64(8)),
+            )))
+            .build();
+
+        assert_eq!(
+            generate(&cir),
+            Err(CodegenError::Unsupported {
+                stmt_index: 0,
+                operation: "the comparison `<`".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unsupported_field() {
+        let cir = CirBuilder::new()
+            .field(Field::Other)
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 0),
+                BinOp::Equal,
+                Expression::Value(Value::U64(3)),
+            )))
+            .build();
+
+        assert_eq!(
+            generate(&cir),
+            Err(CodegenError::UnsupportedField(Field::Other))
+        );
+    }
+
+    #[test]
+    fn test_colliding_names() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local("a.b".into(), Wire::new_private(0, 0).into()))
+            .add_stmt(Stmt::Local(
+                "a_b".into(),
+                binop(
+                    Expression::Ident("a.b".into()),
+                    BinOp::Add,
+                    Expression::Value(Value::U64(1)),
+                ),
+            ))
+            .build();
+
+        let source = generate(&cir).unwrap();
+
+        assert!(source.contains("let local_a_b = wire_0_0;"));
+        assert!(source.contains("let t1 = builder.add(local_a_b, t0);"));
+        assert!(source.contains("let local_a_b_2 = t1;"));
+    }
+}
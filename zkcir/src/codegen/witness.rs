@@ -0,0 +1,394 @@
+//! Generates a standalone Rust function computing every local of a `Cir` from its inputs, so witnesses can be
+//! regenerated for new inputs without rerunning the framework program that emitted the IR
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use super::{check_goldilocks, CodegenError, RustIdents};
+use crate::analysis::eval::{constant_value, free_vars, solvable_equality, Fp};
+use crate::analysis::Var;
+use crate::ast::{BinOp, Expression, Op, Stmt, Value};
+use crate::ir::Cir;
+use crate::node::Node;
+
+/// Goldilocks arithmetic the generated function needs, so it doesn't depend on plonky2 or zkcir
+const PRELUDE: &str = "mod field {
+    #![allow(dead_code)]
+
+    pub const P: u64 = 0xFFFF_FFFF_0000_0001;
+
+    pub fn add(a: u64, b: u64) -> u64 {
+        ((u128::from(a) + u128::from(b)) % u128::from(P)) as u64
+    }
+
+    pub fn sub(a: u64, b: u64) -> u64 {
+        add(a, P - b)
+    }
+
+    pub fn mul(a: u64, b: u64) -> u64 {
+        ((u128::from(a) * u128::from(b)) % u128::from(P)) as u64
+    }
+
+    pub fn neg(a: u64) -> u64 {
+        sub(0, a)
+    }
+
+    pub fn pow(mut base: u64, mut exponent: u64) -> u64 {
+        let mut result = 1;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = mul(result, base);
+            }
+            base = mul(base, base);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    pub fn div(a: u64, b: u64) -> Option<u64> {
+        (b != 0).then(|| mul(a, pow(b, P - 2)))
+    }
+}
+
+use field::*;";
+
+/// Generates `generate_witness`, which runs the statements of `cir` over the Goldilocks field like
+/// `eval::evaluate`: every `Stmt::Local` assigns its variable, a verify like `verify!(out == e)` solves `out`, and
+/// every other verify is checked. It takes an `Inputs` with a field per variable in `eval::free_vars` and returns a
+/// `Witness` with the final value of every variable, or which statement failed.
+///
+/// # Errors
+///
+/// Errors if the circuit isn't over Goldilocks, and on omitted random values, since the value the circuit was built
+/// with is unknown
+pub fn generate(cir: &Cir) -> Result<String, CodegenError> {
+    check_goldilocks(cir)?;
+
+    let inputs = free_vars(cir);
+
+    let mut generator = Generator {
+        idents: RustIdents::new(cir),
+        known: inputs.clone(),
+        body: Vec::new(),
+        stmt_index: 0,
+    };
+
+    for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+        generator.stmt_index = stmt_index;
+        generator.body.push(String::new());
+        generator.body.push(format!("// {}", stmt.to_code_ir()));
+        generator.stmt(stmt)?;
+    }
+
+    let mut source = format!("// Generated by zkcir\n\n{PRELUDE}\n\n");
+
+    let fields = |vars: &BTreeSet<Var>, idents: &RustIdents, source: &mut String| {
+        for var in vars {
+            let _ = writeln!(
+                source,
+                "    /// {}\n    pub {}: u64,",
+                cir.var_name(var),
+                idents.get(var)
+            );
+        }
+    };
+
+    source.push_str("#[derive(Clone, Copy, Debug, Default)]\npub struct Inputs {\n");
+    fields(&inputs, &generator.idents, &mut source);
+    source.push_str("}\n\n");
+
+    source.push_str("/// Final value of every variable, reduced modulo `P`\n#[derive(Clone, Copy, Debug, PartialEq, Eq)]\npub struct Witness {\n");
+    fields(&generator.known, &generator.idents, &mut source);
+    source.push_str("}\n\n");
+
+    source.push_str(
+        "/// Errors with the first statement that fails or divides by zero\npub fn generate_witness(inputs: &Inputs) -> Result<Witness, &'static str> {\n",
+    );
+
+    for var in &inputs {
+        let _ = writeln!(
+            source,
+            "    let {0} = inputs.{0} % P;",
+            generator.idents.get(var)
+        );
+    }
+
+    for line in &generator.body {
+        if line.is_empty() {
+            source.push('\n');
+        } else {
+            let _ = writeln!(source, "    {line}");
+        }
+    }
+
+    let fields = generator
+        .known
+        .iter()
+        .map(|var| generator.idents.get(var))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(source, "\n    Ok(Witness {{ {fields} }})\n}}");
+
+    Ok(source)
+}
+
+struct Generator {
+    idents: RustIdents,
+    /// Variables bound to their Rust identifier
+    known: BTreeSet<Var>,
+    body: Vec<String>,
+    stmt_index: usize,
+}
+
+impl Generator {
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), CodegenError> {
+        let (var, expr) = match stmt {
+            Stmt::Local(ident, expr) => (Var::from(ident), expr),
+            Stmt::Verify(expr) => {
+                if let Some((var, expr)) = solvable_equality(expr, &|var| self.known.contains(var))
+                {
+                    (var, expr)
+                } else {
+                    let value = self.expr(expr)?;
+                    let check = format!(
+                        "if {value} == 0 {{\n    return Err(\"stmt {} fails\");\n}}",
+                        self.stmt_index
+                    );
+                    self.body.extend(check.lines().map(ToString::to_string));
+                    return Ok(());
+                }
+            }
+        };
+
+        let value = self.expr(expr)?;
+        self.body
+            .push(format!("let {} = {value};", self.idents.get(&var)));
+        self.known.insert(var);
+
+        Ok(())
+    }
+
+    /// Rust expression computing `expr` as a canonical `u64`
+    fn expr(&self, expr: &Expression) -> Result<String, CodegenError> {
+        match expr {
+            Expression::Value(value) => self.value(*value),
+            Expression::Ident(ident) => match constant_value(ident) {
+                Some(value) => self.value(value),
+                None => Ok(self.idents.get(&Var::from(ident))),
+            },
+            Expression::Unary { op: Op::Sub, expr } => Ok(format!("neg({})", self.expr(expr)?)),
+            Expression::BinaryOperator { lhs, binop, rhs } => {
+                let (lhs, rhs) = (self.expr(lhs)?, self.expr(rhs)?);
+
+                Ok(match binop {
+                    BinOp::Add => format!("add({lhs}, {rhs})"),
+                    BinOp::Subtract => format!("sub({lhs}, {rhs})"),
+                    BinOp::Multiply => format!("mul({lhs}, {rhs})"),
+                    BinOp::Divide => format!(
+                        "div({lhs}, {rhs}).ok_or(\"stmt {} divides by zero\")?",
+                        self.stmt_index
+                    ),
+                    BinOp::Exponent => format!("pow({lhs}, {rhs})"),
+                    BinOp::Equal
+                    | BinOp::LessThan
+                    | BinOp::LessThanEqual
+                    | BinOp::GreaterThan
+                    | BinOp::GreaterThanEqual => format!("u64::from({lhs} {binop} {rhs})"),
+                })
+            }
+        }
+    }
+
+    fn value(&self, value: Value) -> Result<String, CodegenError> {
+        match value {
+            Value::U64(value) | Value::RandomU64(value) | Value::Sampled { value, .. } => {
+                Ok(Fp::new(value).to_string())
+            }
+            Value::Random | Value::OmittedSample { .. } => Err(CodegenError::Unsupported {
+                stmt_index: self.stmt_index,
+                operation: "an omitted random value".to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use alloc::boxed::Box;
+    use alloc::collections::BTreeMap;
+    use std::{env, fs, process};
+
+    use crate::{
+        analysis::eval::evaluate,
+        ast::{Ident, VirtualWire, Wire, WireRef},
+        ir::{CirBuilder, Field},
+        test_util::{binop, test_code_ir},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                "squared".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Multiply,
+                    Wire::new_private(0, 0),
+                ),
+            ))
+            .add_stmt(Stmt::Local(
+                Ident::Wire(Wire::new_private(1, 0)),
+                binop(
+                    Expression::Ident("squared".into()),
+                    BinOp::Divide,
+                    Wire::new_private(0, 1),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                VirtualWire::new_public(0),
+                BinOp::Equal,
+                binop(
+                    Wire::new_private(1, 0),
+                    BinOp::Subtract,
+                    Expression::Value(Value::U64(1)),
+                ),
+            )))
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 1),
+                BinOp::LessThan,
+                Expression::Value(Value::U64(256)),
+            )))
+            .name_virtual_wire(0, "out")
+            .build();
+
+        test_code_ir("witness_codegen", &generate(&cir).unwrap());
+    }
+
+    #[test]
+    fn test_omitted_random() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local("x".into(), Expression::Value(Value::Random)))
+            .build();
+
+        assert_eq!(
+            generate(&cir),
+            Err(CodegenError::Unsupported {
+                stmt_index: 0,
+                operation: "an omitted random value".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unsupported_field() {
+        let cir = CirBuilder::new()
+            .field(Field::Other)
+            .add_stmt(Stmt::Local("x".into(), Wire::new_private(0, 0).into()))
+            .build();
+
+        assert_eq!(
+            generate(&cir),
+            Err(CodegenError::UnsupportedField(Field::Other))
+        );
+    }
+
+    /// Compiles the generated function with `rustc` and checks it computes the same values as `evaluate`
+    #[test]
+    fn test_matches_evaluate() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                "a.b".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Exponent,
+                    Expression::Value(Value::U64(3)),
+                ),
+            ))
+            .add_stmt(Stmt::Local(
+                "a_b".into(),
+                binop(
+                    Expression::Ident("a.b".into()),
+                    BinOp::Divide,
+                    Wire::new_private(0, 1),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                VirtualWire::new_public(0),
+                BinOp::Equal,
+                binop(
+                    Expression::Ident("a_b".into()),
+                    BinOp::Subtract,
+                    Wire::new_private(0, 0),
+                ),
+            )))
+            .add_stmt(Stmt::Local(
+                Ident::Wire(Wire::new_private(1, 0)),
+                Expression::Unary {
+                    op: Op::Sub,
+                    expr: Box::new(binop(
+                        Wire::new_private(0, 1),
+                        BinOp::LessThan,
+                        Expression::Value(Value::U64(256)),
+                    )),
+                },
+            ))
+            .build();
+
+        let inputs = BTreeMap::from([
+            (Var::Wire(WireRef::Wire { row: 0, column: 0 }), Fp::new(7)),
+            (Var::Wire(WireRef::Wire { row: 0, column: 1 }), Fp::new(2)),
+        ]);
+        let evaluation = evaluate(&cir, &inputs).unwrap();
+
+        let idents = RustIdents::new(&cir);
+        let mut source = generate(&cir).unwrap();
+        source.push_str("\nfn main() {\n    let inputs = Inputs {\n");
+        for (var, value) in &inputs {
+            let _ = writeln!(source, "        {}: {value},", idents.get(var));
+        }
+        source.push_str("    };\n    let witness = generate_witness(&inputs).unwrap();\n");
+        for var in evaluation.values.keys() {
+            let _ = writeln!(
+                source,
+                "    println!(\"{{}}\", witness.{});",
+                idents.get(var)
+            );
+        }
+        source.push_str("}\n");
+
+        let dir = env::temp_dir().join(format!("zkcir_witness_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (path, binary) = (dir.join("witness.rs"), dir.join("witness"));
+        fs::write(&path, source).unwrap();
+
+        let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let status = process::Command::new(rustc)
+            .args(["--edition", "2021", "-A", "warnings", "-o"])
+            .arg(&binary)
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let output = process::Command::new(&binary).output().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(output.status.success());
+
+        let expected = evaluation
+            .values
+            .values()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let actual = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(actual.lines().collect::<Vec<_>>(), expected);
+    }
+}
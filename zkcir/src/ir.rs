@@ -1,36 +1,86 @@
 extern crate alloc;
 
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::format;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
-use serde::Deserialize;
-use serde::Serialize;
-use serde_json;
+use core::fmt::Write;
+use derive_more::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+use crate::analysis::eval::GOLDILOCKS_ORDER;
+use crate::analysis::{expression_vars, stmt_def, stmt_uses, Var};
 use crate::ast::Expression;
+use crate::ast::Ident;
 use crate::ast::Stmt;
 use crate::ast::Value;
 use crate::ast::VirtualWire;
 use crate::ast::Wire;
+use crate::ast::WireRef;
 use crate::ast::Wiretype;
+use crate::canonical::canonicalize;
+#[cfg(feature = "json")]
+use crate::canonical::fingerprint;
 use crate::node::Node;
-use crate::END_DISCRIMINATOR;
-use crate::START_DISCRIMINATOR;
+use crate::parse::{is_name, parse, ParseError};
+#[cfg(feature = "json")]
+use crate::{END_DISCRIMINATOR, START_DISCRIMINATOR};
 
 /// Built circuit. This should be built using `CirBuilder` instead of creating this directly.
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
 pub struct Cir {
     pub config: Config,
     pub stmts: Vec<Stmt>,
 
     pub public_wire_inputs: Vec<Wire>,
     pub public_virtual_wire_inputs: Vec<VirtualWire>,
+
+    /// External interface, ordered as in the proof's public input vector
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub signature: Vec<PublicValue>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub annotations: Vec<WireAnnotation>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub gadgets: Vec<Gadget>,
+
+    /// Sources of `Value::Sampled` values, indexed by `source`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub random_sources: Vec<RandomSource>,
+
+    /// Fiat-Shamir transcript operations in the order the frontend performed them
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub transcript: Vec<TranscriptOp>,
 }
 
 impl Cir {
     /// # Errors
     ///
     /// Errors from `serde_json::to_string_pretty`
+    #[cfg(feature = "json")]
     pub fn to_string(&self) -> Result<String, &'static str> {
         serde_json::to_string_pretty(&self).map_err(|_| "Failed serializing to json")
     }
@@ -40,24 +90,48 @@ impl Cir {
     /// # Errors
     ///
     /// Errors from `serde_json::to_string_pretty`
+    #[cfg(feature = "json")]
     pub fn to_string_omit_random(&self) -> Result<String, &'static str> {
+        serde_json::to_string_pretty(&self.omit_random()).map_err(|_| "Failed serializing to json")
+    }
+
+    /// Copy with random values omitted. `Value::Sampled` values keep their source, so only values the frontend tagged
+    /// as drawn from a `RandomSource` are affected, along with legacy `Value::RandomU64` values.
+    #[must_use]
+    pub fn omit_random(&self) -> Self {
         let mut new = self.clone();
 
-        for expression in &mut new.stmts {
-            expression.visit_wires(&mut |wire| {
-                if let Some(Value::RandomU64(_)) = wire.value {
-                    wire.value = Some(Value::Random);
-                }
-            });
+        for stmt in &mut new.stmts {
+            stmt.visit_values(&mut |value| *value = value.omit_random());
+            stmt.visit_wires(&mut |wire| wire.value = wire.value.map(Value::omit_random));
+            stmt.visit_virtual_wires(&mut |wire| wire.value = wire.value.map(Value::omit_random));
+        }
 
-            expression.visit_virtual_wires(&mut |wire| {
-                if let Some(Value::RandomU64(_)) = wire.value {
-                    wire.value = Some(Value::Random);
-                }
-            });
+        for wire in &mut new.public_wire_inputs {
+            wire.value = wire.value.map(Value::omit_random);
+        }
+
+        for wire in &mut new.public_virtual_wire_inputs {
+            wire.value = wire.value.map(Value::omit_random);
         }
 
-        serde_json::to_string_pretty(&new).map_err(|_| "Failed serializing to json")
+        for op in &mut new.transcript {
+            if let TranscriptOp::Absorb(expr) = op {
+                expr.visit_values(&mut |value| *value = value.omit_random());
+                expr.visit_wires(&mut |wire| wire.value = wire.value.map(Value::omit_random));
+                expr.visit_virtual_wires(&mut |wire| {
+                    wire.value = wire.value.map(Value::omit_random);
+                });
+            }
+        }
+
+        new
+    }
+
+    /// Where `Value::Sampled { source, .. }` values came from
+    #[must_use]
+    pub fn random_source(&self, source: usize) -> Option<&RandomSource> {
+        self.random_sources.get(source)
     }
 
     /// Appends discriminator to the start and end so zkcir's CLI can parse the output. You likely want `to_string`
@@ -66,6 +140,7 @@ impl Cir {
     /// # Errors
     ///
     /// Errors from `self.to_string()`
+    #[cfg(feature = "json")]
     pub fn to_cli_string(&self) -> Result<String, &'static str> {
         Ok(format!(
             "{START_DISCRIMINATOR}{}\n{END_DISCRIMINATOR}\n",
@@ -73,37 +148,611 @@ impl Cir {
         ))
     }
 
+    /// Renders the circuit as source. Annotated wires are printed by name. Parsing the result with `from_code_ir`
+    /// gives back the same circuit.
+    #[must_use]
     pub fn to_code_ir(&self) -> String {
-        self.stmts
+        self.render_code_ir(false, &|_| None)
+    }
+
+    /// Same as `to_code_ir` but annotated wires are printed by name followed by their coordinates, like
+    /// `balance_after@wire::private(row: 12, column: 3)`.
+    #[must_use]
+    pub fn to_code_ir_with_coordinates(&self) -> String {
+        self.render_code_ir(true, &|_| None)
+    }
+
+    /// Same as `to_code_ir` but appends `// {comment}` to every statement `comment` returns a comment for, given the
+    /// statement's index. Useful for annotating analysis results.
+    #[must_use]
+    pub fn to_code_ir_with_comments(&self, comment: &dyn Fn(usize) -> Option<String>) -> String {
+        self.render_code_ir(false, comment)
+    }
+
+    fn render_code_ir(
+        &self,
+        show_coordinates: bool,
+        comment: &dyn Fn(usize) -> Option<String>,
+    ) -> String {
+        let bare_names = self.bare_names();
+        let label = |ident: &Ident| self.label(ident, show_coordinates, &bare_names);
+        let mut sections = Vec::new();
+
+        let header = self
+            .config
+            .to_code_ir()
+            .into_iter()
+            .chain(
+                self.public_wire_inputs
+                    .iter()
+                    .map(|wire| format!("// public!({})", wire.to_code_ir())),
+            )
+            .chain(
+                self.public_virtual_wire_inputs
+                    .iter()
+                    .map(|wire| format!("// public!({})", wire.to_code_ir())),
+            )
+            .chain(
+                self.signature
+                    .iter()
+                    .enumerate()
+                    .map(|(position, public_value)| public_value.to_code_ir(position)),
+            )
+            .chain(
+                self.random_sources
+                    .iter()
+                    .enumerate()
+                    .map(|(source, random_source)| random_source.to_code_ir(source)),
+            )
+            .chain(self.transcript.iter().map(|op| match op {
+                TranscriptOp::Absorb(expr) => {
+                    format!("// absorb!({})", expr.to_labelled_code_ir(&label))
+                }
+                TranscriptOp::Squeeze { .. } => format!("// {}", op.to_code_ir()),
+            }))
+            .chain(self.gadgets.iter().map(Gadget::to_code_ir))
+            .chain(self.annotations.iter().map(|annotation| {
+                match bare_names.get(&annotation.wire_ref) {
+                    Some(wire) => annotation.to_code_ir_with_wire(&wire.to_code_ir()),
+                    None => annotation.to_code_ir(),
+                }
+            }))
+            .collect::<Vec<_>>();
+
+        if !header.is_empty() {
+            sections.push(header.join("\n"));
+        }
+
+        for (i, stmt) in self.stmts.iter().enumerate() {
+            let stmt = stmt.to_labelled_code_ir(&label);
+
+            sections.push(match comment(i) {
+                Some(comment) => format!("{stmt} // {comment}"),
+                None => stmt,
+            });
+        }
+
+        sections.join("\n\n")
+    }
+
+    /// How a named wire is printed, or `None` to print `ident` as is. Without coordinates, the first occurrence of a
+    /// wire with a name in `bare_names` is printed by name alone, and the header line of its annotation records the
+    /// wire so the parser can restore it. Other occurrences are printed as `name@wire`, and wires whose name isn't a
+    /// single identifier are printed as is.
+    fn label(
+        &self,
+        ident: &Ident,
+        show_coordinates: bool,
+        bare_names: &BTreeMap<WireRef, Ident>,
+    ) -> Option<String> {
+        let wire_ref = match ident {
+            Ident::Wire(wire) => wire.wire_ref(),
+            Ident::VirtualWire(virtual_wire) => virtual_wire.wire_ref(),
+            Ident::String(_) => return None,
+        };
+        let name = self
+            .annotation(wire_ref)?
+            .name
+            .as_ref()
+            .filter(|name| is_name(name))?;
+
+        Some(
+            if !show_coordinates && bare_names.get(&wire_ref) == Some(ident) {
+                name.clone()
+            } else {
+                format!("{name}@{}", ident.to_code_ir())
+            },
+        )
+    }
+
+    /// First occurrence of every named wire that can be printed by name alone: its name is a single identifier that
+    /// no other wire or local has
+    fn bare_names(&self) -> BTreeMap<WireRef, Ident> {
+        let absorbed = self.transcript.iter().filter_map(|op| match op {
+            TranscriptOp::Absorb(expr) => Some(expr),
+            TranscriptOp::Squeeze { .. } => None,
+        });
+
+        let mut taken = BTreeMap::<&str, usize>::new();
+        for name in self
+            .annotations
             .iter()
-            .map(Stmt::to_code_ir)
-            .collect::<Vec<_>>()
-            .join("\n\n")
+            .filter_map(|annotation| annotation.name.as_deref())
+        {
+            *taken.entry(name).or_default() += 1;
+        }
+
+        let mut locals = BTreeSet::new();
+        for var in self
+            .stmts
+            .iter()
+            .flat_map(|stmt| stmt_uses(stmt).into_iter().chain(stmt_def(stmt)))
+            .chain(absorbed.clone().flat_map(expression_vars))
+        {
+            if let Var::Local(name) = var {
+                locals.insert(name);
+            }
+        }
+
+        let mut occurrences = Vec::new();
+        for stmt in &self.stmts {
+            match stmt {
+                Stmt::Verify(expr) => collect_wires(expr, &mut occurrences),
+                Stmt::Local(ident, expr) => {
+                    occurrences.push(ident);
+                    collect_wires(expr, &mut occurrences);
+                }
+            }
+        }
+        for expr in absorbed {
+            collect_wires(expr, &mut occurrences);
+        }
+
+        let mut bare_names = BTreeMap::new();
+        for ident in occurrences {
+            let wire_ref = match ident {
+                Ident::Wire(wire) => wire.wire_ref(),
+                Ident::VirtualWire(virtual_wire) => virtual_wire.wire_ref(),
+                Ident::String(_) => continue,
+            };
+
+            let Some(name) = self
+                .annotation(wire_ref)
+                .and_then(|annotation| annotation.name.as_ref())
+            else {
+                continue;
+            };
+
+            if is_name(name) && taken[name.as_str()] == 1 && !locals.contains(name) {
+                bare_names.entry(wire_ref).or_insert_with(|| ident.clone());
+            }
+        }
+
+        bare_names
+    }
+
+    /// Name `var` is printed as in code IR: the annotated name of a wire if it has one
+    #[must_use]
+    pub fn var_name(&self, var: &Var) -> String {
+        match var {
+            Var::Wire(wire_ref) => self
+                .annotation(*wire_ref)
+                .and_then(|annotation| annotation.name.clone())
+                .unwrap_or_else(|| var.to_code_ir()),
+            Var::Local(_) => var.to_code_ir(),
+        }
+    }
+
+    #[must_use]
+    pub fn annotation(&self, wire_ref: WireRef) -> Option<&WireAnnotation> {
+        self.annotations
+            .iter()
+            .find(|annotation| annotation.wire_ref == wire_ref)
     }
 
     /// # Errors
     ///
     /// Errors if cannot deserialize from json
+    #[cfg(feature = "json")]
     pub fn from_json(json_str: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(json_str)
     }
+
+    /// Copy without cosmetic differences like local names and random values. See `canonical::canonicalize`.
+    #[must_use]
+    pub fn canonicalize(&self) -> Self {
+        canonicalize(self)
+    }
+
+    /// Hex SHA-256 digest of the canonical form, equal for circuits that differ only cosmetically
+    ///
+    /// # Errors
+    ///
+    /// Errors from `serde_json::to_string`
+    #[cfg(feature = "json")]
+    pub fn fingerprint(&self) -> Result<String, &'static str> {
+        fingerprint(self)
+    }
+
+    /// Parses the text format emitted by `to_code_ir`. See `parse::parse` for what survives the round trip.
+    ///
+    /// # Errors
+    ///
+    /// Errors with the location of the first syntax error
+    pub fn from_code_ir(source: &str) -> Result<Self, ParseError> {
+        parse(source).map(|parsed| parsed.cir)
+    }
+}
+
+/// File helpers, enabled by the `std` feature
+#[cfg(feature = "std")]
+impl Cir {
+    /// Writes the source ir, as rendered by `to_code_ir`, to `path`
+    ///
+    /// # Errors
+    ///
+    /// Errors from `std::fs::write`
+    pub fn write_code_ir(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_code_ir())
+    }
+
+    /// Writes the json ir, as rendered by `to_string`, to `path`
+    ///
+    /// # Errors
+    ///
+    /// Errors from `to_string` or `std::fs::write`
+    #[cfg(feature = "json")]
+    pub fn write_json(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string().map_err(std::io::Error::other)?)
+    }
+
+    /// # Errors
+    ///
+    /// Errors from `std::fs::read_to_string`, or with `std::io::ErrorKind::InvalidData` if the source ir doesn't parse
+    pub fn read_code_ir(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        Self::from_code_ir(&std::fs::read_to_string(path)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// # Errors
+    ///
+    /// Errors from `std::fs::read_to_string`, or with `std::io::ErrorKind::InvalidData` if the json ir doesn't parse
+    #[cfg(feature = "json")]
+    pub fn read_json(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        Ok(Self::from_json(&std::fs::read_to_string(path)?)?)
+    }
 }
 
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub struct Config {
     num_wires: Option<u64>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Field::is_goldilocks")
+    )]
+    field: Field,
+}
+
+impl Config {
+    #[must_use]
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Renders as a header comment line like `// config!(num_wires: 10, field: other)`, or `None` if this is the
+    /// default config
+    #[must_use]
+    pub fn to_code_ir(&self) -> Option<String> {
+        let mut fields = Vec::new();
+
+        if let Some(num_wires) = self.num_wires {
+            fields.push(format!("num_wires: {num_wires}"));
+        }
+
+        if self.field != Field::Goldilocks {
+            fields.push(format!("field: {}", self.field.name()));
+        }
+
+        (!fields.is_empty()).then(|| format!("// config!({})", fields.join(", ")))
+    }
+}
+
+/// Prime field a circuit's values live in
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum Field {
+    /// plonky2's field of order `2^64 - 2^32 + 1`
+    #[default]
+    #[display(fmt = "Goldilocks")]
+    Goldilocks,
+
+    /// Any other prime field, like the BN254 scalar field Circom uses by default. Always wider than 64 bits, since
+    /// values are `u64`.
+    #[display(fmt = "a field other than Goldilocks")]
+    Other,
+}
+
+impl Field {
+    /// Name in the source IR, like `goldilocks`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Goldilocks => "goldilocks",
+            Field::Other => "other",
+        }
+    }
+
+    /// Inverse of `name`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "goldilocks" => Some(Field::Goldilocks),
+            "other" => Some(Field::Other),
+            _ => None,
+        }
+    }
+
+    /// Order of the field, or `None` if it doesn't fit in a `u64`
+    #[must_use]
+    pub fn modulus(&self) -> Option<u64> {
+        match self {
+            Field::Goldilocks => Some(GOLDILOCKS_ORDER),
+            Field::Other => None,
+        }
+    }
+
+    // Takes a reference since it is serde's `skip_serializing_if`
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    fn is_goldilocks(&self) -> bool {
+        *self == Field::Goldilocks
+    }
+}
+
+/// Human-friendly metadata attached to a wire so reviewers don't have to reason about raw coordinates
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct WireAnnotation {
+    pub wire_ref: WireRef,
+    pub name: Option<String>,
+    pub doc: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl WireAnnotation {
+    #[must_use]
+    pub fn new(wire_ref: WireRef) -> Self {
+        Self {
+            wire_ref,
+            name: None,
+            doc: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Renders as a header comment line like
+    /// `// balance_after = wire(row: 12, column: 3): balance after the transfer [tags: balance, output]`
+    #[must_use]
+    pub fn to_code_ir(&self) -> String {
+        self.to_code_ir_with_wire(&Var::Wire(self.wire_ref).to_code_ir())
+    }
+
+    /// Same as `to_code_ir` but with `wire` in place of the coordinates, like `wire::private(row: 12, column: 3)`
+    pub(crate) fn to_code_ir_with_wire(&self, wire: &str) -> String {
+        let mut line = match &self.name {
+            Some(name) => format!("// {name} = {wire}"),
+            None => format!("// {wire}"),
+        };
+
+        if let Some(doc) = &self.doc {
+            let _ = write!(line, ": {doc}");
+        }
+
+        if !self.tags.is_empty() {
+            let _ = write!(line, " [tags: {}]", self.tags.join(", "));
+        }
+
+        line
+    }
+}
+
+/// Whether a public value is given to the circuit or computed by it
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum Direction {
+    #[display(fmt = "input")]
+    Input,
+
+    #[display(fmt = "output")]
+    Output,
+}
+
+/// Entry of `Cir::signature`
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct PublicValue {
+    pub wire_ref: WireRef,
+    pub name: String,
+    pub direction: Direction,
+}
+
+impl PublicValue {
+    /// Renders as a header comment line like `// input!(0): amount = wire(row: 0, column: 1)`, given its position in
+    /// `Cir::signature`
+    #[must_use]
+    pub fn to_code_ir(&self, position: usize) -> String {
+        format!(
+            "// {}!({position}): {} = {}",
+            self.direction,
+            self.name,
+            Var::Wire(self.wire_ref).to_code_ir()
+        )
+    }
+}
+
+/// Signature entry whose wire the circuit's statements type as private or constant
+#[derive(PartialEq, Eq, Clone, Debug, Display)]
+#[display(
+    fmt = "public {} `{}` is `{}`, which the circuit uses as {wiretype}",
+    "public_value.direction",
+    "public_value.name",
+    "Var::Wire(public_value.wire_ref).to_code_ir()"
+)]
+pub struct SignatureError {
+    /// Index into `Cir::signature`
+    pub position: usize,
+    pub public_value: PublicValue,
+    pub wiretype: Wiretype,
+}
+
+/// Randomness registered by a frontend with `CirBuilder::register_random_source`
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct RandomSource {
+    /// Identifier given by the frontend, like `beta` or `blinding`
+    pub name: String,
+    pub kind: RandomSourceKind,
+}
+
+impl RandomSource {
+    /// Renders as a header comment line like `// random!(0): beta [challenge]`, given its index in
+    /// `Cir::random_sources`
+    #[must_use]
+    pub fn to_code_ir(&self, source: usize) -> String {
+        format!("// random!({source}): {} [{}]", self.name, self.kind)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum RandomSourceKind {
+    /// Verifier challenge derived from the transcript, like a Fiat-Shamir challenge
+    #[display(fmt = "challenge")]
+    Challenge,
+
+    /// Randomness the prover adds to hide the witness
+    #[display(fmt = "blinding")]
+    Blinding,
+
+    /// Randomness generated by test code to fill in witnesses
+    #[display(fmt = "test_rng")]
+    TestRng,
+}
+
+impl RandomSourceKind {
+    /// Inverse of the `Display` implementation
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "challenge" => Some(Self::Challenge),
+            "blinding" => Some(Self::Blinding),
+            "test_rng" => Some(Self::TestRng),
+            _ => None,
+        }
+    }
 }
 
-#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Debug)]
+/// Operation on a Fiat-Shamir transcript, recorded so analyses can check what each challenge is bound to
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum TranscriptOp {
+    /// Hashes the value of the expression into the transcript
+    Absorb(Expression),
+
+    /// Derives the challenge drawn from `Cir::random_sources[source]` from everything absorbed so far
+    Squeeze { source: usize },
+}
+
+impl TranscriptOp {
+    /// Renders like `absorb!(wire::public(row: 0, column: 0))` or `squeeze!(0)`
+    #[must_use]
+    pub fn to_code_ir(&self) -> String {
+        match self {
+            TranscriptOp::Absorb(expr) => format!("absorb!({})", expr.to_code_ir()),
+            TranscriptOp::Squeeze { source } => format!("squeeze!({source})"),
+        }
+    }
+}
+
+/// Named range of statements emitted by a single gadget, like a hash or range check
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub struct Gadget {
+    pub name: String,
+
+    /// Index of the first statement in `Cir::stmts`
+    pub start: usize,
+
+    /// Index one past the last statement in `Cir::stmts`
+    pub end: usize,
+}
+
+impl Gadget {
+    /// Renders as a header comment line like `// gadget!(0..4): poseidon`
+    #[must_use]
+    pub fn to_code_ir(&self) -> String {
+        format!("// gadget!({}..{}): {}", self.start, self.end, self.name)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CirBuilder {
     pub config: Config,
     pub stmts: Vec<Stmt>,
 
     pub public_wire_inputs: Vec<Wire>,
     pub public_virtual_wire_inputs: Vec<VirtualWire>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub annotations: Vec<WireAnnotation>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub gadgets: Vec<Gadget>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub signature: Vec<PublicValue>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub random_sources: Vec<RandomSource>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub transcript: Vec<TranscriptOp>,
+
+    /// Indices into `gadgets` that haven't been ended yet
+    #[cfg_attr(feature = "serde", serde(skip))]
+    open_gadgets: Vec<usize>,
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
 pub struct Operation {
     name: String,
     args: Vec<Expression>,
@@ -113,10 +762,19 @@ impl CirBuilder {
     #[must_use]
     pub fn new() -> Self {
         CirBuilder {
-            config: Config { num_wires: None },
+            config: Config {
+                num_wires: None,
+                field: Field::Goldilocks,
+            },
             stmts: Vec::new(),
             public_wire_inputs: Vec::new(),
             public_virtual_wire_inputs: Vec::new(),
+            annotations: Vec::new(),
+            gadgets: Vec::new(),
+            signature: Vec::new(),
+            random_sources: Vec::new(),
+            transcript: Vec::new(),
+            open_gadgets: Vec::new(),
         }
     }
 
@@ -125,11 +783,132 @@ impl CirBuilder {
         self
     }
 
+    pub fn field(&mut self, field: Field) -> &mut Self {
+        self.config.field = field;
+        self
+    }
+
     pub fn add_stmt(&mut self, x: Stmt) -> &mut Self {
         self.stmts.push(x);
+
+        for &open_gadget in &self.open_gadgets {
+            self.gadgets[open_gadget].end = self.stmts.len();
+        }
+
+        self
+    }
+
+    /// Statements added until the matching `end_gadget` are attributed to gadget `name`. Gadgets can be nested.
+    pub fn begin_gadget(&mut self, name: &str) -> &mut Self {
+        self.open_gadgets.push(self.gadgets.len());
+        self.gadgets.push(Gadget {
+            name: name.to_string(),
+            start: self.stmts.len(),
+            end: self.stmts.len(),
+        });
+        self
+    }
+
+    pub fn end_gadget(&mut self) -> &mut Self {
+        self.open_gadgets.pop();
+        self
+    }
+
+    /// Registers a source of randomness and returns the `source` to tag its values with, as in
+    /// `Value::Sampled { source, value }`. Tagging values where they are drawn, instead of recognizing them later by
+    /// their value, avoids marking an unrelated constant that happens to be equal as random.
+    pub fn register_random_source(&mut self, name: &str, kind: RandomSourceKind) -> usize {
+        self.random_sources.push(RandomSource {
+            name: name.to_string(),
+            kind,
+        });
+        self.random_sources.len() - 1
+    }
+
+    /// Appends a public input named `name` to the signature, after every public value declared so far. Declaring the
+    /// same input again does nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the wire is already in the signature under another name or as an output
+    pub fn public_input(&mut self, wire_ref: WireRef, name: &str) -> &mut Self {
+        self.declare_public_value(wire_ref, name, Direction::Input)
+    }
+
+    /// Appends a public output named `name` to the signature, after every public value declared so far. Declaring the
+    /// same output again does nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the wire is already in the signature under another name or as an input
+    pub fn public_output(&mut self, wire_ref: WireRef, name: &str) -> &mut Self {
+        self.declare_public_value(wire_ref, name, Direction::Output)
+    }
+
+    fn declare_public_value(
+        &mut self,
+        wire_ref: WireRef,
+        name: &str,
+        direction: Direction,
+    ) -> &mut Self {
+        match self
+            .signature
+            .iter()
+            .find(|public_value| public_value.wire_ref == wire_ref)
+        {
+            Some(declared) => assert!(
+                declared.name == name && declared.direction == direction,
+                "`{}` is already declared as public {} `{}`",
+                Var::Wire(wire_ref).to_code_ir(),
+                declared.direction,
+                declared.name
+            ),
+            None => self.signature.push(PublicValue {
+                wire_ref,
+                name: name.to_string(),
+                direction,
+            }),
+        }
+        self
+    }
+
+    /// Type of each wire that some statement uses as private or constant, from the first statement doing so
+    fn non_public_wires(&self) -> BTreeMap<WireRef, Wiretype> {
+        let mut non_public = BTreeMap::new();
+
+        for stmt in &self.stmts {
+            let mut stmt = stmt.clone();
+            stmt.visit_wires(&mut |wire| {
+                if wire.wiretype != Wiretype::Public {
+                    non_public.entry(wire.wire_ref()).or_insert(wire.wiretype);
+                }
+            });
+            stmt.visit_virtual_wires(&mut |virtual_wire| {
+                if virtual_wire.wiretype != Wiretype::Public {
+                    non_public
+                        .entry(virtual_wire.wire_ref())
+                        .or_insert(virtual_wire.wiretype);
+                }
+            });
+        }
+
+        non_public
+    }
+
+    /// Records that `expr` was hashed into the Fiat-Shamir transcript
+    pub fn absorb(&mut self, expr: impl Into<Expression>) -> &mut Self {
+        self.transcript.push(TranscriptOp::Absorb(expr.into()));
         self
     }
 
+    /// Registers a challenge derived from everything absorbed so far and returns its `source`, to tag the challenge
+    /// value with as in `Value::Sampled { source, value }`
+    pub fn squeeze(&mut self, name: &str) -> usize {
+        let source = self.register_random_source(name, RandomSourceKind::Challenge);
+        self.transcript.push(TranscriptOp::Squeeze { source });
+        source
+    }
+
     pub fn set_virtual_wire_value(&mut self, index: usize, value: Value) -> &mut Self {
         for stmt in &mut self.stmts {
             stmt.visit_virtual_wires(&mut |virtual_wire| {
@@ -166,6 +945,53 @@ impl CirBuilder {
         self
     }
 
+    pub fn name_wire(&mut self, row: usize, column: usize, name: &str) -> &mut Self {
+        self.annotation_mut(WireRef::Wire { row, column }).name = Some(name.to_string());
+        self
+    }
+
+    pub fn name_virtual_wire(&mut self, index: usize, name: &str) -> &mut Self {
+        self.annotation_mut(WireRef::VirtualWire { index }).name = Some(name.to_string());
+        self
+    }
+
+    pub fn document_wire(&mut self, row: usize, column: usize, doc: &str) -> &mut Self {
+        self.annotation_mut(WireRef::Wire { row, column }).doc = Some(doc.to_string());
+        self
+    }
+
+    pub fn document_virtual_wire(&mut self, index: usize, doc: &str) -> &mut Self {
+        self.annotation_mut(WireRef::VirtualWire { index }).doc = Some(doc.to_string());
+        self
+    }
+
+    pub fn tag_wire(&mut self, row: usize, column: usize, tag: &str) -> &mut Self {
+        self.annotation_mut(WireRef::Wire { row, column })
+            .tags
+            .push(tag.to_string());
+        self
+    }
+
+    pub fn tag_virtual_wire(&mut self, index: usize, tag: &str) -> &mut Self {
+        self.annotation_mut(WireRef::VirtualWire { index })
+            .tags
+            .push(tag.to_string());
+        self
+    }
+
+    fn annotation_mut(&mut self, wire_ref: WireRef) -> &mut WireAnnotation {
+        if let Some(position) = self
+            .annotations
+            .iter()
+            .position(|annotation| annotation.wire_ref == wire_ref)
+        {
+            &mut self.annotations[position]
+        } else {
+            self.annotations.push(WireAnnotation::new(wire_ref));
+            self.annotations.last_mut().unwrap()
+        }
+    }
+
     // TODO: self shouldn't be mut
     pub fn has_wire_defined(&mut self, row: usize, column: usize, wire_type: Wiretype) -> bool {
         self.stmts.iter_mut().any(|stmt| {
@@ -181,14 +1007,58 @@ impl CirBuilder {
         })
     }
 
+    /// # Panics
+    ///
+    /// Panics if the signature declares a wire the circuit uses as private or constant, see `try_build`
     #[must_use]
     pub fn build(&self) -> Cir {
-        Cir {
+        self.try_build().unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Same as `build`, but returns the first signature entry contradicting the circuit instead of panicking
+    ///
+    /// # Errors
+    ///
+    /// Errors if the signature declares a wire the circuit uses as private or constant
+    pub fn try_build(&self) -> Result<Cir, SignatureError> {
+        if !self.signature.is_empty() {
+            let non_public = self.non_public_wires();
+
+            for (position, public_value) in self.signature.iter().enumerate() {
+                if let Some(&wiretype) = non_public.get(&public_value.wire_ref) {
+                    return Err(SignatureError {
+                        position,
+                        public_value: public_value.clone(),
+                        wiretype,
+                    });
+                }
+            }
+        }
+
+        Ok(Cir {
             config: self.config,
             stmts: self.stmts.clone(),
             public_wire_inputs: self.public_wire_inputs.clone(),
             public_virtual_wire_inputs: self.public_virtual_wire_inputs.clone(),
+            annotations: self.annotations.clone(),
+            gadgets: self.gadgets.clone(),
+            signature: self.signature.clone(),
+            random_sources: self.random_sources.clone(),
+            transcript: self.transcript.clone(),
+        })
+    }
+}
+
+/// Wires and virtual wires `expr` reads, in the order they are printed
+fn collect_wires<'a>(expr: &'a Expression, idents: &mut Vec<&'a Ident>) {
+    match expr {
+        Expression::BinaryOperator { lhs, rhs, .. } => {
+            collect_wires(lhs, idents);
+            collect_wires(rhs, idents);
         }
+        Expression::Ident(ident) => idents.push(ident),
+        Expression::Value(_) => {}
+        Expression::Unary { expr, .. } => collect_wires(expr, idents),
     }
 }
 
@@ -202,24 +1072,29 @@ impl Default for CirBuilder {
 mod tests {
     use alloc::boxed::Box;
 
+    #[cfg(feature = "json")]
+    use crate::{ast::Ident, test_util::test_ir_string};
     use crate::{
-        ast::{BinOp, Ident, VirtualWire, Wire},
-        test_util::{test_code_ir, test_ir_string},
+        ast::{BinOp, VirtualWire, Wire},
+        test_util::{binop, test_code_ir},
     };
 
     use super::*;
 
     #[test]
+    #[cfg(feature = "json")]
     fn test_valid_cir() {
         test_ir_string("valid_cir", CirBuilder::new().num_wires(10));
     }
 
     #[test]
+    #[cfg(feature = "json")]
     fn test_no_wires() {
         test_ir_string("test_no_wires", &CirBuilder::new());
     }
 
     #[test]
+    #[cfg(feature = "json")]
     fn test_binop() {
         let mut circuit = CirBuilder::new();
         circuit
@@ -243,6 +1118,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "json")]
     fn test_verify() {
         test_ir_string(
             "test_verify",
@@ -255,6 +1131,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "json")]
     fn test_omit_random() {
         test_ir_string(
             "test_omit_random",
@@ -276,6 +1153,33 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_random_sources() {
+        let mut circuit = CirBuilder::new();
+        let beta = circuit.register_random_source("beta", RandomSourceKind::Challenge);
+        circuit.add_stmt(Stmt::Verify(Expression::BinaryOperator {
+            lhs: Box::new(Wire::new_private(0, 0).into()),
+            binop: BinOp::Equal,
+            rhs: Box::new(Expression::BinaryOperator {
+                lhs: Box::new(Expression::Value(Value::Sampled {
+                    source: beta,
+                    value: 7,
+                })),
+                binop: BinOp::Multiply,
+                // Equal to the sampled value but not drawn from a source, so it must be kept
+                rhs: Box::new(Expression::Value(Value::U64(7))),
+            }),
+        }));
+
+        test_ir_string("test_random_sources", &circuit);
+        test_code_ir("ir_random_sources", &circuit.build().to_code_ir());
+        test_code_ir(
+            "ir_random_sources_omitted",
+            &circuit.build().omit_random().to_code_ir(),
+        );
+    }
+
     #[test]
     fn test_public_input() {
         test_code_ir(
@@ -297,4 +1201,92 @@ mod tests {
                 .to_code_ir(),
         );
     }
+
+    #[test]
+    fn test_signature() {
+        let mut builder = CirBuilder::new();
+        builder
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_public(0, 0),
+                BinOp::Equal,
+                Wire::new_private(0, 1),
+            )))
+            .public_input(WireRef::Wire { row: 0, column: 0 }, "amount")
+            .public_input(WireRef::Wire { row: 0, column: 0 }, "amount")
+            .public_output(WireRef::VirtualWire { index: 0 }, "out");
+
+        assert_eq!(
+            builder.build().signature,
+            [
+                PublicValue {
+                    wire_ref: WireRef::Wire { row: 0, column: 0 },
+                    name: "amount".into(),
+                    direction: Direction::Input,
+                },
+                PublicValue {
+                    wire_ref: WireRef::VirtualWire { index: 0 },
+                    name: "out".into(),
+                    direction: Direction::Output,
+                },
+            ]
+        );
+
+        builder.public_input(WireRef::Wire { row: 0, column: 1 }, "secret");
+        let error = builder.try_build().unwrap_err();
+        assert_eq!(error.position, 2);
+        assert_eq!(error.wiretype, Wiretype::Private);
+        assert_eq!(
+            error.to_string(),
+            "public input `secret` is `wire(row: 0, column: 1)`, which the circuit uses as private"
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "`wire(row: 0, column: 0)` is already declared as public input `amount`"
+    )]
+    fn test_conflicting_signature() {
+        CirBuilder::new()
+            .public_input(WireRef::Wire { row: 0, column: 0 }, "amount")
+            .public_output(WireRef::Wire { row: 0, column: 0 }, "amount_again");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "public input `secret` is `wire(row: 0, column: 1)`, which the circuit uses as private"
+    )]
+    fn test_private_signature() {
+        let _ = CirBuilder::new()
+            .add_stmt(Stmt::Verify(Wire::new_private(0, 1).into()))
+            .public_input(WireRef::Wire { row: 0, column: 1 }, "secret")
+            .build();
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_named_wires() {
+        let mut circuit = CirBuilder::new();
+        circuit
+            .add_stmt(Stmt::Local(
+                Ident::Wire(Wire::new_private(12, 3)),
+                Expression::BinaryOperator {
+                    lhs: Box::new(Wire::new_private(4, 3).into()),
+                    binop: BinOp::Subtract,
+                    rhs: Box::new(VirtualWire::new_public(1).into()),
+                },
+            ))
+            .name_wire(12, 3, "balance_after")
+            .name_wire(4, 3, "balance_before")
+            .name_virtual_wire(1, "amount")
+            .document_wire(12, 3, "balance after the transfer")
+            .tag_wire(12, 3, "balance")
+            .tag_virtual_wire(1, "input");
+
+        test_ir_string("test_named_wires", &circuit);
+        test_code_ir("ir_named_wires", &circuit.build().to_code_ir());
+        test_code_ir(
+            "ir_named_wires_coordinates",
+            &circuit.build().to_code_ir_with_coordinates(),
+        );
+    }
 }
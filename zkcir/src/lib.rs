@@ -3,9 +3,23 @@
 #![allow(clippy::struct_excessive_bools)]
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod analysis;
 pub mod ast;
+pub mod canonical;
+pub mod circom;
+pub mod codegen;
 pub mod ir;
 pub mod node;
+pub mod parse;
+pub mod query;
+pub mod r1cs;
+pub mod reduce;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 #[cfg(test)]
 mod test_util;
@@ -29,4 +29,11 @@ pub trait Node {
 
     #[must_use]
     fn to_code_ir(&self) -> String;
+
+    /// Same as `to_code_ir` but identifiers `label` returns a rendering for are printed as that rendering
+    #[must_use]
+    fn to_labelled_code_ir(&self, label: &dyn Fn(&Ident) -> Option<String>) -> String {
+        let _ = label;
+        self.to_code_ir()
+    }
 }
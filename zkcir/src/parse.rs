@@ -0,0 +1,1357 @@
+//! Parser for the `.cir` text format emitted by `Cir::to_code_ir` and `Cir::to_code_ir_with_coordinates`
+//!
+//! Header comments before the first statement restore everything else in the `Cir`:
+//! - `// config!(num_wires: 10, field: other)` the config, if it isn't the default
+//! - `// public!(wire::public(row: 0, column: 1))` the public wire and virtual wire inputs
+//! - `// input!(0): amount = wire(row: 0, column: 1)` the signature
+//! - `// random!(0): beta [challenge]` random sources
+//! - `// absorb!(x)` and `// squeeze!(0)` the transcript
+//! - `// gadget!(0..4): poseidon` gadgets
+//! - `// name = wire::private(row: 1, column: 2): doc [tags: a, b]` annotations
+//!
+//! A bare name is parsed as the wire its annotation's header line gives in full, like above, and as a local otherwise.
+//! Named wires can also be written as `name@wire::private(row: 1, column: 2)`.
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_more::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{BinOp, Expression, Ident, Op, Stmt, Value, VirtualWire, Wire, WireRef, Wiretype};
+use crate::ir::{
+    Cir, CirBuilder, Config, Direction, Field, Gadget, PublicValue, RandomSource, RandomSourceKind,
+    TranscriptOp,
+};
+
+/// Byte range in the source
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    #[must_use]
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    #[must_use]
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// 1-based line and column of `start` in `source`
+    #[must_use]
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        line_column(source, self.start)
+    }
+}
+
+/// 1-based line and column of the byte `offset` in `source`, counting columns in characters
+#[must_use]
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Display)]
+#[display(fmt = "{line}:{column}: {message}")]
+pub struct ParseError {
+    pub span: Span,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Identifier as written in the source
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IdentOccurrence {
+    pub ident: Ident,
+    pub span: Span,
+
+    /// Index into `Cir::stmts` of the statement it appears in
+    pub stmt_index: usize,
+
+    /// Whether this is the target of a `let`
+    pub is_definition: bool,
+}
+
+/// Parsed circuit along with where everything came from in the source
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParsedCir {
+    pub cir: Cir,
+
+    /// Span of each statement in `cir.stmts`, including the trailing `;`
+    pub stmt_spans: Vec<Span>,
+
+    /// Every identifier in source order
+    pub idents: Vec<IdentOccurrence>,
+}
+
+impl ParsedCir {
+    /// Innermost identifier at the byte `offset`
+    #[must_use]
+    pub fn ident_at(&self, offset: usize) -> Option<&IdentOccurrence> {
+        self.idents
+            .iter()
+            .find(|occurrence| occurrence.span.contains(offset))
+    }
+
+    /// Index of the statement at the byte `offset`
+    #[must_use]
+    pub fn stmt_at(&self, offset: usize) -> Option<usize> {
+        self.stmt_spans
+            .iter()
+            .position(|span| span.contains(offset))
+    }
+}
+
+/// Parses `.cir` source into a `Cir`
+///
+/// # Errors
+///
+/// Errors with the location of the first syntax error
+pub fn parse(source: &str) -> Result<ParsedCir, ParseError> {
+    let (tokens, comments) = lex(source, false)?;
+
+    // Header comments come before the first statement
+    let first_stmt = tokens.first().map_or(source.len(), |(_, span)| span.start);
+    let header = comments
+        .iter()
+        .filter(|(span, _)| span.start < first_stmt)
+        .copied()
+        .collect::<Vec<_>>();
+
+    let wires = header
+        .iter()
+        .filter_map(|(_, text)| match annotation(text)? {
+            HeaderAnnotation {
+                name: Some(name),
+                label: Label::Wire(wire),
+                ..
+            } => Some((name.to_string(), wire)),
+            _ => None,
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let mut parser = Parser {
+        source,
+        tokens,
+        position: 0,
+        stmt_index: 0,
+        idents: Vec::new(),
+        names: Vec::new(),
+        wires: &wires,
+    };
+
+    let mut builder = CirBuilder::new();
+    let mut stmt_spans = Vec::new();
+
+    while !parser.is_at_end() {
+        let start = parser.peek_span().start;
+        builder.add_stmt(parser.stmt()?);
+        stmt_spans.push(Span::new(start, parser.previous_span().end));
+        parser.stmt_index += 1;
+    }
+
+    let names = parser
+        .names
+        .iter()
+        .map(|(name, wire_ref)| (name.clone(), *wire_ref))
+        .collect::<BTreeMap<_, _>>();
+
+    // Span of the header line declaring each signature entry
+    let mut signature_spans = Vec::new();
+
+    for (span, text) in header {
+        if let Some(config) = config(text) {
+            builder.config = config;
+        } else if let Some(input) = public_input(text, &wires) {
+            match input {
+                Ident::Wire(wire) => builder.public_wire_inputs.push(wire),
+                Ident::VirtualWire(virtual_wire) => {
+                    builder.public_virtual_wire_inputs.push(virtual_wire);
+                }
+                Ident::String(_) => {}
+            }
+        } else if let Some((position, public_value)) = public_value(text) {
+            // Declared in order, since the position is the index in the proof's public input vector
+            if position == builder.signature.len() {
+                builder.signature.push(public_value);
+                signature_spans.push(span);
+            }
+        } else if let Some((source, random_source)) = random_source(text) {
+            // Sources are only restored in order, since `Value::Sampled` refers to them by index
+            if source == builder.random_sources.len() {
+                builder.register_random_source(&random_source.name, random_source.kind);
+            }
+        } else if let Some(op) = transcript_op(text, &wires) {
+            builder.transcript.push(op);
+        } else if let Some(gadget) = gadget(text) {
+            builder.gadgets.push(gadget);
+        } else {
+            annotate(&mut builder, &names, text);
+        }
+    }
+
+    // Wires named with `name@wire` that the header doesn't mention
+    for (name, wire_ref) in &parser.names {
+        if builder
+            .annotations
+            .iter()
+            .any(|annotation| annotation.wire_ref == *wire_ref && annotation.name.is_some())
+        {
+            continue;
+        }
+
+        match *wire_ref {
+            WireRef::Wire { row, column } => builder.name_wire(row, column, name),
+            WireRef::VirtualWire { index } => builder.name_virtual_wire(index, name),
+        };
+    }
+
+    let cir = builder
+        .try_build()
+        .map_err(|e| error(source, signature_spans[e.position], &e.to_string()))?;
+
+    Ok(ParsedCir {
+        cir,
+        stmt_spans,
+        idents: parser.idents,
+    })
+}
+
+/// What a token of the source is, for syntax highlighting
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TokenKind {
+    Comment,
+
+    /// `let`, macros like `verify!`, and the parts of a wire like `wire::private`
+    Keyword,
+
+    /// Field of a wire, like `row` in `row: 1`
+    Label,
+
+    /// Local or wire name
+    Name,
+    Number,
+    Punctuation,
+}
+
+/// Every token and comment in source order, for syntax highlighting. Unlike `parse` this only needs the source to lex.
+///
+/// # Errors
+///
+/// Errors with the location of the first character that can't start a token
+pub fn tokens(source: &str) -> Result<Vec<(Span, TokenKind)>, ParseError> {
+    let (tokens, comments) = lex(source, false)?;
+
+    let punct_at = |index: usize| match tokens.get(index) {
+        Some((Token::Punct(punct), _)) => Some(*punct),
+        _ => None,
+    };
+
+    let mut kinds = tokens
+        .iter()
+        .enumerate()
+        .map(|(index, (token, span))| {
+            let kind = match token {
+                Token::Name(name) => {
+                    let previous = index.checked_sub(1).and_then(punct_at);
+                    let next = punct_at(index + 1);
+
+                    if name == "let" || previous == Some("::") || matches!(next, Some("!" | "::")) {
+                        TokenKind::Keyword
+                    } else if next == Some(":") {
+                        TokenKind::Label
+                    } else {
+                        TokenKind::Name
+                    }
+                }
+                Token::Number(_) => TokenKind::Number,
+                Token::Punct(_) => TokenKind::Punctuation,
+            };
+
+            (*span, kind)
+        })
+        .chain(comments.iter().map(|(span, _)| (*span, TokenKind::Comment)))
+        .collect::<Vec<_>>();
+
+    kinds.sort_by_key(|(span, _)| *span);
+    Ok(kinds)
+}
+
+/// Query pattern: a statement, or an expression to find anywhere in a statement. Names starting with `$` are
+/// metavariables, parsed as locals.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) enum PatternAst {
+    Stmt(Stmt),
+    Expression(Expression),
+}
+
+pub(crate) fn parse_pattern(source: &str) -> Result<PatternAst, ParseError> {
+    let (mut tokens, _) = lex(source, true)?;
+
+    // The trailing `;` is optional in patterns
+    if !matches!(tokens.last(), Some((Token::Punct(";"), _))) {
+        tokens.push((Token::Punct(";"), Span::new(source.len(), source.len())));
+    }
+
+    let mut parser = Parser {
+        source,
+        tokens,
+        position: 0,
+        stmt_index: 0,
+        idents: Vec::new(),
+        names: Vec::new(),
+        wires: &BTreeMap::new(),
+    };
+
+    let pattern = if matches!(parser.peek(), Some(Token::Name(name)) if name == "let" || name == "verify")
+    {
+        PatternAst::Stmt(parser.stmt()?)
+    } else {
+        let expr = parser.expr()?;
+        parser.expect(";")?;
+        PatternAst::Expression(expr)
+    };
+
+    if !parser.is_at_end() {
+        return Err(parser.error_here("expected the end of the pattern"));
+    }
+
+    Ok(pattern)
+}
+
+/// What a header comment labels: a wire written like the first occurrence of a wire printed by name, as in
+/// `wire::private(row: 1, column: 2)`, coordinates like `wire(row: 1, column: 2)`, or a name given with `name@wire`
+enum Label<'a> {
+    Wire(Ident),
+    Coordinates(WireRef),
+    Name(&'a str),
+}
+
+/// Annotation header comment like `// name = wire::private(row: 1, column: 2): doc [tags: a, b]`
+struct HeaderAnnotation<'a> {
+    name: Option<&'a str>,
+    label: Label<'a>,
+    doc: Option<&'a str>,
+    tags: Vec<&'a str>,
+}
+
+/// Splits an annotation header comment into its parts. Also accepts `// wire(row: 1, column: 2): doc` for unnamed
+/// wires and `// name: doc` for wires named with `name@wire`.
+fn annotation(text: &str) -> Option<HeaderAnnotation<'_>> {
+    let (rest, tags) = match text
+        .strip_suffix(']')
+        .and_then(|rest| rest.rsplit_once(" [tags: "))
+    {
+        Some((rest, tags)) => (rest, tags.split(", ").collect::<Vec<_>>()),
+        None => (text, Vec::new()),
+    };
+
+    let (name, wire) = match rest.split_once(" = ") {
+        Some((name, wire)) if !name.contains(':') => (Some(name), wire),
+        _ => (None, rest),
+    };
+
+    let (label, doc) = if wire.starts_with("wire::") || wire.starts_with("virtual_wire::") {
+        // The wire may have a value with parentheses of its own, like `value: (random!(0) -> 5u64)`
+        let mut depth = 0;
+        let end = wire.char_indices().find_map(|(i, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i + 1);
+                    }
+                }
+                _ => {}
+            }
+            None
+        })?;
+
+        let (tokens, _) = lex(&wire[..end], false).ok()?;
+        let mut parser = Parser {
+            source: &wire[..end],
+            tokens,
+            position: 0,
+            stmt_index: 0,
+            idents: Vec::new(),
+            names: Vec::new(),
+            wires: &BTreeMap::new(),
+        };
+        let ident = parser.ident(false).ok()?;
+        if !parser.is_at_end() || matches!(ident, Ident::String(_)) {
+            return None;
+        }
+
+        (Label::Wire(ident), &wire[end..])
+    } else if wire.starts_with("wire(") || wire.starts_with("virtual_wire(") {
+        let end = wire.find(')')? + 1;
+        (Label::Coordinates(coordinates(&wire[..end])?), &wire[end..])
+    } else {
+        let end = wire.find(':').unwrap_or(wire.len());
+        (Label::Name(&wire[..end]), &wire[end..])
+    };
+
+    let doc = match doc.strip_prefix(':') {
+        Some(doc) => Some(doc.trim()).filter(|doc| !doc.is_empty()),
+        None if doc.is_empty() => None,
+        None => return None,
+    };
+
+    Some(HeaderAnnotation {
+        name,
+        label,
+        doc,
+        tags,
+    })
+}
+
+/// Applies an annotation header comment to the wire it labels. See `annotation`.
+fn annotate(builder: &mut CirBuilder, names: &BTreeMap<String, WireRef>, text: &str) {
+    let Some(HeaderAnnotation {
+        name,
+        label,
+        doc,
+        tags,
+    }) = annotation(text)
+    else {
+        return;
+    };
+
+    let wire_ref = match label {
+        Label::Wire(Ident::Wire(wire)) => wire.wire_ref(),
+        Label::Wire(Ident::VirtualWire(virtual_wire)) => virtual_wire.wire_ref(),
+        Label::Coordinates(wire_ref) => wire_ref,
+        Label::Name(label) => match names.get(label) {
+            Some(wire_ref) => *wire_ref,
+            None => return,
+        },
+        Label::Wire(Ident::String(_)) => return,
+    };
+    let name = name.or(match label {
+        Label::Name(label) => Some(label),
+        _ => None,
+    });
+
+    match wire_ref {
+        WireRef::Wire { row, column } => {
+            if let Some(name) = name {
+                builder.name_wire(row, column, name);
+            }
+            if let Some(doc) = doc {
+                builder.document_wire(row, column, doc);
+            }
+            for tag in tags {
+                builder.tag_wire(row, column, tag);
+            }
+        }
+        WireRef::VirtualWire { index } => {
+            if let Some(name) = name {
+                builder.name_virtual_wire(index, name);
+            }
+            if let Some(doc) = doc {
+                builder.document_virtual_wire(index, doc);
+            }
+            for tag in tags {
+                builder.tag_virtual_wire(index, tag);
+            }
+        }
+    }
+}
+
+/// Header comment like `// config!(num_wires: 10, field: other)`
+fn config(text: &str) -> Option<Config> {
+    let fields = text.strip_prefix("config!(")?.strip_suffix(')')?;
+    let mut builder = CirBuilder::new();
+
+    for field in fields.split(',') {
+        match field.split_once(':')? {
+            (name, num_wires) if name.trim() == "num_wires" => {
+                builder.num_wires(num_wires.trim().parse().ok()?);
+            }
+            (name, field) if name.trim() == "field" => {
+                builder.field(Field::from_name(field.trim())?);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(builder.config)
+}
+
+/// Header comment like `// public!(wire::public(row: 0, column: 1))` declaring an entry of `Cir::public_wire_inputs`
+/// or `Cir::public_virtual_wire_inputs`
+fn public_input(text: &str, wires: &BTreeMap<String, Ident>) -> Option<Ident> {
+    let source = text.strip_prefix("public!(")?.strip_suffix(')')?;
+
+    match expression(source, wires)? {
+        Expression::Ident(ident @ (Ident::Wire(_) | Ident::VirtualWire(_))) => Some(ident),
+        _ => None,
+    }
+}
+
+/// Header comment like `// gadget!(0..4): poseidon`
+fn gadget(text: &str) -> Option<Gadget> {
+    let (range, name) = text.strip_prefix("gadget!(")?.split_once("):")?;
+    let (start, end) = range.split_once("..")?;
+
+    Some(Gadget {
+        name: name.trim().to_string(),
+        start: start.trim().parse().ok()?,
+        end: end.trim().parse().ok()?,
+    })
+}
+
+/// Header comment like `// input!(0): amount = wire(row: 0, column: 1)` declaring a public value of the signature
+fn public_value(text: &str) -> Option<(usize, PublicValue)> {
+    let (direction, rest) = if let Some(rest) = text.strip_prefix("input!(") {
+        (Direction::Input, rest)
+    } else {
+        (Direction::Output, text.strip_prefix("output!(")?)
+    };
+    let (position, rest) = rest.split_once("):")?;
+    let (name, wire) = rest.split_once(" = ")?;
+
+    Some((
+        position.parse().ok()?,
+        PublicValue {
+            wire_ref: coordinates(wire.trim())?,
+            name: name.trim().to_string(),
+            direction,
+        },
+    ))
+}
+
+/// Header comment like `// random!(0): beta [challenge]` declaring a random source
+fn random_source(text: &str) -> Option<(usize, RandomSource)> {
+    let (source, rest) = text.strip_prefix("random!(")?.split_once("):")?;
+    let (name, kind) = rest.trim().strip_suffix(']')?.rsplit_once(" [")?;
+
+    Some((
+        source.parse().ok()?,
+        RandomSource {
+            name: name.to_string(),
+            kind: RandomSourceKind::from_name(kind)?,
+        },
+    ))
+}
+
+/// Header comment like `// absorb!(x)` or `// squeeze!(0)` recording a transcript operation
+fn transcript_op(text: &str, wires: &BTreeMap<String, Ident>) -> Option<TranscriptOp> {
+    if let Some(source) = text
+        .strip_prefix("squeeze!(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return Some(TranscriptOp::Squeeze {
+            source: source.trim().parse().ok()?,
+        });
+    }
+
+    let source = text.strip_prefix("absorb!(")?.strip_suffix(')')?;
+    expression(source, wires).map(TranscriptOp::Absorb)
+}
+
+/// Whole of `source` as an expression
+fn expression(source: &str, wires: &BTreeMap<String, Ident>) -> Option<Expression> {
+    let (tokens, _) = lex(source, false).ok()?;
+
+    let mut parser = Parser {
+        source,
+        tokens,
+        position: 0,
+        stmt_index: 0,
+        idents: Vec::new(),
+        names: Vec::new(),
+        wires,
+    };
+
+    let expr = parser.expr().ok()?;
+    parser.is_at_end().then_some(expr)
+}
+
+/// Whether `name` is lexed as a single name that isn't a keyword, so it can be printed in place of a wire
+pub(crate) fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || "_.[]".contains(c))
+        && !matches!(name, "let" | "verify" | "random")
+}
+
+/// Wire written like `wire(row: 1, column: 2)` or `virtual_wire(index: 3)`
+fn coordinates(label: &str) -> Option<WireRef> {
+    let number = |field: &str, prefix: &str| field.trim().strip_prefix(prefix)?.trim().parse().ok();
+
+    if let Some(fields) = label
+        .strip_prefix("wire(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let (row, column) = fields.split_once(',')?;
+        Some(WireRef::Wire {
+            row: number(row, "row:")?,
+            column: number(column, "column:")?,
+        })
+    } else {
+        let index = label
+            .strip_prefix("virtual_wire(")
+            .and_then(|rest| rest.strip_suffix(')'))?;
+        Some(WireRef::VirtualWire {
+            index: number(index, "index:")?,
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Token {
+    Name(String),
+    Number(u64),
+    Punct(&'static str),
+}
+
+/// Longest first so `==` isn't lexed as two `=`
+const PUNCTUATION: [&str; 20] = [
+    "::", "->", "==", "<=", ">=", "(", ")", ",", ":", ";", "=", "+", "-", "*", "/", "^", "<", ">",
+    "@", "!",
+];
+
+type Comment<'a> = (Span, &'a str);
+
+type Lexed = (Token, Span);
+
+/// `allow_metavariables` also lexes names like `$x` and `$x:ident` for query patterns
+fn lex(
+    source: &str,
+    allow_metavariables: bool,
+) -> Result<(Vec<Lexed>, Vec<Comment<'_>>), ParseError> {
+    let mut tokens = Vec::new();
+    let mut comments = Vec::new();
+    let mut offset = 0;
+
+    while offset < source.len() {
+        let rest = &source[offset..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        if c.is_whitespace() {
+            offset += c.len_utf8();
+        } else if let Some(comment) = rest.strip_prefix("//") {
+            let length = comment.find('\n').unwrap_or(comment.len());
+            comments.push((
+                Span::new(offset, offset + 2 + length),
+                comment[..length].trim(),
+            ));
+            offset += 2 + length;
+        } else if c.is_ascii_digit() {
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let mut end = offset + digits;
+
+            let value = rest[..digits].parse::<u64>().map_err(|_| {
+                error(
+                    source,
+                    Span::new(offset, end),
+                    "number doesn't fit in a u64",
+                )
+            })?;
+
+            if source[end..].starts_with("u64") {
+                end += 3;
+            }
+
+            tokens.push((Token::Number(value), Span::new(offset, end)));
+            offset = end;
+        } else if c.is_alphabetic() || c == '_' || (allow_metavariables && c == '$') {
+            let name_length = |rest: &str| {
+                rest.find(|c: char| !(c.is_alphanumeric() || "_.[]".contains(c)))
+                    .unwrap_or(rest.len())
+            };
+
+            let mut length = c.len_utf8() + name_length(&rest[c.len_utf8()..]);
+
+            // Kind of a metavariable, like the `:ident` in `$x:ident`
+            if c == '$' && rest[length..].starts_with(':') && !rest[length..].starts_with("::") {
+                length += 1 + name_length(&rest[length + 1..]);
+            }
+
+            tokens.push((
+                Token::Name(rest[..length].to_string()),
+                Span::new(offset, offset + length),
+            ));
+            offset += length;
+        } else if let Some(punct) = PUNCTUATION.iter().find(|punct| rest.starts_with(**punct)) {
+            tokens.push((Token::Punct(punct), Span::new(offset, offset + punct.len())));
+            offset += punct.len();
+        } else {
+            return Err(error(
+                source,
+                Span::new(offset, offset + c.len_utf8()),
+                &format!("unexpected character `{c}`"),
+            ));
+        }
+    }
+
+    Ok((tokens, comments))
+}
+
+fn error(source: &str, span: Span, message: &str) -> ParseError {
+    let (line, column) = span.line_column(source);
+
+    ParseError {
+        span,
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Lexed>,
+    position: usize,
+    stmt_index: usize,
+    idents: Vec<IdentOccurrence>,
+
+    /// Names given to wires with `name@wire`, in order of first appearance
+    names: Vec<(String, WireRef)>,
+
+    /// Wires the header says are printed by name alone
+    wires: &'a BTreeMap<String, Ident>,
+}
+
+impl Parser<'_> {
+    fn stmt(&mut self) -> Result<Stmt, ParseError> {
+        if self.eat_name("let") {
+            let ident = self.ident(true)?;
+            self.expect("=")?;
+            let expr = self.expr()?;
+            self.expect(";")?;
+            Ok(Stmt::Local(ident, expr))
+        } else if self.eat_name("verify") {
+            self.expect("!")?;
+            self.expect("(")?;
+            let expr = self.expr()?;
+            self.expect(")")?;
+            self.expect(";")?;
+            Ok(Stmt::Verify(expr))
+        } else {
+            Err(self.error_here("expected `let` or `verify!`"))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expression, ParseError> {
+        let mut lhs = self.additive()?;
+
+        while let Some(binop) = self.eat_binop(&[
+            ("==", BinOp::Equal),
+            ("<=", BinOp::LessThanEqual),
+            (">=", BinOp::GreaterThanEqual),
+            ("<", BinOp::LessThan),
+            (">", BinOp::GreaterThan),
+        ]) {
+            lhs = binary(lhs, binop, self.additive()?);
+        }
+
+        Ok(lhs)
+    }
+
+    fn additive(&mut self) -> Result<Expression, ParseError> {
+        let mut lhs = self.multiplicative()?;
+
+        while let Some(binop) = self.eat_binop(&[("+", BinOp::Add), ("-", BinOp::Subtract)]) {
+            lhs = binary(lhs, binop, self.multiplicative()?);
+        }
+
+        Ok(lhs)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expression, ParseError> {
+        let mut lhs = self.exponent()?;
+
+        while let Some(binop) = self.eat_binop(&[("*", BinOp::Multiply), ("/", BinOp::Divide)]) {
+            lhs = binary(lhs, binop, self.exponent()?);
+        }
+
+        Ok(lhs)
+    }
+
+    /// Right associative
+    fn exponent(&mut self) -> Result<Expression, ParseError> {
+        let base = self.unary()?;
+
+        if self.eat("^") {
+            Ok(binary(base, BinOp::Exponent, self.exponent()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, ParseError> {
+        if self.eat("-") {
+            Ok(Expression::Unary {
+                op: Op::Sub,
+                expr: Box::new(self.unary()?),
+            })
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, ParseError> {
+        if self.at_random_value() {
+            return Ok(Expression::Value(self.value()?));
+        }
+
+        match self.peek() {
+            Some(Token::Number(_)) => Ok(Expression::Value(self.value()?)),
+            Some(Token::Punct("(")) => {
+                self.advance();
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(Token::Name(_)) => Ok(Expression::Ident(self.ident(false)?)),
+            _ => Err(self.error_here("expected an expression")),
+        }
+    }
+
+    /// `5u64`, `random!()` or `(random!() -> 5u64)`, optionally with a random source like `random!(0)`
+    fn value(&mut self) -> Result<Value, ParseError> {
+        if let Some(Token::Number(value)) = self.peek() {
+            let value = *value;
+            self.advance();
+            return Ok(Value::U64(value));
+        }
+
+        let parenthesized = self.eat("(");
+        self.expect_name("random")?;
+        self.expect("!")?;
+        self.expect("(")?;
+        let source = match self.peek() {
+            Some(Token::Number(source)) => {
+                let source = usize::try_from(*source)
+                    .map_err(|_| self.error_here("random source out of range"))?;
+                self.advance();
+                Some(source)
+            }
+            _ => None,
+        };
+        self.expect(")")?;
+
+        if !parenthesized {
+            return Ok(match source {
+                Some(source) => Value::OmittedSample { source },
+                None => Value::Random,
+            });
+        }
+
+        self.expect("->")?;
+        let Some(Token::Number(value)) = self.peek() else {
+            return Err(self.error_here("expected the random value"));
+        };
+        let value = *value;
+        self.advance();
+        self.expect(")")?;
+
+        Ok(match source {
+            Some(source) => Value::Sampled { source, value },
+            None => Value::RandomU64(value),
+        })
+    }
+
+    fn at_random_value(&self) -> bool {
+        let name_at = |offset| {
+            matches!(
+                self.tokens.get(self.position + offset),
+                Some((Token::Name(name), _)) if name == "random"
+            )
+        };
+
+        name_at(0) || (matches!(self.peek(), Some(Token::Punct("("))) && name_at(1))
+    }
+
+    /// A local, wire, virtual wire or named wire like `name@wire::private(row: 1, column: 2)`
+    fn ident(&mut self, is_definition: bool) -> Result<Ident, ParseError> {
+        let start = self.peek_span().start;
+
+        let Some(Token::Name(name)) = self.peek().cloned() else {
+            return Err(self.error_here("expected an identifier"));
+        };
+        self.advance();
+
+        let ident = if self.eat("::") {
+            self.wire(&name)?
+        } else if self.eat("@") {
+            let Some(Token::Name(kind)) = self.peek().cloned() else {
+                return Err(self.error_here("expected a wire after `@`"));
+            };
+            self.advance();
+            self.expect("::")?;
+
+            let ident = self.wire(&kind)?;
+            let wire_ref = match &ident {
+                Ident::Wire(wire) => wire.wire_ref(),
+                Ident::VirtualWire(virtual_wire) => virtual_wire.wire_ref(),
+                Ident::String(_) => unreachable!("`wire` only returns wires"),
+            };
+
+            if !self.names.iter().any(|(existing, _)| *existing == name) {
+                self.names.push((name, wire_ref));
+            }
+
+            ident
+        } else if let Some(wire) = self.wires.get(&name) {
+            wire.clone()
+        } else {
+            Ident::String(name)
+        };
+
+        self.idents.push(IdentOccurrence {
+            ident: ident.clone(),
+            span: Span::new(start, self.previous_span().end),
+            stmt_index: self.stmt_index,
+            is_definition,
+        });
+
+        Ok(ident)
+    }
+
+    /// Rest of `wire::private(row: 1, column: 2, value: 5u64)` or `virtual_wire::public(index: 3)` after `::`
+    fn wire(&mut self, kind: &str) -> Result<Ident, ParseError> {
+        if kind != "wire" && kind != "virtual_wire" {
+            return Err(self.error_previous(&format!(
+                "expected `wire` or `virtual_wire`, found `{kind}`"
+            )));
+        }
+
+        let wiretype = match self.peek() {
+            Some(Token::Name(wiretype)) if wiretype == "public" => Wiretype::Public,
+            Some(Token::Name(wiretype)) if wiretype == "private" => Wiretype::Private,
+            Some(Token::Name(wiretype)) if wiretype == "const" => Wiretype::Constant,
+            _ => return Err(self.error_here("expected `public`, `private` or `const`")),
+        };
+        self.advance();
+
+        self.expect("(")?;
+
+        let mut fields = BTreeMap::new();
+        let mut value = None;
+        loop {
+            let Some(Token::Name(field)) = self.peek().cloned() else {
+                return Err(self.error_here("expected a field name"));
+            };
+            self.advance();
+            self.expect(":")?;
+
+            if field == "value" {
+                value = Some(self.value()?);
+            } else if let Some(Token::Number(number)) = self.peek() {
+                fields.insert(field, *number);
+                self.advance();
+            } else {
+                return Err(self.error_here("expected a number"));
+            }
+
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(")")?;
+
+        let mut field = |name: &str| {
+            let number = fields
+                .remove(name)
+                .ok_or_else(|| self.error_previous(&format!("missing field `{name}`")))?;
+            usize::try_from(number).map_err(|_| self.error_previous("coordinate out of range"))
+        };
+
+        if kind == "wire" {
+            Ok(Ident::Wire(Wire {
+                row: field("row")?,
+                column: field("column")?,
+                value,
+                wiretype,
+            }))
+        } else {
+            Ok(Ident::VirtualWire(VirtualWire {
+                index: field("index")?,
+                value,
+                wiretype,
+            }))
+        }
+    }
+
+    fn eat_binop(&mut self, binops: &[(&str, BinOp)]) -> Option<BinOp> {
+        let binop = binops.iter().find_map(|(punct, binop)| match self.peek() {
+            Some(Token::Punct(token)) if token == punct => Some(*binop),
+            _ => None,
+        })?;
+
+        self.advance();
+        Some(binop)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn peek_span(&self) -> Span {
+        self.tokens.get(self.position).map_or_else(
+            || Span::new(self.source.len(), self.source.len()),
+            |(_, span)| *span,
+        )
+    }
+
+    fn previous_span(&self) -> Span {
+        self.tokens
+            .get(self.position.wrapping_sub(1))
+            .map_or(Span::default(), |(_, span)| *span)
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    fn advance(&mut self) {
+        self.position += 1;
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(token)) if *token == punct) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_name(&mut self, name: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Name(token)) if token == name) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), ParseError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.error_here(&format!("expected `{punct}`")))
+        }
+    }
+
+    fn expect_name(&mut self, name: &str) -> Result<(), ParseError> {
+        if self.eat_name(name) {
+            Ok(())
+        } else {
+            Err(self.error_here(&format!("expected `{name}`")))
+        }
+    }
+
+    fn error_here(&self, message: &str) -> ParseError {
+        error(self.source, self.peek_span(), message)
+    }
+
+    fn error_previous(&self, message: &str) -> ParseError {
+        error(self.source, self.previous_span(), message)
+    }
+}
+
+fn binary(lhs: Expression, binop: BinOp, rhs: Expression) -> Expression {
+    Expression::BinaryOperator {
+        lhs: Box::new(lhs),
+        binop,
+        rhs: Box::new(rhs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Node,
+        test_util::{binop, test_code_ir},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                Ident::Wire(Wire::new_private(12, 3)),
+                binop(
+                    Wire::new_private(4, 3),
+                    BinOp::Subtract,
+                    Expression::Unary {
+                        op: Op::Sub,
+                        expr: Box::new(binop(
+                            VirtualWire::new_public(1),
+                            BinOp::Exponent,
+                            Expression::Value(Value::U64(3)),
+                        )),
+                    },
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                binop(
+                    Expression::Ident("x".into()),
+                    BinOp::Multiply,
+                    Expression::Value(Value::RandomU64(7)),
+                ),
+                BinOp::LessThanEqual,
+                Wire {
+                    row: 1,
+                    column: 2,
+                    value: Some(Value::U64(5)),
+                    wiretype: Wiretype::Constant,
+                },
+            )))
+            .name_wire(12, 3, "balance_after")
+            .document_wire(12, 3, "balance after the transfer")
+            .tag_wire(12, 3, "balance")
+            .name_virtual_wire(1, "amount")
+            .tag_virtual_wire(1, "input")
+            .build();
+
+        let source = cir.to_code_ir_with_coordinates();
+        let parsed = parse(&source).unwrap();
+
+        assert_eq!(parsed.cir, cir);
+        assert_eq!(parsed.cir.to_code_ir_with_coordinates(), source);
+
+        // Without coordinates the header records which wire each name stands for
+        let parsed = parse(&cir.to_code_ir()).unwrap();
+        assert_eq!(parsed.cir, cir);
+        assert_eq!(
+            parsed.idents[0].ident,
+            Ident::Wire(Wire::new_private(12, 3))
+        );
+    }
+
+    #[test]
+    fn test_round_trip_header() {
+        let mut builder = CirBuilder::new();
+        builder
+            .num_wires(10)
+            .field(Field::Other)
+            .begin_gadget("hash")
+            .add_stmt(Stmt::Local(
+                Ident::Wire(Wire::new_private(0, 0)),
+                binop(
+                    Wire::new_public(1, 0),
+                    BinOp::Add,
+                    Expression::Ident("x".into()),
+                ),
+            ))
+            .begin_gadget("inner")
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_public(1, 0),
+                BinOp::Equal,
+                // Same wire as the first statement's, but with a value, so it can't be printed by name alone
+                Wire {
+                    row: 0,
+                    column: 0,
+                    value: Some(Value::U64(3)),
+                    wiretype: Wiretype::Private,
+                },
+            )))
+            .end_gadget()
+            .end_gadget()
+            .add_stmt(Stmt::Verify(binop(
+                VirtualWire::new_public(4),
+                BinOp::Equal,
+                Wire::new_private(2, 0),
+            )))
+            .name_wire(0, 0, "sum")
+            .name_wire(1, 0, "in")
+            // Taken by a local
+            .name_wire(2, 0, "x")
+            .name_virtual_wire(4, "not an identifier")
+            .document_virtual_wire(4, "output")
+            .public_input(WireRef::Wire { row: 1, column: 0 }, "in")
+            .public_output(WireRef::VirtualWire { index: 4 }, "out");
+        builder.public_wire_inputs.push(Wire {
+            row: 1,
+            column: 0,
+            value: Some(Value::U64(5)),
+            wiretype: Wiretype::Public,
+        });
+        builder
+            .public_virtual_wire_inputs
+            .push(VirtualWire::new_public(4));
+        builder.absorb(Wire::new_public(1, 0));
+        builder.squeeze("alpha");
+        let cir = builder.build();
+
+        let source = cir.to_code_ir();
+        assert_eq!(parse(&source).unwrap().cir, cir);
+        assert_eq!(parse(&cir.to_code_ir_with_coordinates()).unwrap().cir, cir);
+
+        test_code_ir("parse_round_trip_header", &source);
+    }
+
+    #[test]
+    fn test_random_sources() {
+        let mut builder = CirBuilder::new();
+        let beta = builder.register_random_source("beta", RandomSourceKind::Challenge);
+        let blinding = builder.register_random_source("blinding", RandomSourceKind::Blinding);
+        let cir = builder
+            .add_stmt(Stmt::Verify(binop(
+                Expression::Value(Value::Sampled {
+                    source: beta,
+                    value: 7,
+                }),
+                BinOp::Equal,
+                Wire {
+                    row: 0,
+                    column: 0,
+                    value: Some(Value::OmittedSample { source: blinding }),
+                    wiretype: Wiretype::Private,
+                },
+            )))
+            .build();
+
+        let parsed = parse(&cir.to_code_ir()).unwrap().cir;
+        assert_eq!(parsed.random_sources, cir.random_sources);
+        assert_eq!(parsed.stmts, cir.stmts);
+    }
+
+    #[test]
+    fn test_signature() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                Ident::VirtualWire(VirtualWire::new_public(2)),
+                binop(
+                    Wire::new_public(0, 1),
+                    BinOp::Multiply,
+                    Wire::new_private(0, 2),
+                ),
+            ))
+            .public_input(WireRef::Wire { row: 0, column: 1 }, "amount")
+            .public_output(WireRef::VirtualWire { index: 2 }, "total")
+            .build();
+
+        let parsed = parse(&cir.to_code_ir()).unwrap().cir;
+        assert_eq!(parsed.signature, cir.signature);
+    }
+
+    #[test]
+    fn test_transcript() {
+        let mut builder = CirBuilder::new();
+        builder.absorb(Wire::new_public(0, 0)).absorb(binop(
+            Wire::new_private(0, 1),
+            BinOp::Add,
+            Expression::Value(Value::U64(1)),
+        ));
+        builder.squeeze("alpha");
+        let cir = builder.build();
+
+        let parsed = parse(&cir.to_code_ir()).unwrap().cir;
+        assert_eq!(parsed.random_sources, cir.random_sources);
+        assert_eq!(parsed.transcript, cir.transcript);
+    }
+
+    #[test]
+    fn test_precedence() {
+        let parsed = parse("verify!(a + b * c ^ 2 ^ 3 == -d);").unwrap();
+
+        assert_eq!(
+            parsed.cir.stmts[0].to_code_ir(),
+            "verify!((a + (b * (c ^ (2u64 ^ 3u64)))) == -d);"
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let source =
+            "// header\n\nlet x = wire::private(row: 0, column: 1);\n\nverify!(x == 2u64);";
+        let parsed = parse(source).unwrap();
+
+        assert_eq!(parsed.stmt_spans.len(), 2);
+        assert_eq!(
+            &source[parsed.stmt_spans[1].start..parsed.stmt_spans[1].end],
+            "verify!(x == 2u64);"
+        );
+
+        let definition = &parsed.idents[0];
+        assert_eq!(definition.ident, Ident::String("x".into()));
+        assert!(definition.is_definition);
+
+        let offset = source.find("row").unwrap();
+        let wire = parsed.ident_at(offset).unwrap();
+        assert_eq!(
+            &source[wire.span.start..wire.span.end],
+            "wire::private(row: 0, column: 1)"
+        );
+        assert_eq!(parsed.stmt_at(offset), Some(0));
+    }
+
+    #[test]
+    fn test_tokens() {
+        let source = "// x\nlet x = wire::private(row: 0, column: 1) + 2u64;";
+
+        let kinds = tokens(source)
+            .unwrap()
+            .into_iter()
+            .map(|(span, kind)| (&source[span.start..span.end], kind))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds[..8],
+            [
+                ("// x", TokenKind::Comment),
+                ("let", TokenKind::Keyword),
+                ("x", TokenKind::Name),
+                ("=", TokenKind::Punctuation),
+                ("wire", TokenKind::Keyword),
+                ("::", TokenKind::Punctuation),
+                ("private", TokenKind::Keyword),
+                ("(", TokenKind::Punctuation),
+            ]
+        );
+        assert_eq!(kinds[8], ("row", TokenKind::Label));
+        assert_eq!(kinds[kinds.len() - 2], ("2u64", TokenKind::Number));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = parse("let x = 1u64;\nverify!(x == );").unwrap_err();
+
+        assert_eq!((error.line, error.column), (2, 14));
+        assert_eq!(error.to_string(), "2:14: expected an expression");
+
+        let error = parse(
+            "// output!(0): out = virtual_wire(index: 0)\n// input!(1): secret = wire(row: 0, column: 1)\n\n\
+             verify!(virtual_wire::public(index: 0) == wire::private(row: 0, column: 1));",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "2:1: public input `secret` is `wire(row: 0, column: 1)`, which the circuit uses as private"
+        );
+    }
+}
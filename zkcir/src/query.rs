@@ -0,0 +1,309 @@
+//! Structural search over statements, with patterns written like `.cir` code
+//!
+//! A pattern is a statement like `verify!($x * ($x - 1) == 0)`, matching whole statements, or an expression like
+//! `$a / $b`, matching any statement containing it. Metavariables start with `$` and match any expression, or with a
+//! kind like `$x:ident` only:
+//!
+//! - `expr`: any expression, the default
+//! - `ident`: a wire, virtual wire or local
+//! - `wire`: a wire or virtual wire
+//! - `local`: a local
+//! - `value`: a literal value
+//!
+//! Every occurrence of a metavariable must match the same expression, except `$_` which matches anything each time.
+//! Wires match by coordinates regardless of their value, `random!()` matches any random value, `random!(0)` any value
+//! from random source 0, and the operands of `+`, `*` and `==` match in either order.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::analysis::Var;
+use crate::ast::{BinOp, Expression, Ident, Stmt, Value};
+use crate::ir::Cir;
+use crate::node::Node;
+use crate::parse::{line_column, parse_pattern, ParseError, PatternAst, Span};
+
+/// Expression each metavariable matched, keyed by name without the `$`
+pub type Bindings = BTreeMap<String, Expression>;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Kind {
+    Expression,
+    Ident,
+    Wire,
+    Local,
+    Value,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Pattern {
+    ast: PatternAst,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Match {
+    pub stmt_index: usize,
+    pub bindings: Bindings,
+}
+
+impl Pattern {
+    /// # Errors
+    ///
+    /// Errors on syntax errors and unknown metavariable kinds
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let ast = parse_pattern(source)?;
+
+        let mut stmt = match &ast {
+            PatternAst::Stmt(stmt) => stmt.clone(),
+            PatternAst::Expression(expr) => Stmt::Verify(expr.clone()),
+        };
+
+        let mut unknown = None;
+        stmt.visit_idents_mut(&mut |ident| {
+            if let Ident::String(name) = ident {
+                if name.starts_with('$') && metavariable(name).is_none() && unknown.is_none() {
+                    unknown = Some(name.clone());
+                }
+            }
+            ident.clone()
+        });
+
+        if let Some(name) = unknown {
+            let start = source.find(&name).unwrap_or_default();
+            let (line, column) = line_column(source, start);
+            let kind = name.split_once(':').map_or("", |(_, kind)| kind);
+
+            return Err(ParseError {
+                span: Span::new(start, start + name.len()),
+                line,
+                column,
+                message: format!(
+                    "unknown metavariable kind `{kind}`, expected `expr`, `ident`, `wire`, `local` or `value`"
+                ),
+            });
+        }
+
+        Ok(Self { ast })
+    }
+
+    /// Bindings of the first match in `stmt`. Expression patterns are tried on every subexpression, outermost first.
+    #[must_use]
+    pub fn matches(&self, stmt: &Stmt) -> Option<Bindings> {
+        let mut bindings = Bindings::new();
+
+        match (&self.ast, stmt) {
+            (PatternAst::Stmt(Stmt::Verify(pattern)), Stmt::Verify(expr)) => {
+                match_expr(pattern, expr, &mut bindings).then_some(bindings)
+            }
+            (
+                PatternAst::Stmt(Stmt::Local(pattern_ident, pattern_expr)),
+                Stmt::Local(ident, expr),
+            ) => (match_expr(
+                &Expression::Ident(pattern_ident.clone()),
+                &Expression::Ident(ident.clone()),
+                &mut bindings,
+            ) && match_expr(pattern_expr, expr, &mut bindings))
+            .then_some(bindings),
+            (PatternAst::Stmt(_), _) => None,
+            (PatternAst::Expression(pattern), Stmt::Local(ident, expr)) => {
+                find(pattern, &Expression::Ident(ident.clone())).or_else(|| find(pattern, expr))
+            }
+            (PatternAst::Expression(pattern), Stmt::Verify(expr)) => find(pattern, expr),
+        }
+    }
+}
+
+/// Every statement of `cir` matching `pattern`, in order
+#[must_use]
+pub fn query(cir: &Cir, pattern: &Pattern) -> Vec<Match> {
+    cir.stmts
+        .iter()
+        .enumerate()
+        .filter_map(|(stmt_index, stmt)| {
+            pattern.matches(stmt).map(|bindings| Match {
+                stmt_index,
+                bindings,
+            })
+        })
+        .collect()
+}
+
+/// First match of `pattern` in `expr` or its subexpressions
+fn find(pattern: &Expression, expr: &Expression) -> Option<Bindings> {
+    let mut bindings = Bindings::new();
+    if match_expr(pattern, expr, &mut bindings) {
+        return Some(bindings);
+    }
+
+    match expr {
+        Expression::BinaryOperator { lhs, rhs, .. } => {
+            find(pattern, lhs).or_else(|| find(pattern, rhs))
+        }
+        Expression::Unary { expr, .. } => find(pattern, expr),
+        Expression::Ident(_) | Expression::Value(_) => None,
+    }
+}
+
+/// Whether `expr` matches `pattern`, adding to `bindings` only on success
+fn match_expr(pattern: &Expression, expr: &Expression, bindings: &mut Bindings) -> bool {
+    match pattern {
+        Expression::Ident(Ident::String(name)) if name.starts_with('$') => {
+            bind(name, expr, bindings)
+        }
+        Expression::Ident(pattern) => {
+            matches!(expr, Expression::Ident(ident) if Var::from(pattern) == Var::from(ident))
+        }
+        Expression::Value(Value::Random) => {
+            matches!(expr, Expression::Value(value) if value.is_random())
+        }
+        Expression::Value(Value::OmittedSample { source: pattern }) => matches!(
+            expr,
+            Expression::Value(
+                Value::Sampled { source, .. } | Value::OmittedSample { source }
+            ) if source == pattern
+        ),
+        Expression::Value(pattern) => matches!(expr, Expression::Value(value) if value == pattern),
+        Expression::Unary {
+            op: pattern_op,
+            expr: pattern,
+        } => match expr {
+            Expression::Unary { op, expr } if op == pattern_op => {
+                match_expr(pattern, expr, bindings)
+            }
+            _ => false,
+        },
+        Expression::BinaryOperator {
+            lhs: pattern_lhs,
+            binop: pattern_binop,
+            rhs: pattern_rhs,
+        } => {
+            let Expression::BinaryOperator { lhs, binop, rhs } = expr else {
+                return false;
+            };
+            if binop != pattern_binop {
+                return false;
+            }
+
+            let commutative = matches!(binop, BinOp::Add | BinOp::Multiply | BinOp::Equal);
+            let orders = [(lhs, rhs), (rhs, lhs)];
+
+            orders
+                .iter()
+                .take(if commutative { 2 } else { 1 })
+                .any(|(lhs, rhs)| {
+                    let mut attempt = bindings.clone();
+                    let matched = match_expr(pattern_lhs, lhs, &mut attempt)
+                        && match_expr(pattern_rhs, rhs, &mut attempt);
+
+                    if matched {
+                        *bindings = attempt;
+                    }
+                    matched
+                })
+        }
+    }
+}
+
+fn bind(name: &str, expr: &Expression, bindings: &mut Bindings) -> bool {
+    let Some((name, kind)) = metavariable(name) else {
+        return false;
+    };
+
+    let kind_matches = match kind {
+        Kind::Expression => true,
+        Kind::Ident => matches!(expr, Expression::Ident(_)),
+        Kind::Wire => matches!(
+            expr,
+            Expression::Ident(Ident::Wire(_) | Ident::VirtualWire(_))
+        ),
+        Kind::Local => matches!(expr, Expression::Ident(Ident::String(_))),
+        Kind::Value => matches!(expr, Expression::Value(_)),
+    };
+
+    if !kind_matches {
+        return false;
+    }
+
+    if name == "_" {
+        return true;
+    }
+
+    if let Some(bound) = bindings.get(name) {
+        return match_expr(bound, expr, &mut Bindings::new());
+    }
+
+    bindings.insert(name.to_string(), expr.clone());
+    true
+}
+
+/// Name without the `$` and kind of a metavariable like `$x:ident`
+fn metavariable(name: &str) -> Option<(&str, Kind)> {
+    let name = name.strip_prefix('$')?;
+
+    let (name, kind) = match name.split_once(':') {
+        Some((name, "expr")) => (name, Kind::Expression),
+        Some((name, "ident")) => (name, Kind::Ident),
+        Some((name, "wire")) => (name, Kind::Wire),
+        Some((name, "local")) => (name, Kind::Local),
+        Some((name, "value")) => (name, Kind::Value),
+        Some(_) => return None,
+        None => (name, Kind::Expression),
+    };
+
+    Some((name, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse::parse, test_util::test_code_ir};
+
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let source = "let bit = wire::private(row: 0, column: 0);
+
+verify!((bit * (bit - 1u64)) == 0u64);
+
+verify!(0u64 == ((wire::private(row: 0, column: 1) - 1u64) * wire::private(row: 0, column: 1)));
+
+verify!((bit * (wire::private(row: 0, column: 1) - 1u64)) == 0u64);
+
+let ratio = (bit + 2u64) / wire::private(row: 0, column: 2);";
+        let cir = parse(source).unwrap().cir;
+
+        let boolean = Pattern::parse("verify!($x * ($x - 1) == 0)").unwrap();
+        let division = Pattern::parse("$a / $b:wire").unwrap();
+
+        let report = query(&cir, &boolean)
+            .into_iter()
+            .chain(query(&cir, &division))
+            .map(|found| {
+                let bindings = found
+                    .bindings
+                    .iter()
+                    .map(|(name, expr)| format!("${name} = {}", expr.to_code_ir()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("{}: {bindings}", found.stmt_index)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        test_code_ir("query", &report);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = Pattern::parse("verify!($x:number == 0)").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 9));
+
+        assert!(Pattern::parse("verify!($x == 0) extra").is_err());
+        assert!(Pattern::parse("$x == 0;").is_ok());
+    }
+}
@@ -0,0 +1,682 @@
+//! Lowering of a `Cir` to a rank-1 constraint system, where every constraint has the form `A * B = C` for linear
+//! combinations `A`, `B` and `C` of signals
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_more::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::eval::{check_field, constant_value, free_vars, Fp, GOLDILOCKS_ORDER};
+use crate::analysis::range::constant;
+use crate::analysis::{public_values, Var};
+use crate::ast::{BinOp, Expression, Ident, Op, Stmt, Value, WireRef};
+use crate::ir::{Cir, Direction, Field};
+
+/// Size in bytes of a field element in the iden3 format
+const FIELD_SIZE: u32 = 8;
+
+/// What a signal of the constraint system stands for
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Signal {
+    /// Always `1`, at index 0 so constants can be written as multiples of it
+    One,
+    Wire(WireRef),
+
+    /// Auxiliary variable introduced to lower statement `stmt_index`, like a product or an inverse
+    Aux {
+        stmt_index: usize,
+    },
+}
+
+/// Sum of signals scaled by coefficients, keyed by signal index
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LinearCombination(pub BTreeMap<usize, Fp>);
+
+impl LinearCombination {
+    fn constant(value: Fp) -> Self {
+        Self::term(0, value)
+    }
+
+    fn signal(index: usize) -> Self {
+        Self::term(index, Fp::ONE)
+    }
+
+    fn term(index: usize, coefficient: Fp) -> Self {
+        let mut terms = BTreeMap::new();
+        if coefficient != Fp::ZERO {
+            terms.insert(index, coefficient);
+        }
+        Self(terms)
+    }
+
+    /// Value if this only has a constant term
+    fn as_constant(&self) -> Option<Fp> {
+        match self.0.iter().next() {
+            None => Some(Fp::ZERO),
+            Some((0, &value)) if self.0.len() == 1 => Some(value),
+            Some(_) => None,
+        }
+    }
+
+    fn add(mut self, other: &LinearCombination) -> Self {
+        for (&index, &coefficient) in &other.0 {
+            let sum = self.0.get(&index).copied().unwrap_or_default() + coefficient;
+
+            if sum == Fp::ZERO {
+                self.0.remove(&index);
+            } else {
+                self.0.insert(index, sum);
+            }
+        }
+        self
+    }
+
+    fn sub(self, other: &LinearCombination) -> Self {
+        self.add(&other.scale(-Fp::ONE))
+    }
+
+    fn scale(&self, factor: Fp) -> Self {
+        if factor == Fp::ZERO {
+            return Self::default();
+        }
+
+        Self(
+            self.0
+                .iter()
+                .map(|(&index, &coefficient)| (index, coefficient * factor))
+                .collect(),
+        )
+    }
+
+    /// Value under `witness`, indexed by signal
+    #[must_use]
+    pub fn evaluate(&self, witness: &[Fp]) -> Fp {
+        self.0.iter().fold(Fp::ZERO, |sum, (&index, &coefficient)| {
+            sum + coefficient * witness[index]
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Constraint {
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination,
+}
+
+impl Constraint {
+    #[must_use]
+    pub fn is_satisfied(&self, witness: &[Fp]) -> bool {
+        self.a.evaluate(witness) * self.b.evaluate(witness) == self.c.evaluate(witness)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Display)]
+pub enum R1csError {
+    #[display(fmt = "stmt {stmt_index} uses {operation}, which can't be expressed in R1CS")]
+    Unsupported {
+        stmt_index: usize,
+        operation: String,
+    },
+
+    #[display(fmt = "stmt {stmt_index} divides by zero")]
+    DivisionByZero { stmt_index: usize },
+
+    #[display(fmt = "stmt {stmt_index} reads local `{name}` before it is assigned")]
+    UnassignedLocal { stmt_index: usize, name: String },
+
+    /// Coefficients are reduced mod Goldilocks, so lowering a circuit over another field would silently change it
+    #[display(fmt = "circuit is over {_0}, but R1CS lowering only supports Goldilocks")]
+    UnsupportedField(Field),
+}
+
+/// Rank-1 constraint system over the Goldilocks field. Signals are ordered like the iden3 format expects: `One`, then
+/// the outputs of the signature, then public inputs in declaration order, then private inputs the circuit reads before
+/// assigning, then the remaining wires in order of appearance, then auxiliary variables interleaved as they are
+/// introduced.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct R1cs {
+    pub prime: u64,
+    pub signals: Vec<Signal>,
+
+    /// Public values declared as outputs in `Cir::signature`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub num_public_outputs: usize,
+    pub num_public_inputs: usize,
+    pub num_private_inputs: usize,
+    pub constraints: Vec<Constraint>,
+}
+
+impl R1cs {
+    /// Lowers every statement of `cir`. Locals assigned to names are substituted into the statements reading them,
+    /// products of two non-constant terms get an auxiliary variable unless they are the single product of a
+    /// `verify!`, divisions by non-constants get an auxiliary inverse, and constant exponents are expanded into
+    /// multiplications. `verify!(x)` on an expression that isn't an equality constrains `x` to be nonzero.
+    ///
+    /// # Errors
+    ///
+    /// Errors on circuits not over Goldilocks, comparisons, equalities other than a `verify!` of two sides,
+    /// non-constant exponents, omitted random values, divisions by constant zero and locals read before they are
+    /// assigned
+    pub fn new(cir: &Cir) -> Result<Self, R1csError> {
+        check_field(cir).map_err(|_| R1csError::UnsupportedField(cir.config.field()))?;
+
+        let mut lowering = Lowering {
+            signals: Vec::from([Signal::One]),
+            wire_indices: BTreeMap::new(),
+            locals: BTreeMap::new(),
+            constraints: Vec::new(),
+            stmt_index: 0,
+        };
+
+        // iden3 orders public outputs before public inputs
+        let (outputs, inputs): (Vec<_>, Vec<_>) =
+            public_values(cir).into_iter().partition(|wire_ref| {
+                cir.signature.iter().any(|public_value| {
+                    public_value.wire_ref == *wire_ref
+                        && public_value.direction == Direction::Output
+                })
+            });
+        for &wire_ref in outputs.iter().chain(&inputs) {
+            lowering.wire(wire_ref);
+        }
+
+        let private = free_vars(cir)
+            .into_iter()
+            .filter_map(|var| match var {
+                Var::Wire(wire_ref)
+                    if !outputs.contains(&wire_ref) && !inputs.contains(&wire_ref) =>
+                {
+                    Some(wire_ref)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for &wire_ref in &private {
+            lowering.wire(wire_ref);
+        }
+
+        for (stmt_index, stmt) in cir.stmts.iter().enumerate() {
+            lowering.stmt_index = stmt_index;
+            lowering.stmt(stmt)?;
+        }
+
+        Ok(Self {
+            prime: GOLDILOCKS_ORDER,
+            signals: lowering.signals,
+            num_public_outputs: outputs.len(),
+            num_public_inputs: inputs.len(),
+            num_private_inputs: private.len(),
+            constraints: lowering.constraints,
+        })
+    }
+
+    /// Whether `witness`, indexed by signal, satisfies every constraint
+    #[must_use]
+    pub fn is_satisfied(&self, witness: &[Fp]) -> bool {
+        witness.len() == self.signals.len()
+            && self
+                .constraints
+                .iter()
+                .all(|constraint| constraint.is_satisfied(witness))
+    }
+
+    /// # Errors
+    ///
+    /// Errors from `serde_json::to_string_pretty`
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, &'static str> {
+        serde_json::to_string_pretty(&self).map_err(|_| "Failed serializing to json")
+    }
+
+    /// Binary `.r1cs` file in the iden3 format read by circom tooling and snarkjs. Every signal is its own label.
+    ///
+    /// # Errors
+    ///
+    /// Errors if there are more signals or constraints than the format can count
+    pub fn to_iden3_bytes(&self) -> Result<Vec<u8>, &'static str> {
+        let count =
+            |n: usize| u32::try_from(n).map_err(|_| "Too many signals or constraints for r1cs");
+
+        let mut header = Vec::new();
+        header.extend(FIELD_SIZE.to_le_bytes());
+        header.extend(self.prime.to_le_bytes());
+        header.extend(count(self.signals.len())?.to_le_bytes());
+        header.extend(count(self.num_public_outputs)?.to_le_bytes());
+        header.extend(count(self.num_public_inputs)?.to_le_bytes());
+        header.extend(count(self.num_private_inputs)?.to_le_bytes());
+        header.extend((self.signals.len() as u64).to_le_bytes());
+        header.extend(count(self.constraints.len())?.to_le_bytes());
+
+        let mut constraints = Vec::new();
+        for constraint in &self.constraints {
+            for lc in [&constraint.a, &constraint.b, &constraint.c] {
+                constraints.extend(count(lc.0.len())?.to_le_bytes());
+
+                for (&index, coefficient) in &lc.0 {
+                    constraints.extend(count(index)?.to_le_bytes());
+                    constraints.extend(coefficient.value().to_le_bytes());
+                }
+            }
+        }
+
+        let labels = (0..self.signals.len() as u64)
+            .flat_map(u64::to_le_bytes)
+            .collect::<Vec<_>>();
+
+        let mut bytes = Vec::new();
+        bytes.extend(b"r1cs");
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(3u32.to_le_bytes());
+
+        for (section_type, section) in [(1u32, header), (2, constraints), (3, labels)] {
+            bytes.extend(section_type.to_le_bytes());
+            bytes.extend((section.len() as u64).to_le_bytes());
+            bytes.extend(section);
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// Expression of the form `product.0 * product.1 + linear`
+struct Quadratic {
+    product: Option<(LinearCombination, LinearCombination)>,
+    linear: LinearCombination,
+}
+
+struct Lowering {
+    signals: Vec<Signal>,
+    wire_indices: BTreeMap<WireRef, usize>,
+    locals: BTreeMap<String, LinearCombination>,
+    constraints: Vec<Constraint>,
+    stmt_index: usize,
+}
+
+impl Lowering {
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), R1csError> {
+        match stmt {
+            Stmt::Local(Ident::String(name), expr) => {
+                let lc = self.linear(expr)?;
+                self.locals.insert(name.clone(), lc);
+            }
+            Stmt::Local(ident, expr) => {
+                let target = self.ident(ident)?;
+                let quadratic = self.quadratic(expr)?;
+                self.constrain_equal(quadratic, Quadratic::linear(target));
+            }
+            Stmt::Verify(Expression::BinaryOperator {
+                lhs,
+                binop: BinOp::Equal,
+                rhs,
+            }) => {
+                let (lhs, rhs) = (self.quadratic(lhs)?, self.quadratic(rhs)?);
+                self.constrain_equal(lhs, rhs);
+            }
+            Stmt::Verify(expr) => {
+                let lc = self.linear(expr)?;
+
+                match lc.as_constant() {
+                    Some(value) if value != Fp::ZERO => {}
+                    // Unsatisfiable, like the statement itself
+                    Some(_) => self.constrain(
+                        LinearCombination::default(),
+                        LinearCombination::constant(Fp::ONE),
+                        LinearCombination::constant(Fp::ONE),
+                    ),
+                    None => {
+                        self.inverse(lc);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds `lhs == rhs` as one constraint, materializing a product first if both sides have one
+    fn constrain_equal(&mut self, lhs: Quadratic, rhs: Quadratic) {
+        let (lhs, rhs) = match (&lhs.product, &rhs.product) {
+            (Some(_), Some(_)) => {
+                let rhs = self.materialize(rhs);
+                (lhs, Quadratic::linear(rhs))
+            }
+            (None, Some(_)) => (rhs, lhs),
+            _ => (lhs, rhs),
+        };
+
+        match lhs.product {
+            Some((a, b)) => self.constrain(a, b, rhs.linear.sub(&lhs.linear)),
+            None => self.constrain(
+                lhs.linear.sub(&rhs.linear),
+                LinearCombination::constant(Fp::ONE),
+                LinearCombination::default(),
+            ),
+        }
+    }
+
+    fn constrain(&mut self, a: LinearCombination, b: LinearCombination, c: LinearCombination) {
+        self.constraints.push(Constraint { a, b, c });
+    }
+
+    fn quadratic(&mut self, expr: &Expression) -> Result<Quadratic, R1csError> {
+        Ok(match expr {
+            Expression::BinaryOperator {
+                lhs,
+                binop: BinOp::Multiply,
+                rhs,
+            } => {
+                let (lhs, rhs) = (self.linear(lhs)?, self.linear(rhs)?);
+
+                if lhs.as_constant().is_some() || rhs.as_constant().is_some() {
+                    Quadratic::linear(self.multiply(lhs, rhs))
+                } else {
+                    Quadratic {
+                        product: Some((lhs, rhs)),
+                        linear: LinearCombination::default(),
+                    }
+                }
+            }
+            Expression::BinaryOperator {
+                lhs,
+                binop: binop @ (BinOp::Add | BinOp::Subtract),
+                rhs,
+            } => {
+                let lhs = self.quadratic(lhs)?;
+                let mut rhs = self.quadratic(rhs)?;
+
+                if *binop == BinOp::Subtract {
+                    rhs = rhs.negate();
+                }
+
+                match (lhs.product, rhs.product) {
+                    (Some(lhs_product), Some(rhs_product)) => {
+                        let rhs = self.materialize(Quadratic {
+                            product: Some(rhs_product),
+                            linear: rhs.linear,
+                        });
+
+                        Quadratic {
+                            product: Some(lhs_product),
+                            linear: lhs.linear.add(&rhs),
+                        }
+                    }
+                    (product, None) | (None, product) => Quadratic {
+                        product,
+                        linear: lhs.linear.add(&rhs.linear),
+                    },
+                }
+            }
+            Expression::Unary { op: Op::Sub, expr } => self.quadratic(expr)?.negate(),
+            expr => Quadratic::linear(self.linear(expr)?),
+        })
+    }
+
+    fn materialize(&mut self, quadratic: Quadratic) -> LinearCombination {
+        match quadratic.product {
+            Some((a, b)) => self.multiply(a, b).add(&quadratic.linear),
+            None => quadratic.linear,
+        }
+    }
+
+    /// Lowers `expr` to a linear combination, introducing auxiliary variables for every nonlinear operation
+    fn linear(&mut self, expr: &Expression) -> Result<LinearCombination, R1csError> {
+        Ok(match expr {
+            Expression::Value(value) => LinearCombination::constant(self.value(*value)?),
+            Expression::Ident(ident) => self.ident(ident)?,
+            Expression::Unary { op: Op::Sub, expr } => self.linear(expr)?.scale(-Fp::ONE),
+            Expression::BinaryOperator { lhs, binop, rhs } => match binop {
+                BinOp::Add => self.linear(lhs)?.add(&self.linear(rhs)?),
+                BinOp::Subtract => self.linear(lhs)?.sub(&self.linear(rhs)?),
+                BinOp::Multiply => {
+                    let (lhs, rhs) = (self.linear(lhs)?, self.linear(rhs)?);
+                    self.multiply(lhs, rhs)
+                }
+                BinOp::Divide => {
+                    let (lhs, rhs) = (self.linear(lhs)?, self.linear(rhs)?);
+
+                    if let Some(divisor) = rhs.as_constant() {
+                        lhs.scale(divisor.inverse().ok_or(R1csError::DivisionByZero {
+                            stmt_index: self.stmt_index,
+                        })?)
+                    } else {
+                        let inverse = self.inverse(rhs);
+                        self.multiply(lhs, inverse)
+                    }
+                }
+                BinOp::Exponent => {
+                    let Some(exponent) = constant(rhs) else {
+                        return Err(self.unsupported("a non-constant exponent"));
+                    };
+
+                    let base = self.linear(lhs)?;
+                    self.pow(base, exponent)
+                }
+                BinOp::Equal
+                | BinOp::LessThan
+                | BinOp::LessThanEqual
+                | BinOp::GreaterThan
+                | BinOp::GreaterThanEqual => return Err(self.unsupported(&format!("`{binop}`"))),
+            },
+        })
+    }
+
+    fn multiply(&mut self, lhs: LinearCombination, rhs: LinearCombination) -> LinearCombination {
+        if let Some(factor) = lhs.as_constant() {
+            return rhs.scale(factor);
+        }
+        if let Some(factor) = rhs.as_constant() {
+            return lhs.scale(factor);
+        }
+
+        let product = self.aux();
+        self.constrain(lhs, rhs, product.clone());
+        product
+    }
+
+    /// Square and multiply
+    fn pow(&mut self, base: LinearCombination, mut exponent: u64) -> LinearCombination {
+        if let Some(base) = base.as_constant() {
+            return LinearCombination::constant(base.pow(exponent));
+        }
+
+        let (mut base, mut result) = (base, LinearCombination::constant(Fp::ONE));
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = self.multiply(result, base.clone());
+            }
+
+            exponent >>= 1;
+            if exponent > 0 {
+                base = self.multiply(base.clone(), base);
+            }
+        }
+
+        result
+    }
+
+    /// Auxiliary variable constrained by `lc * inverse == 1`, which also proves `lc` nonzero
+    fn inverse(&mut self, lc: LinearCombination) -> LinearCombination {
+        let inverse = self.aux();
+        self.constrain(lc, inverse.clone(), LinearCombination::constant(Fp::ONE));
+        inverse
+    }
+
+    fn aux(&mut self) -> LinearCombination {
+        self.signals.push(Signal::Aux {
+            stmt_index: self.stmt_index,
+        });
+        LinearCombination::signal(self.signals.len() - 1)
+    }
+
+    fn wire(&mut self, wire_ref: WireRef) -> LinearCombination {
+        let index = *self.wire_indices.entry(wire_ref).or_insert_with(|| {
+            self.signals.push(Signal::Wire(wire_ref));
+            self.signals.len() - 1
+        });
+
+        LinearCombination::signal(index)
+    }
+
+    fn ident(&mut self, ident: &Ident) -> Result<LinearCombination, R1csError> {
+        if let Some(value) = constant_value(ident) {
+            return Ok(LinearCombination::constant(self.value(value)?));
+        }
+
+        match Var::from(ident) {
+            Var::Wire(wire_ref) => Ok(self.wire(wire_ref)),
+            Var::Local(name) => self
+                .locals
+                .get(&name)
+                .cloned()
+                .ok_or(R1csError::UnassignedLocal {
+                    stmt_index: self.stmt_index,
+                    name,
+                }),
+        }
+    }
+
+    fn value(&self, value: Value) -> Result<Fp, R1csError> {
+        match value {
+            Value::U64(value) | Value::RandomU64(value) | Value::Sampled { value, .. } => {
+                Ok(Fp::new(value))
+            }
+            Value::Random | Value::OmittedSample { .. } => {
+                Err(self.unsupported("an omitted random value"))
+            }
+        }
+    }
+
+    fn unsupported(&self, operation: &str) -> R1csError {
+        R1csError::Unsupported {
+            stmt_index: self.stmt_index,
+            operation: operation.to_string(),
+        }
+    }
+}
+
+impl Quadratic {
+    fn linear(linear: LinearCombination) -> Self {
+        Self {
+            product: None,
+            linear,
+        }
+    }
+
+    fn negate(self) -> Self {
+        Self {
+            product: self.product.map(|(a, b)| (a.scale(-Fp::ONE), b)),
+            linear: self.linear.scale(-Fp::ONE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{VirtualWire, Wire},
+        ir::CirBuilder,
+        test_util::binop,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_cubic() {
+        // out == x^3 + x + 5
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Local(
+                "cube".into(),
+                binop(
+                    Wire::new_private(0, 0),
+                    BinOp::Exponent,
+                    Expression::Value(Value::U64(3)),
+                ),
+            ))
+            .add_stmt(Stmt::Verify(binop(
+                VirtualWire::new_public(0),
+                BinOp::Equal,
+                binop(
+                    binop(
+                        Expression::Ident("cube".into()),
+                        BinOp::Add,
+                        Wire::new_private(0, 0),
+                    ),
+                    BinOp::Add,
+                    Expression::Value(Value::U64(5)),
+                ),
+            )))
+            .build();
+
+        let r1cs = R1cs::new(&cir).unwrap();
+
+        assert_eq!(
+            r1cs.signals,
+            [
+                Signal::One,
+                Signal::Wire(WireRef::VirtualWire { index: 0 }),
+                Signal::Wire(WireRef::Wire { row: 0, column: 0 }),
+                Signal::Aux { stmt_index: 0 },
+                Signal::Aux { stmt_index: 0 },
+            ]
+        );
+        assert_eq!((r1cs.num_public_inputs, r1cs.num_private_inputs), (1, 1));
+        assert_eq!(r1cs.constraints.len(), 3);
+
+        let x = Fp::new(3);
+        let witness = [Fp::ONE, Fp::new(35), x, x * x, x * x * x];
+        assert!(r1cs.is_satisfied(&witness));
+        assert!(!r1cs.is_satisfied(&[Fp::ONE, Fp::new(36), x, x * x, x * x * x]));
+
+        let bytes = r1cs.to_iden3_bytes().unwrap();
+        assert_eq!(&bytes[..4], b"r1cs");
+        // Header section: field size, then the prime
+        assert_eq!(bytes[28..36], GOLDILOCKS_ORDER.to_le_bytes());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 0),
+                BinOp::LessThan,
+                Expression::Value(Value::U64(3)),
+            )))
+            .build();
+
+        assert_eq!(
+            R1cs::new(&cir),
+            Err(R1csError::Unsupported {
+                stmt_index: 0,
+                operation: "`<`".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unsupported_field() {
+        let cir = CirBuilder::new()
+            .field(Field::Other)
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_private(0, 0),
+                BinOp::Equal,
+                Expression::Value(Value::U64(3)),
+            )))
+            .build();
+
+        assert_eq!(
+            R1cs::new(&cir),
+            Err(R1csError::UnsupportedField(Field::Other))
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! Delta debugging: shrinks a circuit while a predicate over it keeps holding, so a finding in a huge circuit can be
+//! reproduced with a handful of statements
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use crate::analysis::{expression_vars, stmt_def, stmt_uses, Var};
+use crate::ast::{Expression, Ident, Stmt, Value, WireRef};
+use crate::ir::{Cir, TranscriptOp};
+
+/// Smallest circuit found and how much work it took
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Reduction {
+    pub cir: Cir,
+
+    /// Number of times the predicate was called, including on the original circuit
+    pub tests: usize,
+}
+
+/// Shrinks `cir` while `interesting` holds, or `None` if it doesn't hold for `cir` itself. Every candidate is
+/// strictly smaller than the last one accepted, so this always terminates.
+///
+/// Reduction repeats these passes until none of them makes progress:
+///
+/// 1. Removes chunks of statements, halving the chunk size down to single statements
+/// 2. Replaces each subexpression with one of its operands, or with `0u64` or `1u64`
+/// 3. Drops annotations, public inputs and signature entries of wires no statement or absorb uses anymore
+pub fn reduce<F>(cir: &Cir, mut interesting: F) -> Option<Reduction>
+where
+    F: FnMut(&Cir) -> bool,
+{
+    let mut reducer = Reducer {
+        interesting: &mut interesting,
+        tests: 0,
+    };
+
+    if !reducer.test(cir) {
+        return None;
+    }
+
+    let mut cir = cir.clone();
+
+    loop {
+        let mut progress = reducer.remove_stmts(&mut cir);
+        progress |= reducer.simplify_expressions(&mut cir);
+        progress |= reducer.remove_unused_wires(&mut cir);
+
+        if !progress {
+            break;
+        }
+    }
+
+    Some(Reduction {
+        cir,
+        tests: reducer.tests,
+    })
+}
+
+struct Reducer<'a> {
+    interesting: &'a mut dyn FnMut(&Cir) -> bool,
+    tests: usize,
+}
+
+impl Reducer<'_> {
+    fn test(&mut self, cir: &Cir) -> bool {
+        self.tests += 1;
+        (self.interesting)(cir)
+    }
+
+    /// Whether any statement was removed
+    fn remove_stmts(&mut self, cir: &mut Cir) -> bool {
+        let mut progress = false;
+        let mut chunk = (cir.stmts.len() / 2).max(1);
+
+        loop {
+            let mut start = 0;
+
+            while start < cir.stmts.len() && !cir.stmts.is_empty() {
+                let end = (start + chunk).min(cir.stmts.len());
+                let candidate = without_stmts(cir, start, end);
+
+                if self.test(&candidate) {
+                    *cir = candidate;
+                    progress = true;
+                } else {
+                    start = end;
+                }
+            }
+
+            if chunk == 1 {
+                return progress;
+            }
+            chunk = (chunk / 2).max(1);
+        }
+    }
+
+    /// Whether any expression was simplified
+    fn simplify_expressions(&mut self, cir: &mut Cir) -> bool {
+        let mut progress = false;
+
+        for stmt_index in 0..cir.stmts.len() {
+            let mut node = 0;
+
+            while let Some(expr) = stmt_expression(&cir.stmts[stmt_index]).nth_node(node) {
+                let accepted = simplifications(expr).into_iter().find_map(|replacement| {
+                    let mut candidate = cir.clone();
+                    let stmt = &mut candidate.stmts[stmt_index];
+                    *stmt_expression_mut(stmt) =
+                        stmt_expression(stmt).replace_node(node, &replacement);
+
+                    self.test(&candidate).then_some(candidate)
+                });
+
+                match accepted {
+                    // The replacement may simplify further, so the same node is tried again
+                    Some(candidate) => {
+                        *cir = candidate;
+                        progress = true;
+                    }
+                    None => node += 1,
+                }
+            }
+        }
+
+        progress
+    }
+
+    /// Whether annotations, public inputs or signature entries were dropped
+    fn remove_unused_wires(&mut self, cir: &mut Cir) -> bool {
+        let mut used = BTreeSet::new();
+        for stmt in &cir.stmts {
+            used.extend(stmt_def(stmt));
+            used.extend(stmt_uses(stmt));
+        }
+        for op in &cir.transcript {
+            if let TranscriptOp::Absorb(expr) = op {
+                used.extend(expression_vars(expr));
+            }
+        }
+
+        let is_used = |wire_ref: WireRef| used.contains(&Var::Wire(wire_ref));
+
+        let mut candidate = cir.clone();
+        candidate
+            .annotations
+            .retain(|annotation| is_used(annotation.wire_ref));
+        candidate
+            .public_wire_inputs
+            .retain(|wire| is_used(wire.wire_ref()));
+        candidate
+            .public_virtual_wire_inputs
+            .retain(|virtual_wire| is_used(virtual_wire.wire_ref()));
+        candidate
+            .signature
+            .retain(|public_value| is_used(public_value.wire_ref));
+
+        if candidate == *cir || !self.test(&candidate) {
+            return false;
+        }
+
+        *cir = candidate;
+        true
+    }
+}
+
+/// `cir` without `cir.stmts[start..end]`, with gadgets shifted to match
+fn without_stmts(cir: &Cir, start: usize, end: usize) -> Cir {
+    let removed = end - start;
+    let shift = |index: usize| {
+        if index <= start {
+            index
+        } else if index <= end {
+            start
+        } else {
+            index - removed
+        }
+    };
+
+    let mut candidate = cir.clone();
+    candidate.stmts.drain(start..end);
+
+    for gadget in &mut candidate.gadgets {
+        gadget.start = shift(gadget.start);
+        gadget.end = shift(gadget.end);
+    }
+    candidate.gadgets.retain(|gadget| gadget.start < gadget.end);
+
+    candidate
+}
+
+fn stmt_expression(stmt: &Stmt) -> &Expression {
+    match stmt {
+        Stmt::Verify(expr) | Stmt::Local(_, expr) => expr,
+    }
+}
+
+fn stmt_expression_mut(stmt: &mut Stmt) -> &mut Expression {
+    match stmt {
+        Stmt::Verify(expr) | Stmt::Local(_, expr) => expr,
+    }
+}
+
+/// Smaller replacements for `expr`, most aggressive first
+fn simplifications(expr: &Expression) -> Vec<Expression> {
+    let zero = Expression::Value(Value::U64(0));
+    let one = Expression::Value(Value::U64(1));
+
+    match expr {
+        Expression::BinaryOperator { lhs, rhs, .. } => {
+            alloc::vec![zero, one, (**lhs).clone(), (**rhs).clone()]
+        }
+        Expression::Unary { expr, .. } => alloc::vec![zero, one, (**expr).clone()],
+        Expression::Ident(Ident::String(_) | Ident::Wire(_) | Ident::VirtualWire(_))
+        | Expression::Value(
+            Value::Random
+            | Value::RandomU64(_)
+            | Value::Sampled { .. }
+            | Value::OmittedSample { .. },
+        ) => alloc::vec![zero, one],
+        Expression::Value(Value::U64(value)) if *value > 1 => alloc::vec![zero, one],
+        Expression::Value(Value::U64(1)) => alloc::vec![zero],
+        Expression::Value(Value::U64(_)) => Vec::new(),
+    }
+}
+
+trait Nodes {
+    /// `n`th subexpression in pre-order, where `0` is the expression itself
+    fn nth_node(&self, n: usize) -> Option<&Expression>;
+
+    /// Copy with the `n`th subexpression in pre-order replaced
+    fn replace_node(&self, n: usize, replacement: &Expression) -> Expression;
+
+    fn node_count(&self) -> usize;
+}
+
+impl Nodes for Expression {
+    fn nth_node(&self, n: usize) -> Option<&Expression> {
+        if n == 0 {
+            return Some(self);
+        }
+
+        match self {
+            Expression::BinaryOperator { lhs, rhs, .. } => {
+                let lhs_count = lhs.node_count();
+
+                if n <= lhs_count {
+                    lhs.nth_node(n - 1)
+                } else {
+                    rhs.nth_node(n - 1 - lhs_count)
+                }
+            }
+            Expression::Unary { expr, .. } => expr.nth_node(n - 1),
+            Expression::Ident(_) | Expression::Value(_) => None,
+        }
+    }
+
+    fn replace_node(&self, n: usize, replacement: &Expression) -> Expression {
+        if n == 0 {
+            return replacement.clone();
+        }
+
+        match self {
+            Expression::BinaryOperator { lhs, binop, rhs } => {
+                let lhs_count = lhs.node_count();
+
+                let (lhs, rhs) = if n <= lhs_count {
+                    (lhs.replace_node(n - 1, replacement), (**rhs).clone())
+                } else {
+                    (
+                        (**lhs).clone(),
+                        rhs.replace_node(n - 1 - lhs_count, replacement),
+                    )
+                };
+
+                Expression::BinaryOperator {
+                    lhs: Box::new(lhs),
+                    binop: *binop,
+                    rhs: Box::new(rhs),
+                }
+            }
+            Expression::Unary { op, expr } => Expression::Unary {
+                op: op.clone(),
+                expr: Box::new(expr.replace_node(n - 1, replacement)),
+            },
+            Expression::Ident(_) | Expression::Value(_) => self.clone(),
+        }
+    }
+
+    fn node_count(&self) -> usize {
+        match self {
+            Expression::BinaryOperator { lhs, rhs, .. } => 1 + lhs.node_count() + rhs.node_count(),
+            Expression::Unary { expr, .. } => 1 + expr.node_count(),
+            Expression::Ident(_) | Expression::Value(_) => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::lint::{lint, DIVISION_BY_ZERO},
+        ast::{BinOp, Wire},
+        ir::CirBuilder,
+        test_util::{binop, test_code_ir},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_reduce() {
+        let mut builder = CirBuilder::new();
+
+        for column in 0..20 {
+            builder.add_stmt(Stmt::Verify(binop(
+                binop(
+                    Wire::new_private(0, column),
+                    BinOp::Multiply,
+                    binop(
+                        Wire::new_private(0, column),
+                        BinOp::Subtract,
+                        Expression::Value(Value::U64(1)),
+                    ),
+                ),
+                BinOp::Equal,
+                Expression::Value(Value::U64(0)),
+            )));
+        }
+
+        builder
+            .begin_gadget("ratio")
+            .add_stmt(Stmt::Local(
+                "ratio".into(),
+                binop(
+                    binop(Wire::new_private(1, 0), BinOp::Add, Wire::new_private(1, 1)),
+                    BinOp::Divide,
+                    binop(
+                        Wire::new_public(1, 2),
+                        BinOp::Multiply,
+                        Wire::new_public(1, 3),
+                    ),
+                ),
+            ))
+            .end_gadget()
+            .name_wire(0, 3, "bit")
+            .name_wire(1, 2, "denominator");
+
+        let cir = builder.build();
+        let divides_by_zero = |cir: &Cir| {
+            lint(cir)
+                .iter()
+                .any(|finding| finding.rule == DIVISION_BY_ZERO)
+        };
+
+        let reduction = reduce(&cir, divides_by_zero).unwrap();
+        assert!(reduction.tests > 0);
+
+        test_code_ir("reduce", &reduction.cir.to_code_ir());
+
+        assert!(reduce(&cir, |_| false).is_none());
+    }
+
+    #[test]
+    fn test_remove_unused_signature() {
+        let (a, b) = (
+            WireRef::Wire { row: 0, column: 0 },
+            WireRef::Wire { row: 0, column: 1 },
+        );
+
+        let cir = CirBuilder::new()
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_public(0, 0),
+                BinOp::Equal,
+                Expression::Value(Value::U64(5)),
+            )))
+            .add_stmt(Stmt::Verify(binop(
+                Wire::new_public(0, 1),
+                BinOp::Equal,
+                Expression::Value(Value::U64(7)),
+            )))
+            .public_input(a, "a")
+            .public_output(b, "b")
+            .build();
+
+        let reduction = reduce(&cir, |cir| {
+            cir.stmts
+                .iter()
+                .any(|stmt| stmt_uses(stmt).contains(&Var::Wire(a)))
+        })
+        .unwrap();
+
+        assert_eq!(reduction.cir.stmts.len(), 1);
+        assert_eq!(
+            reduction
+                .cir
+                .signature
+                .iter()
+                .map(|public_value| public_value.wire_ref)
+                .collect::<Vec<_>>(),
+            [a]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Snapshot assertions for crates that build IR, like framework forks. Enabled by the `testing` feature.
+//!
+//! Missing snapshots are written and the assertion passes. A mismatch panics with a line diff, unless the
+//! `ZKCIR_UPDATE_SNAPSHOTS` environment variable is set, in which case the snapshot is overwritten instead.
+//!
+//! ```ignore
+//! let snapshots = zkcir::testing::Snapshots::new(concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots"));
+//! snapshots.assert_builder("square_root", &builder);
+//! ```
+
+extern crate alloc;
+extern crate std;
+
+#[cfg(feature = "json")]
+use alloc::format;
+use alloc::string::String;
+use std::{
+    env, eprintln, fs,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "json")]
+use crate::ir::{Cir, CirBuilder};
+
+/// Set to overwrite snapshots that don't match instead of failing
+pub const UPDATE_ENV_VAR: &str = "ZKCIR_UPDATE_SNAPSHOTS";
+
+/// Directory of snapshot files, named after each assertion
+#[derive(Clone, Debug)]
+pub struct Snapshots {
+    dir: PathBuf,
+}
+
+impl Snapshots {
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Asserts `name.json` matches the built IR with random values omitted, and `name.ir.txt` matches the builder's
+    /// debug output
+    #[cfg(feature = "json")]
+    pub fn assert_builder(&self, name: &str, builder: &CirBuilder) {
+        self.assert_cir(name, &builder.build());
+        assert_snapshot(
+            &self.dir.join(name).with_extension("ir.txt"),
+            &format!("{builder:#?}"),
+        );
+    }
+
+    /// Asserts `name.json` matches `cir` with random values omitted, so circuits using randomness are deterministic
+    ///
+    /// # Panics
+    ///
+    /// Panics if the snapshot doesn't match, or `cir` can't be serialized
+    #[cfg(feature = "json")]
+    pub fn assert_cir(&self, name: &str, cir: &Cir) {
+        let json = cir.to_string_omit_random().expect("couldn't serialize cir");
+
+        assert_snapshot(&self.dir.join(name).with_extension("json"), &json);
+    }
+
+    /// Asserts `name.cir` matches `code_ir`, like the output of `Cir::to_code_ir`
+    pub fn assert_code_ir(&self, name: &str, code_ir: &str) {
+        assert_snapshot(&self.dir.join(name).with_extension("cir"), code_ir);
+    }
+}
+
+/// Asserts the file at `path` contains `actual`, ignoring `\r\n` vs `\n`
+///
+/// # Panics
+///
+/// Panics with a diff if the snapshot doesn't match, or if it can't be written
+pub fn assert_snapshot(path: &Path, actual: &str) {
+    // Must normalize newline characters otherwise testing on windows locally passes but fails in github actions
+    // environment
+    let actual = actual.replace("\r\n", "\n");
+
+    match fs::read_to_string(path) {
+        Ok(expected) if expected.replace("\r\n", "\n") == actual => {}
+        Ok(_) if env::var_os(UPDATE_ENV_VAR).is_some() => {
+            write_snapshot(path, &actual);
+            eprintln!("updated snapshot {}", path.display());
+        }
+        Ok(expected) => {
+            let expected: String = expected.replace("\r\n", "\n");
+            pretty_assertions::assert_str_eq!(
+                expected,
+                actual,
+                "snapshot {} doesn't match, rerun with {UPDATE_ENV_VAR}=1 to update it",
+                path.display()
+            );
+        }
+        Err(_) => write_snapshot(path, &actual),
+    }
+}
+
+fn write_snapshot(path: &Path, contents: &str) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).expect("couldn't create snapshot directory");
+    }
+
+    fs::write(path, contents).expect("couldn't write snapshot");
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use crate::ast::{Expression, Ident, Stmt, Value, Wire, Wiretype};
+
+    use super::*;
+
+    #[test]
+    fn test_omits_random() {
+        let snapshots = Snapshots::new(concat!(env!("CARGO_MANIFEST_DIR"), "/cir_test_snapshots"));
+
+        for value in [3, 5] {
+            let mut builder = CirBuilder::new();
+            builder.add_stmt(Stmt::Local(
+                Ident::String("challenge".into()),
+                Expression::Ident(Ident::Wire(Wire {
+                    row: 0,
+                    column: 0,
+                    value: Some(Value::RandomU64(value)),
+                    wiretype: Wiretype::Constant,
+                })),
+            ));
+
+            snapshots.assert_cir("testing_omits_random", &builder.build());
+        }
+    }
+}